@@ -0,0 +1,16 @@
+use sablier_merkle_api::{
+    controller::append,
+    utils::{cors, logging},
+};
+use vercel_runtime as Vercel;
+
+#[tokio::main]
+async fn main() -> Result<(), Vercel::Error> {
+    logging::init();
+    Vercel::run(Vercel::service_fn(handler)).await
+}
+
+pub async fn handler(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    let origin = cors::request_origin(&req);
+    cors::apply(origin, append::handler_to_vercel(req).await)
+}
@@ -1,11 +1,16 @@
-use sablier_merkle_api::controller::health;
+use sablier_merkle_api::{
+    controller::health,
+    utils::{cors, logging},
+};
 use vercel_runtime as Vercel;
 
 #[tokio::main]
 async fn main() -> Result<(), Vercel::Error> {
+    logging::init();
     Vercel::run(Vercel::service_fn(handler)).await
 }
 
-pub async fn handler(_req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
-    health::handler_to_vercel().await
+pub async fn handler(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    let origin = cors::request_origin(&req);
+    cors::apply(origin, health::handler_to_vercel(req).await)
 }
@@ -0,0 +1,31 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use sablier_merkle_api::utils::solana_merkle::{HashAlgorithm, LeafOrder, MerkleLeaf, MerkleTree};
+
+fn leaves(count: u32) -> Vec<MerkleLeaf> {
+    (0..count)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[..4].copy_from_slice(&i.to_le_bytes());
+            MerkleLeaf { index: i, recipient: bs58::encode(key).into_string(), amount: u64::from(i) + 1 }
+        })
+        .collect()
+}
+
+fn build_tree(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solana_build_tree");
+    group.sample_size(10);
+    for count in [10_000, 100_000] {
+        let leaves = leaves(count);
+        group.bench_function(count.to_string(), |b| {
+            b.iter_batched(
+                || leaves.clone(),
+                |leaves| MerkleTree::build_tree(leaves, LeafOrder::default(), HashAlgorithm::default()),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, build_tree);
+criterion_main!(benches);
@@ -1,6 +1,22 @@
+pub mod append;
+pub mod attestation_key;
+pub mod batch;
+pub mod campaign;
+pub mod campaign_exists;
+pub mod claim_url;
 pub mod create;
 pub mod create_solana;
+pub mod create_solana_json;
 pub mod eligibility;
 pub mod eligibility_solana;
+pub mod eligibility_solana_multi;
+pub mod export;
 pub mod health;
+pub mod openapi;
+pub mod pin_verified;
+pub mod proofs;
+pub mod root;
+pub mod sign;
+pub mod validate_solana;
+pub mod validate_stream;
 pub mod validity;
@@ -0,0 +1,270 @@
+use crate::{
+    controller::{create, create_solana},
+    csv_campaign_parser::{CampaignCsvParsed, CampaignCsvRecord, ParseOptions},
+    data_objects::{
+        dto::{CampaignUpload, PersistentCampaignDto},
+        query_param::Append,
+        response::{self, ErrorCode, GeneralErrorResponse, UploadSuccessResponse, ValidationErrorResponse},
+    },
+    services::ipfs::{download_from_ipfs, IpfsError},
+    utils::{
+        auth,
+        build_pool::BUILDS,
+        csv_validator::{AddressType, ValidationError},
+        evm_merkle::build_tree,
+        request::{self, UploadError},
+    },
+};
+
+use std::collections::HashSet;
+
+use serde_json::json;
+use vercel_runtime as Vercel;
+
+/// Append request common handler. It downloads a pinned campaign, adds the recipients of the CSV file after the
+/// existing ones and pins the result as a new campaign, built with the stored params of the old one.
+async fn handler(append: Append, options: ParseOptions, buffer: &[u8]) -> response::R {
+    if let Err(error) = request::check_csv(buffer) {
+        return response::error(400, ErrorCode::InvalidFile, error);
+    }
+    let Ok(_build) = BUILDS.acquire().await else {
+        return response::error(503, ErrorCode::ServerBusy, "Server busy, retry later");
+    };
+
+    let campaign = match download_from_ipfs::<PersistentCampaignDto>(&append.cid).await {
+        Ok(campaign) => campaign,
+        Err(IpfsError::NotFound) => {
+            return response::error(404, ErrorCode::CampaignNotFound, "The provided CID is not pinned")
+        }
+        Err(_) => {
+            return response::error(
+                500,
+                ErrorCode::IpfsDownloadFailed,
+                "There was a problem processing your request: Bad CID provided",
+            )
+        }
+    };
+
+    // Without its params the tree of the campaign cannot be rebuilt the same way
+    let Some(params) = campaign.params else {
+        return response::error(400, ErrorCode::CampaignNotAppendable, "The campaign has no stored params");
+    };
+    let address_type = match params.chain.as_str() {
+        "ethereum" => AddressType::Ethereum,
+        "solana" => AddressType::Solana,
+        chain => {
+            return response::error(400, ErrorCode::CampaignNotAppendable, format!("Unsupported chain {chain}"));
+        }
+    };
+
+    let mut records = Vec::with_capacity(campaign.recipients.len());
+    for recipient in campaign.recipients {
+        let Ok(amount) = recipient.amount.parse() else {
+            return response::error(500, ErrorCode::MalformedCampaign, "Malformed recipient amount in IPFS data");
+        };
+        records.push(CampaignCsvRecord { address: recipient.address, amount, label: recipient.label });
+    }
+
+    // The file is parsed in its own order, so the rows of the duplicate errors below match the file
+    let file_options = ParseOptions { canonical: false, min_recipients: 1, ..options.clone() };
+    let rdr = options.reader(buffer);
+    let parsed_csv = match CampaignCsvParsed::build_with_options(rdr, params.decimals, address_type, &file_options) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            return response::error(
+                500,
+                ErrorCode::CsvParsingFailed,
+                format!("There was a problem in csv file parsing process: {error}"),
+            );
+        }
+    };
+
+    let existing: HashSet<String> = records.iter().map(|record| address_type.address_key(&record.address)).collect();
+    let mut validation_errors = parsed_csv.validation_errors;
+    if validation_errors.is_empty() {
+        validation_errors = parsed_csv
+            .records
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| existing.contains(&address_type.address_key(&record.address)))
+            .map(|(i, _)| ValidationError {
+                row: i + 2,
+                message: String::from("This address is already a recipient of the campaign"),
+            })
+            .collect();
+    }
+    let total_amount =
+        records.iter().chain(&parsed_csv.records).try_fold(0u128, |total, r| total.checked_add(r.amount));
+    if validation_errors.is_empty() && total_amount.is_none() {
+        let message = String::from("Total amount exceeds representable range");
+        validation_errors.push(ValidationError { row: 1, message });
+    }
+
+    if !validation_errors.is_empty() {
+        let response_json = json!(ValidationErrorResponse {
+            status: "Invalid csv file.".to_string(),
+            code: ErrorCode::CsvValidationFailed,
+            errors: validation_errors,
+        });
+
+        return response::bad_request(response_json);
+    }
+
+    records.extend(parsed_csv.records);
+    if options.canonical {
+        records.sort_by(|a, b| a.address.cmp(&b.address));
+    }
+    let merged = CampaignCsvParsed {
+        number_of_recipients: records.len() as i32,
+        total_amount: total_amount.unwrap_or_default(),
+        records,
+        validation_errors: vec![],
+        rounded_rows: parsed_csv.rounded_rows,
+        skipped: parsed_csv.skipped,
+    };
+
+    let mut response = match address_type {
+        AddressType::Ethereum => {
+            let tree = build_tree(&merged.records);
+            create::publish(&merged, &tree, params.decimals, options.canonical, None, campaign.expires_at).await
+        }
+        AddressType::Solana => {
+            let tree = match params.tree_options() {
+                Ok(tree) => tree,
+                Err(error) => return response::error(400, ErrorCode::CampaignNotAppendable, error),
+            };
+            create_solana::publish(&merged, params.decimals, &tree, options.canonical, None, campaign.expires_at).await
+        }
+    };
+    if response.status == 200 {
+        response.message["supersedes"] = json!(append.cid);
+    }
+    response
+}
+
+/// Vercel specific handler for the append endpoint
+#[utoipa::path(
+    post,
+    path = "/api/append",
+    summary = "Pin a new campaign made of a pinned one and the recipients of a CSV file",
+    params(Append),
+    request_body(content = CampaignUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Campaign pinned, with the CID it replaces in `supersedes`", body = UploadSuccessResponse),
+        (status = 400, description = "Invalid CSV file, recipients already in the campaign, or a campaign without stored params", body = ValidationErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 404, description = "The CID is not pinned", body = GeneralErrorResponse),
+        (status = 413, description = "File larger than `MAX_UPLOAD_BYTES`", body = GeneralErrorResponse),
+        (status = 500, description = "The campaign could not be read, or IPFS upload failed", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: cid, and the CSV parse options
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    let options = match ParseOptions::from_query(&query) {
+        Ok(options) => options,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
+    };
+
+    // ------------------------------------------------------------
+    // Extract form data from the body: file
+    // ------------------------------------------------------------
+
+    let fallback = String::new();
+    let params = Append { cid: query.get("cid").unwrap_or(&fallback).clone() };
+
+    let buffer = match request::multipart_file(req).await {
+        Ok(buffer) => buffer,
+        Err(UploadError::TooLarge(limit)) => {
+            return response::to_vercel_error(413, ErrorCode::PayloadTooLarge, format!("File exceeds {limit} bytes"))
+        }
+        Err(UploadError::Invalid(error)) => return response::to_vercel_error(400, ErrorCode::InvalidFile, error),
+    };
+
+    response::to_vercel(handler(params, options, &buffer).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        controller::create_solana::campaign_dto,
+        utils::{
+            async_test::{setup_env_vars, SERVER},
+            solana_merkle::{HashAlgorithm, TreeOptions},
+        },
+    };
+
+    const PINNED_CSV: &str = "address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,200.0";
+
+    fn pinned_campaign(csv_data: &str) -> PersistentCampaignDto {
+        let parsed_csv =
+            CampaignCsvParsed::build_solana(ParseOptions::default().reader(csv_data.as_bytes()), 2).unwrap();
+        campaign_dto(&parsed_csv, 2, &TreeOptions { hash_algorithm: HashAlgorithm::Sha256, ..TreeOptions::default() })
+    }
+
+    #[tokio::test]
+    async fn handler_appends_recipients() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let download = server
+            .mock("GET", "/old_campaign_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(serde_json::to_string(&pinned_campaign(PINNED_CSV)).unwrap())
+            .create();
+        let upload = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "new_campaign_cid", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .create();
+
+        let csv_data = b"address,amount\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,300.0";
+        let append = Append { cid: "old_campaign_cid".to_string() };
+        let response = handler(append, ParseOptions::default(), csv_data).await;
+
+        // Same root as a campaign created from the three recipients at once, with the stored hash algorithm
+        let expected = pinned_campaign(&format!("{PINNED_CSV}\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,300.0"));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["cid"], "new_campaign_cid");
+        assert_eq!(response.message["supersedes"], "old_campaign_cid");
+        assert_eq!(response.message["recipients"], "3");
+        assert_eq!(response.message["root"], expected.root);
+        download.assert();
+        upload.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_rejects_existing_recipient() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let download = server
+            .mock("GET", "/appended_campaign_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(serde_json::to_string(&pinned_campaign(PINNED_CSV)).unwrap())
+            .create();
+
+        let csv_data =
+            b"address,amount\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,300.0\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,1";
+        let append = Append { cid: "appended_campaign_cid".to_string() };
+        let response = handler(append, ParseOptions::default(), csv_data).await;
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["code"], "CSV_VALIDATION_FAILED");
+        assert_eq!(response.message["errors"][0]["row"], 3);
+        assert_eq!(response.message["errors"][0]["message"], "This address is already a recipient of the campaign");
+        download.assert();
+        drop(server);
+    }
+}
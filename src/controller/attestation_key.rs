@@ -0,0 +1,37 @@
+use crate::{
+    data_objects::response::{self, AttestationKeyResponse, ErrorCode, GeneralErrorResponse},
+    utils::attestation,
+};
+use serde_json::json;
+use solana_sdk::signature::Signer;
+
+use vercel_runtime as Vercel;
+
+/// Attestation key request common handler. Publishes the Ed25519 public key that signs eligibility attestations so
+/// clients and third parties can verify them.
+pub async fn handler() -> response::R {
+    let Some(keypair) = attestation::signing_key() else {
+        return response::error(404, ErrorCode::AttestationUnavailable, "Eligibility attestations are not enabled");
+    };
+
+    response::ok(json!(AttestationKeyResponse {
+        algorithm: "ed25519".to_string(),
+        public_key: keypair.pubkey().to_string(),
+    }))
+}
+
+/// Vercel specific handler for the attestation key endpoint
+#[utoipa::path(
+    get,
+    path = "/api/attestation_key",
+    summary = "Public key that signs the eligibility attestations",
+    responses(
+        (status = 200, description = "The attestation public key", body = AttestationKeyResponse),
+        (status = 404, description = "Attestations are not enabled", body = GeneralErrorResponse),
+    ),
+)]
+pub async fn handler_to_vercel() -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    let result = handler().await;
+
+    response::to_vercel(result)
+}
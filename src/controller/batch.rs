@@ -0,0 +1,163 @@
+use crate::{
+    controller::{claim_url, eligibility, eligibility_solana, root, validity},
+    data_objects::{
+        dto::BatchOperation,
+        response::{self, BatchResult, ErrorCode, GeneralErrorResponse},
+    },
+    services::ipfs::with_download_cache,
+    utils::{
+        auth,
+        request::{self, UploadError},
+    },
+};
+
+use serde_json::{json, Value};
+use vercel_runtime as Vercel;
+
+/// Largest number of operations accepted in one batch request
+pub const MAX_BATCH_OPERATIONS: usize = 50;
+
+/// Batch request common handler. It runs the operations one after the other, downloading each campaign once, and
+/// answers with the status and body of every operation in order. A failing operation does not stop the next ones.
+async fn handler(operations: Vec<Value>) -> response::R {
+    if operations.len() > MAX_BATCH_OPERATIONS {
+        return response::error(
+            400,
+            ErrorCode::BatchTooLarge,
+            format!("A batch can have at most {MAX_BATCH_OPERATIONS} operations"),
+        );
+    }
+
+    let results = with_download_cache(async {
+        let mut results = Vec::with_capacity(operations.len());
+        for operation in operations {
+            results.push(run(operation).await);
+        }
+        results
+    })
+    .await;
+
+    response::ok(json!(results))
+}
+
+/// Runs a single operation of a batch through the handler of its endpoint
+async fn run(operation: Value) -> response::R {
+    // Creating a campaign parses a whole file and builds its tree, too heavy to share a request with other operations
+    if operation.get("op").and_then(Value::as_str).is_some_and(|op| op.starts_with("create")) {
+        return response::error(400, ErrorCode::UnsupportedOperation, "Campaign creation is not allowed in a batch");
+    }
+
+    let operation = match serde_json::from_value::<BatchOperation>(operation) {
+        Ok(operation) => operation,
+        Err(error) => {
+            return response::error(400, ErrorCode::UnsupportedOperation, format!("Invalid operation: {error}"));
+        }
+    };
+
+    match operation {
+        BatchOperation::Eligibility(params) => eligibility::handler(params).await,
+        BatchOperation::EligibilitySolana(params) => eligibility_solana::handler(params).await,
+        BatchOperation::Validity(params) => validity::handler(params).await,
+        BatchOperation::Root(params) => root::handler(params).await,
+        BatchOperation::ClaimUrl(params) => claim_url::handler(params, claim_url::base_url().as_deref()).await,
+    }
+}
+
+/// Vercel specific handler for the batch endpoint
+#[utoipa::path(
+    post,
+    path = "/api/batch",
+    summary = "Run several eligibility, validity, root or claim URL lookups in one request",
+    request_body(content = [BatchOperation], content_type = "application/json"),
+    responses(
+        (status = 200, description = "One result per operation, in request order", body = [BatchResult]),
+        (status = 400, description = "Invalid JSON body, or more than `MAX_BATCH_OPERATIONS` operations", body = GeneralErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 405, description = "Not a POST request", body = GeneralErrorResponse),
+        (status = 413, description = "Body larger than `MAX_UPLOAD_BYTES`", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    if req.method() != http::Method::POST {
+        return response::to_vercel_error(405, ErrorCode::MethodNotAllowed, "Only POST is supported on this endpoint");
+    }
+
+    // ------------------------------------------------------------
+    // Extract the JSON body: the array of operations
+    // ------------------------------------------------------------
+
+    let body = match request::body(req).await {
+        Ok(body) => body,
+        Err(UploadError::TooLarge(limit)) => {
+            return response::to_vercel_error(413, ErrorCode::PayloadTooLarge, format!("File exceeds {limit} bytes"))
+        }
+        Err(UploadError::Invalid(error)) => return response::to_vercel_error(400, ErrorCode::InvalidFile, error),
+    };
+
+    let operations: Vec<Value> = match serde_json::from_slice(&body) {
+        Ok(operations) => operations,
+        Err(error) => {
+            return response::to_vercel_error(400, ErrorCode::InvalidFile, format!("Invalid JSON body: {error}"));
+        }
+    };
+
+    response::to_vercel(handler(operations).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::async_test::{setup_env_vars, SERVER};
+
+    #[tokio::test]
+    async fn handler_downloads_shared_campaign_once() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/batch_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 1, "merkle_tree":"{\"format\":\"standard-v1\",\"tree\":[\"0x23bb7a869a407bc69b27975acff039dfe6a6abe5e3da626e98623d70137eb320\"],\"values\":[{\"value\":[\"0\",\"0x9ad7cad4f10d0c3f875b8a2fd292590490c9f491\",\"5000\"],\"tree_index\":0}],\"leaf_encoding\":[\"uint\",\"address\",\"uint256\"]}", "recipients": [{ "address": "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}]}"#)
+            .expect(1)
+            .create();
+
+        let operations = vec![
+            json!({"op": "eligibility", "cid": "batch_cid", "address": "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491"}),
+            json!({"op": "eligibility", "cid": "batch_cid", "address": "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc"}),
+        ];
+        let response = handler(operations).await;
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message[0]["status"], 200);
+        assert_eq!(response.message[0]["message"]["index"], 0);
+        assert_eq!(response.message[1]["status"], 400);
+        assert_eq!(response.message[1]["message"]["code"], "ADDRESS_NOT_ELIGIBLE");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_rejects_create_operation() {
+        let operations = vec![json!({"op": "create", "decimals": "18"}), json!({"op": "unknown"})];
+        let response = handler(operations).await;
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message[0]["message"]["code"], "UNSUPPORTED_OPERATION");
+        assert_eq!(response.message[0]["message"]["message"], "Campaign creation is not allowed in a batch");
+        assert_eq!(response.message[1]["message"]["code"], "UNSUPPORTED_OPERATION");
+    }
+
+    #[tokio::test]
+    async fn handler_rejects_oversized_batch() {
+        let operations = vec![json!({"op": "root", "cid": "cid"}); MAX_BATCH_OPERATIONS + 1];
+        let response = handler(operations).await;
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["code"], "BATCH_TOO_LARGE");
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    data_objects::{
+        query_param::Campaign,
+        response::{self, ErrorCode, GeneralErrorResponse, MessageResponse},
+    },
+    services::ipfs::{unpin, IpfsError},
+    utils::{auth, request},
+};
+
+use vercel_runtime as Vercel;
+
+/// Campaign delete request common handler. It unpins the campaign from Pinata, so test campaigns can be removed.
+pub async fn handler(campaign: Campaign) -> response::R {
+    match unpin(&campaign.cid).await {
+        Ok(()) => response::message(200, "The campaign was unpinned"),
+        Err(IpfsError::NotFound) => response::error(404, ErrorCode::CampaignNotFound, "The provided CID is not pinned"),
+        Err(IpfsError::InvalidCid) => response::error(400, ErrorCode::InvalidCid, "Bad CID provided"),
+        Err(error) => {
+            tracing::error!("{error}");
+            response::error(500, ErrorCode::IpfsUnpinFailed, "There was an error unpinning the campaign from ipfs")
+        }
+    }
+}
+
+/// Vercel specific handler for the campaign endpoint
+#[utoipa::path(
+    delete,
+    path = "/api/campaign",
+    summary = "Unpin a campaign from Pinata",
+    params(Campaign),
+    responses(
+        (status = 200, description = "The campaign was unpinned", body = MessageResponse),
+        (status = 400, description = "Bad CID provided", body = GeneralErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 404, description = "The CID is not pinned", body = GeneralErrorResponse),
+        (status = 405, description = "Not a DELETE request", body = GeneralErrorResponse),
+        (status = 500, description = "Unpinning failed", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    if req.method() != http::Method::DELETE {
+        return response::to_vercel_error(
+            405,
+            ErrorCode::MethodNotAllowed,
+            "Only DELETE is supported on this endpoint",
+        );
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: cid
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    // ------------------------------------------------------------
+    // Format arguments for the generic handler
+    // ------------------------------------------------------------
+
+    let fallback = String::new();
+    let params = Campaign { cid: query.get("cid").unwrap_or(&fallback).clone() };
+
+    response::to_vercel(handler(params).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::async_test::{setup_env_vars, SERVER};
+
+    #[tokio::test]
+    async fn handler_unpins_campaign() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("DELETE", "/pinning/unpin/pinned_cid")
+            .match_header("pinata_api_key", "mock_pinata_api_key")
+            .with_status(200)
+            .with_body("OK")
+            .create();
+
+        let response = handler(Campaign { cid: "pinned_cid".to_string() }).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message, serde_json::json!({ "message": "The campaign was unpinned" }));
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_not_pinned() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("DELETE", "/pinning/unpin/unpinned_cid")
+            .with_status(400)
+            .with_body(r#"{"error":{"reason":"CURRENT_USER_HAS_NOT_PINNED_CID","details":"unpinned_cid"}}"#)
+            .create();
+
+        let response = handler(Campaign { cid: "unpinned_cid".to_string() }).await;
+        assert_eq!(response.status, 404);
+        assert_eq!(response.message["message"], "The provided CID is not pinned");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_pinata_error() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("DELETE", "/pinning/unpin/failing_cid")
+            .with_status(500)
+            .with_body(r#"{"error":"Internal server error"}"#)
+            .create();
+
+        let response = handler(Campaign { cid: "failing_cid".to_string() }).await;
+        assert_eq!(response.status, 500);
+        mock.assert();
+        drop(server);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    data_objects::{
+        dto::PersistentCampaignDto,
+        query_param::CampaignExists,
+        response::{self, CampaignExistsResponse, ErrorCode, GeneralErrorResponse},
+    },
+    services::ipfs::{download_raw_from_ipfs, IpfsError},
+    utils::{auth, request},
+};
+
+use serde_json::json;
+
+use vercel_runtime as Vercel;
+
+/// Campaign exists request common handler. It tells a CID that is not pinned apart from one that is pinned but does
+/// not hold a well-formed campaign, without building or checking its tree.
+pub async fn handler(campaign_exists: CampaignExists) -> response::R {
+    let bytes = match download_raw_from_ipfs(&campaign_exists.cid).await {
+        Ok(bytes) => bytes,
+        // Not cached: the CID may be pinned later
+        Err(IpfsError::NotFound) => {
+            return response::ok(json!(&CampaignExistsResponse { exists: false, valid_shape: None }))
+        }
+        Err(_) => {
+            return response::error(
+                500,
+                ErrorCode::IpfsDownloadFailed,
+                "There was a problem processing your request: Bad CID provided",
+            )
+        }
+    };
+
+    let valid_shape = serde_json::from_slice::<PersistentCampaignDto>(&bytes)
+        .is_ok_and(|campaign| !campaign.root.is_empty() && !campaign.recipients.is_empty());
+
+    response::ok_immutable(json!(&CampaignExistsResponse { exists: true, valid_shape: Some(valid_shape) }))
+}
+
+/// Vercel specific handler for the campaign exists endpoint
+#[utoipa::path(
+    get,
+    path = "/api/campaign_exists",
+    summary = "Whether a CID is pinned and holds a well-formed campaign",
+    params(CampaignExists),
+    responses(
+        (status = 200, description = "Whether the CID is pinned, and if so whether it has the shape of a campaign", body = CampaignExistsResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 500, description = "Bad CID, or the gateway could not be reached", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: cid
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    // ------------------------------------------------------------
+    // Format arguments for the generic handler
+    // ------------------------------------------------------------
+
+    let fallback = String::new();
+    let params = CampaignExists { cid: query.get("cid").unwrap_or(&fallback).clone() };
+
+    response::to_vercel(handler(params).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::async_test::{setup_env_vars, SERVER};
+
+    #[tokio::test]
+    async fn handler_valid_campaign() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/exists_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "0xstored_root", "total_amount": "10", "number_of_recipients": 1, "merkle_tree": "tree", "recipients": [{ "address": "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}]}"#)
+            .create();
+
+        let response = handler(CampaignExists { cid: "exists_cid".to_string() }).await;
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message, json!({ "exists": true, "valid_shape": true }));
+        assert!(response.cache_control.is_some());
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_malformed_campaign() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/malformed_exists_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "0xstored_root", "recipients": "#)
+            .create();
+
+        let response = handler(CampaignExists { cid: "malformed_exists_cid".to_string() }).await;
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message, json!({ "exists": true, "valid_shape": false }));
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_not_pinned() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/missing_exists_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(404)
+            .create();
+
+        let response = handler(CampaignExists { cid: "missing_exists_cid".to_string() }).await;
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message, json!({ "exists": false }));
+        assert!(response.cache_control.is_none());
+        mock.assert();
+        drop(server);
+    }
+}
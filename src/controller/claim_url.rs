@@ -0,0 +1,145 @@
+use crate::{
+    controller::{eligibility, eligibility_solana},
+    data_objects::{
+        query_param::{ClaimUrl, Eligibility},
+        response::{self, ClaimUrlResponse, ErrorCode, GeneralErrorResponse},
+    },
+    utils::{auth, request},
+};
+use serde_json::json;
+use url::form_urlencoded;
+
+use vercel_runtime as Vercel;
+
+/// Claim URL request common handler. It resolves the recipient's eligibility and packs the claim parameters (`cid`,
+/// `index`, `amount` and the comma-separated `proof`) into a URL-encoded query string. With a claim page `base` URL the
+/// query is also appended to it to form the full claim link.
+pub async fn handler(claim_url: ClaimUrl, base: Option<&str>) -> response::R {
+    let eligibility = Eligibility { cid: claim_url.cid.clone(), address: claim_url.address, attest: false };
+    let result = match claim_url.chain.as_str() {
+        "solana" => eligibility_solana::handler(eligibility).await,
+        "ethereum" => eligibility::handler(eligibility).await,
+        _ => {
+            return response::error(
+                400,
+                ErrorCode::InvalidQuery,
+                "The chain query parameter should be `ethereum` or `solana`",
+            )
+        }
+    };
+
+    if result.status != 200 {
+        return result;
+    }
+
+    let proof: Vec<&str> =
+        result.message["proof"].as_array().into_iter().flatten().filter_map(|p| p.as_str()).collect();
+    let query = form_urlencoded::Serializer::new(String::new())
+        .append_pair("cid", &claim_url.cid)
+        .append_pair("index", &result.message["index"].to_string())
+        .append_pair("amount", result.message["amount"].as_str().unwrap_or_default())
+        .append_pair("proof", &proof.join(","))
+        .finish();
+
+    let url = base.map(|base| {
+        let separator = if base.contains('?') { '&' } else { '?' };
+        format!("{base}{separator}{query}")
+    });
+
+    response::ok(json!(ClaimUrlResponse { query, url }))
+}
+
+/// Load the claim page URL from the `CLAIM_URL_BASE` env var. Returns `None` when it is missing or empty.
+pub fn base_url() -> Option<String> {
+    std::env::var("CLAIM_URL_BASE").ok().filter(|base| !base.is_empty())
+}
+
+/// Vercel specific handler for the claim url endpoint
+#[utoipa::path(
+    get,
+    path = "/api/claim_url",
+    summary = "URL-encoded claim parameters of a recipient, and the claim link when `CLAIM_URL_BASE` is set",
+    params(ClaimUrl),
+    responses(
+        (status = 200, description = "The claim parameters", body = ClaimUrlResponse),
+        (status = 400, description = "Unknown chain, or an address that is not eligible", body = GeneralErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 404, description = "The CID is not pinned", body = GeneralErrorResponse),
+        (status = 500, description = "The campaign could not be read", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: address, cid, chain
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    // ------------------------------------------------------------
+    // Format arguments for the generic handler
+    // ------------------------------------------------------------
+
+    let fallback = String::new();
+    let params = ClaimUrl {
+        address: query.get("address").unwrap_or(&fallback).clone(),
+        cid: query.get("cid").unwrap_or(&fallback).clone(),
+        chain: query.get("chain").cloned().unwrap_or_else(|| "ethereum".to_string()),
+    };
+
+    response::to_vercel(handler(params, base_url().as_deref()).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::async_test::{setup_env_vars, SERVER};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn claim_url_decodes_to_claim_parameters() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/claim_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 4, "merkle_tree": "{\"root\":\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\",\"tree\":[[\"410c2c7cb39bf8cc15b1e22fc5b9c26be08465174ccef0be090d9d9df86d03ad\",\"1f605d6b20676921f61532c385082aae4619ba91dfb83c71bf1bc43678626119\",\"158db4f6ff3d0547cef89e6125c2c39052d1b2b288a8db9742958cc1b80fcb43\",\"77a70b41a193dc0a1e9a07dca4a3f2fb40c37282a6d18849dd7af36b684590ca\"],[\"a6a693f5474548569bfd931d4af466a50a5eb0374d895071f647393ff6da241b\",\"35b4f28cb601668e6f89cb6eace3a2d845e700f718182bdf237f17811ead81a6\"],[\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\"]]}", "recipients": [{ "address": "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y", "amount": "10"}, { "address": "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E", "amount": "10"}, { "address": "4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi", "amount": "10"}, { "address": "8qbHbw2BbbTHBW1sbeqakYXVKRQM8Ne7pLK7m6CVfeR", "amount": "10"}]}"#)
+            .create();
+
+        let params = ClaimUrl {
+            cid: "claim_cid".to_string(),
+            address: "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y".to_string(),
+            chain: "solana".to_string(),
+        };
+        let response = handler(params, Some("https://app.sablier.com/airdrops/claim")).await;
+        assert_eq!(response.status, 200);
+
+        let url = url::Url::parse(response.message["url"].as_str().unwrap()).unwrap();
+        assert_eq!(url.path(), "/airdrops/claim");
+        let decoded: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(decoded["cid"], "claim_cid");
+        assert_eq!(decoded["index"], "0");
+        assert_eq!(decoded["amount"], "10");
+        assert_eq!(
+            decoded["proof"],
+            "0x1f605d6b20676921f61532c385082aae4619ba91dfb83c71bf1bc43678626119,\
+             0x35b4f28cb601668e6f89cb6eace3a2d845e700f718182bdf237f17811ead81a6"
+        );
+        assert_eq!(response.message["query"], url.query().unwrap());
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn claim_url_rejects_unknown_chain() {
+        let params =
+            ClaimUrl { cid: "claim_cid".to_string(), address: "address".to_string(), chain: "bitcoin".to_string() };
+        let response = handler(params, None).await;
+        assert_eq!(response.status, 400);
+    }
+}
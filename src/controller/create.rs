@@ -1,76 +1,146 @@
 use crate::{
-    csv_campaign_parser::CampaignCsvParsed,
+    csv_campaign_parser::{CampaignCsvParsed, ParseOptions},
     data_objects::{
-        dto::{PersistentCampaignDto, RecipientDto},
-        response::{self, UploadSuccessResponse, ValidationErrorResponse},
+        dto::{
+            CampaignMetadataDto, CampaignParamsDto, CampaignUpload, PersistentCampaignDto, RecipientDto,
+            EVM_MERKLE_VERSION,
+        },
+        query_param::{Create, MetadataPin},
+        response::{self, ErrorCode, GeneralErrorResponse, UploadSuccessResponse, ValidationErrorResponse},
+    },
+    services::{
+        ipfs::{pin_everywhere, pin_json, pinners, verify_pin},
+        token,
+    },
+    utils::{
+        auth,
+        build_pool::BUILDS,
+        csv_validator::AddressType,
+        evm_merkle::build_tree,
+        request::{self, UploadError},
     },
-    services::ipfs::{try_deserialize_pinata_response, upload_to_ipfs},
-    utils::{auth, request},
 };
 
-use csv::ReaderBuilder;
-use http_body_util::BodyExt;
 use merkle_tree_rs::standard::StandardMerkleTree;
-use std::io::Read;
+use std::collections::HashMap;
 
 use serde_json::json;
 use vercel_runtime as Vercel;
 
+/// Read `decimals` from the request query parameters, `None` when it is missing so it can be looked up from the token
+/// instead. A given one must be an integer, its range is checked by the handler.
+pub fn decimals(query: &HashMap<String, String>) -> Result<Option<u16>, String> {
+    query.get("decimals").map(|decimals| decimals.parse()).transpose().map_err(|_| {
+        "Decimals query parameter should be a valid integer in order to create a valid campaign!".to_string()
+    })
+}
+
 /// Create request common handler. It validates the received data, creates the merkle tree and uploads it to ipfs.
-async fn handler(decimals: usize, buffer: &[u8]) -> response::R {
-    let rdr = ReaderBuilder::new().from_reader(buffer);
-    let parsed_csv = match CampaignCsvParsed::build_ethereum(rdr, decimals) {
+async fn handler(
+    decimals: usize,
+    options: ParseOptions,
+    metadata: Option<MetadataPin>,
+    expires_at: Option<i64>,
+    buffer: &[u8],
+) -> response::R {
+    if let Err(error) = AddressType::Ethereum.check_decimals(decimals) {
+        return response::error(400, ErrorCode::InvalidDecimals, error);
+    }
+    if let Err(error) = request::check_csv(buffer) {
+        return response::error(400, ErrorCode::InvalidFile, error);
+    }
+    let Ok(_build) = BUILDS.acquire().await else {
+        return response::error(503, ErrorCode::ServerBusy, "Server busy, retry later");
+    };
+
+    let rdr = options.reader(buffer);
+    let parsed_csv = match CampaignCsvParsed::build_with_options(rdr, decimals, AddressType::Ethereum, &options) {
         Ok(parsed) => parsed,
         Err(error) => {
-            return response::message(500, format!("There was a problem in csv file parsing process: {error}"));
+            return response::error(
+                500,
+                ErrorCode::CsvParsingFailed,
+                format!("There was a problem in csv file parsing process: {error}"),
+            );
         }
     };
 
     if !parsed_csv.validation_errors.is_empty() {
         let response_json = json!(ValidationErrorResponse {
             status: "Invalid csv file.".to_string(),
+            code: ErrorCode::CsvValidationFailed,
             errors: parsed_csv.validation_errors,
         });
 
         return response::bad_request(response_json);
     }
 
-    let leaves = parsed_csv
-        .records
-        .iter()
-        .enumerate()
-        .map(|(i, r)| vec![i.to_string(), r.address.clone(), r.amount.to_string()])
-        .collect();
-
-    let tree = StandardMerkleTree::of(leaves, &["uint".to_string(), "address".to_string(), "uint256".to_string()]);
+    publish(&parsed_csv, &build_tree(&parsed_csv.records), decimals, options.canonical, metadata, expires_at).await
+}
 
+/// Pin validated recipients and their merkle tree, built by `build_tree`, to ipfs along with the optional metadata and
+/// answer with the campaign root and CID
+pub async fn publish(
+    parsed_csv: &CampaignCsvParsed,
+    tree: &StandardMerkleTree,
+    decimals: usize,
+    canonical: bool,
+    metadata: Option<MetadataPin>,
+    expires_at: Option<i64>,
+) -> response::R {
     let tree_json = serde_json::to_string(&tree.dump()).unwrap();
 
     let dto = PersistentCampaignDto {
         total_amount: parsed_csv.total_amount.to_string(),
         number_of_recipients: parsed_csv.number_of_recipients,
         merkle_tree: tree_json,
+        merkle_version: Some(EVM_MERKLE_VERSION.to_string()),
         root: tree.root(),
         recipients: parsed_csv
             .records
             .iter()
-            .map(|x| RecipientDto { address: x.address.clone(), amount: x.amount.to_string() })
+            .map(|x| RecipientDto { address: x.address.clone(), amount: x.amount.to_string(), label: x.label.clone() })
             .collect(),
+        params: Some(CampaignParamsDto::ethereum(decimals)),
+        expires_at,
     };
 
-    let ipfs_response = match upload_to_ipfs(&dto).await {
-        Ok(response) => response,
+    let pins = match pin_everywhere(&pinners(), &dto).await {
+        Ok(pins) => pins,
         Err(error) => {
-            println!("Error: {error}");
-            return response::message(500, "There was an error uploading the campaign to ipfs");
+            tracing::error!("{error}");
+            return response::error_with_retry_after(
+                500,
+                ErrorCode::IpfsUploadFailed,
+                "There was an error uploading the campaign to ipfs",
+                error.retry_after(),
+            );
         }
     };
 
-    let deserialized_response = match try_deserialize_pinata_response(&ipfs_response) {
-        Ok(response) => response,
-        Err(error) => {
-            println!("Error: {error}");
-            return response::message(500, "There was an error uploading the campaign to ipfs");
+    let metadata_cid = match metadata {
+        None => None,
+        Some(metadata) => {
+            let metadata = CampaignMetadataDto {
+                root: tree.root(),
+                total_amount: parsed_csv.total_amount.to_string(),
+                number_of_recipients: parsed_csv.number_of_recipients,
+                chain: "ethereum".to_string(),
+                token: metadata.token,
+                created_at: chrono::Utc::now().to_rfc3339(),
+            };
+            match pin_json(&metadata).await {
+                Ok(cid) => Some(cid),
+                Err(error) => {
+                    tracing::error!("{error}");
+                    return response::error_with_retry_after(
+                        500,
+                        ErrorCode::IpfsUploadFailed,
+                        "There was an error uploading the campaign metadata to ipfs",
+                        error.retry_after(),
+                    );
+                }
+            }
         }
     };
 
@@ -79,85 +149,213 @@ async fn handler(decimals: usize, buffer: &[u8]) -> response::R {
         total: parsed_csv.total_amount.to_string(),
         recipients: parsed_csv.number_of_recipients.to_string(),
         root: tree.root(),
-        cid: deserialized_response.ipfs_hash,
+        cid: pins.first().map(|pinned| pinned.cid.clone()).unwrap_or_default(),
+        cids: pins.iter().map(|pinned| pinned.cid.clone()).collect(),
+        pin_size: pins.iter().find_map(|pinned| pinned.size),
+        canonical,
+        tree_depth: UploadSuccessResponse::tree_depth(parsed_csv.records.len()),
+        rounded_rows: parsed_csv.rounded_rows,
+        skipped: parsed_csv.skipped.clone(),
+        metadata_cid,
+        params: dto.params,
+        verified: None,
     });
 
     response::ok(response_json)
 }
 
+/// Read a freshly pinned campaign back from IPFS with `verify_pin` and report in `verified` whether it is intact. A
+/// campaign that cannot be downloaded yet counts as not verified.
+pub async fn verify_upload(mut response: response::R) -> response::R {
+    if response.status != 200 {
+        return response;
+    }
+    let cid = response.message["cid"].as_str().unwrap_or_default().to_string();
+    let root = response.message["root"].as_str().unwrap_or_default().to_string();
+
+    let verified = match verify_pin(&cid, &root).await {
+        Ok(verified) => verified,
+        Err(error) => {
+            tracing::error!("{error}");
+            false
+        }
+    };
+    response.message["verified"] = json!(verified);
+    response
+}
+
 /// Vercel specific handler for the create endpoint
+#[utoipa::path(
+    post,
+    path = "/api/create",
+    summary = "Build the EVM merkle tree of a CSV campaign and pin it to IPFS",
+    params(Create),
+    request_body(content = CampaignUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Campaign pinned", body = UploadSuccessResponse),
+        (status = 400, description = "Invalid or missing decimals, invalid CSV file or unresolvable token decimals", body = ValidationErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 500, description = "Parsing or IPFS upload failed", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
 pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
     if !auth::is_authorized(&req) {
-        return response::to_vercel_message(401, "Bad authentication process provided.");
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
     }
 
     // ------------------------------------------------------------
-    // Extract query parameters from the URL: decimals
-    //
-    // NOTE: the missing/malformed-input branches below intentionally return status 200
-    // to preserve legacy client behavior. Review candidate.
+    // Extract query parameters from the URL: decimals, or token_address and chain_id to look them up
     // ------------------------------------------------------------
 
     let query = request::query_params(&req);
-    let Some(decimals) = query.get("decimals").cloned() else {
-        return response::to_vercel_message(
-            200,
-            "Decimals query parameter is mandatory in order to create a valid campaign!",
-        );
+    let decimals = match (decimals(&query), query.get("token_address"), query.get("chain_id")) {
+        (Err(error), _, _) => return response::to_vercel_error(400, ErrorCode::InvalidDecimals, error),
+        (Ok(Some(decimals)), _, _) => decimals.into(),
+        (Ok(None), Some(token_address), Some(chain_id)) => {
+            let Ok(chain_id) = chain_id.parse::<u64>() else {
+                return response::to_vercel_error(
+                    400,
+                    ErrorCode::TokenDecimalsUnresolved,
+                    "Could not resolve token decimals",
+                );
+            };
+            match token::resolve_decimals(chain_id, token_address).await {
+                Ok(decimals) => decimals,
+                Err(error) => {
+                    tracing::error!("{error}");
+                    return response::to_vercel_error(
+                        400,
+                        ErrorCode::TokenDecimalsUnresolved,
+                        "Could not resolve token decimals",
+                    );
+                }
+            }
+        }
+        (Ok(None), _, _) => {
+            return response::to_vercel_error(
+                400,
+                ErrorCode::InvalidDecimals,
+                "Decimals query parameter is mandatory in order to create a valid campaign!",
+            );
+        }
     };
 
-    // ------------------------------------------------------------
-    // Extract form data from the body: file
-    // ------------------------------------------------------------
-
-    let Some(boundary) = req
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.strip_prefix("multipart/form-data; boundary="))
-        .map(String::from)
-    else {
-        return response::to_vercel_message(200, "Invalid content type header");
+    let expires_at = match request::expires_at(&query) {
+        Ok(expires_at) => expires_at,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
     };
 
-    let body = match req.into_body().collect().await {
-        Ok(collected) => collected.to_bytes().to_vec(),
-        Err(error) => return response::to_vercel_message(200, format!("Could not read body data {error}")),
+    let options = match ParseOptions::from_query(&query) {
+        Ok(options) => options,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
     };
 
-    let mut data = multipart::server::Multipart::with_body(body.as_slice(), boundary);
-    let file = match data.read_entry() {
-        Ok(file) => file,
-        Err(error) => return response::to_vercel_message(200, error.to_string()),
-    };
+    let metadata =
+        query.get("metadata").is_some_and(|v| v == "true").then(|| MetadataPin { token: query.get("token").cloned() });
 
-    let Some(mut file) = file else {
-        return response::to_vercel_message(200, "Invalid form data, missing file");
-    };
-    let mut buffer: Vec<u8> = vec![];
+    // ------------------------------------------------------------
+    // Extract form data from the body: file
+    //
+    // NOTE: the malformed-body branch below intentionally returns status 200
+    // to preserve legacy client behavior. Review candidate.
+    // ------------------------------------------------------------
 
-    if let Err(error) = file.data.read_to_end(&mut buffer) {
-        return response::to_vercel_message(200, format!("Could not read body data {error}"));
-    }
+    let buffer = match request::multipart_file(req).await {
+        Ok(buffer) => buffer,
+        Err(UploadError::TooLarge(limit)) => {
+            return response::to_vercel_error(413, ErrorCode::PayloadTooLarge, format!("File exceeds {limit} bytes"))
+        }
+        Err(UploadError::Invalid(error)) => return response::to_vercel_error(200, ErrorCode::InvalidFile, error),
+    };
 
     // ------------------------------------------------------------
     // Format arguments for the generic handler
     // ------------------------------------------------------------
 
-    let Ok(decimals) = decimals.parse::<u16>() else {
-        return response::to_vercel_message(
-            200,
-            "Decimals query parameter is mandatory and should be a valid integer in order to create a valid campaign!",
-        );
-    };
-
-    response::to_vercel(handler(decimals.into(), &buffer).await)
+    let response = handler(decimals, options, metadata, expires_at, &buffer).await;
+    if query.get("verify").is_some_and(|v| v == "true") {
+        return response::to_vercel(verify_upload(response).await);
+    }
+    response::to_vercel(response)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::async_test::{setup_env_vars, SERVER};
+    use crate::{
+        csv_campaign_parser::CampaignCsvRecord,
+        services::ipfs::{with_store, InMemory},
+        utils::async_test::{setup_env_vars, SERVER},
+    };
+    use csv::ReaderBuilder;
+    use merkle_tree_rs::standard::LeafType;
+    use mockito::Matcher;
+    use sha3::{Digest, Keccak256};
+
+    fn keccak(data: &[u8]) -> [u8; 32] {
+        Keccak256::digest(data).into()
+    }
+
+    /// `keccak256(keccak256(abi.encode(index, account, amount)))`, as in OpenZeppelin's `StandardMerkleTree`
+    fn oz_leaf(index: u8, account: &str, amount: u128) -> [u8; 32] {
+        let mut encoded = [0u8; 96];
+        encoded[31] = index;
+        encoded[44..64].copy_from_slice(&hex::decode(account.trim_start_matches("0x")).unwrap());
+        encoded[80..96].copy_from_slice(&amount.to_be_bytes());
+        keccak(&keccak(&encoded))
+    }
+
+    #[test]
+    fn test_tree_matches_openzeppelin_standard_merkle_tree() {
+        let records = vec![
+            CampaignCsvRecord {
+                address: "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+                amount: 10000,
+                label: None,
+            },
+            CampaignCsvRecord {
+                address: "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc".to_string(),
+                amount: 20000,
+                label: None,
+            },
+        ];
+        let tree = build_tree(&records);
+
+        let mut leaves = [oz_leaf(0, &records[0].address, 10000), oz_leaf(1, &records[1].address, 20000)];
+        leaves.sort();
+        let expected_root = format!("0x{}", hex::encode(keccak(&leaves.concat())));
+
+        assert_eq!(tree.root(), expected_root);
+        assert_eq!(tree.root(), "0x9aa5d0eb7a1350d03d053f55c4e2f31d07a7bc80c5ab23e4036e81270facfd18");
+    }
+
+    #[test]
+    fn test_labels_do_not_change_root() {
+        let plain = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let labelled = "address,label,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,team,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc, investor ,200.0";
+        let parse = |csv: &str| CampaignCsvParsed::build_ethereum(ReaderBuilder::new().from_reader(csv.as_bytes()), 2);
+
+        let plain = parse(plain).unwrap();
+        let labelled = parse(labelled).unwrap();
+        assert!(labelled.validation_errors.is_empty());
+        assert_eq!(labelled.records[0].label.as_deref(), Some("team"));
+        assert_eq!(labelled.records[1].label.as_deref(), Some("investor"));
+        assert_eq!(plain.records[0].label, None);
+        assert_eq!(build_tree(&labelled.records).root(), build_tree(&plain.records).root());
+    }
+
+    #[test]
+    fn test_tree_depth_is_longest_proof() {
+        let records: Vec<CampaignCsvRecord> = (1..=5u8)
+            .map(|i| CampaignCsvRecord { address: format!("0x{}", hex::encode([i; 20])), amount: 100, label: None })
+            .collect();
+        let tree = build_tree(&records);
+
+        let longest = (0..records.len()).map(|i| tree.get_proof(LeafType::Number(i)).len()).max();
+        assert_eq!(longest, Some(3));
+        assert_eq!(UploadSuccessResponse::tree_depth(records.len()), 3);
+    }
 
     #[tokio::test]
     async fn test_valid_csv_upload() {
@@ -170,21 +368,286 @@ mod tests {
             .create();
 
         let csv_data = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 200);
+        assert_eq!(response.message["recipients"], "2");
+        assert_eq!(response.message["tree_depth"], 1);
+        assert_eq!(response.message["pin_size"], 123);
         mock.assert();
         drop(server);
     }
 
+    #[tokio::test]
+    async fn test_verified_upload() {
+        let csv_data = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let response = with_store(InMemory::default(), async {
+            verify_upload(handler(2, ParseOptions::default(), None, None, csv_data).await).await
+        })
+        .await;
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["verified"], true);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_upload() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(429)
+            .with_header("Retry-After", "60")
+            .with_body(r#"{"error": "Too many requests"}"#)
+            .create();
+
+        let csv_data = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
+
+        assert_eq!(response.status, 500);
+        assert_eq!(response.message["code"], "IPFS_UPLOAD_FAILED");
+        assert_eq!(response.message["retry_after_seconds"], 60);
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_skip_invalid_rows() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "test_hash", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .create();
+
+        let csv_data = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xThisIsNotAnAddress,50\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,0";
+        let skip_invalid = ParseOptions { skip_invalid: true, ..ParseOptions::default() };
+        let response = handler(2, skip_invalid, None, None, csv_data).await;
+
+        // The tree is the one of the two valid rows alone
+        let valid = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let parsed = CampaignCsvParsed::build_ethereum(ReaderBuilder::new().from_reader(&valid[..]), 2).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["cid"], "test_hash");
+        assert_eq!(response.message["recipients"], "2");
+        assert_eq!(response.message["root"], build_tree(&parsed.records).root());
+
+        let skipped = response.message["skipped"].as_array().unwrap();
+        let rows: Vec<u64> = skipped.iter().map(|error| error["row"].as_u64().unwrap()).collect();
+        assert_eq!(rows, [3, 5, 5]);
+        assert_eq!(skipped[0]["message"], "Invalid Ethereum address");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_comma_decimal_separator() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "test_hash", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .expect(2)
+            .create();
+        let options = |delimiter: u8| ParseOptions { delimiter, decimal_separator: ',', ..ParseOptions::default() };
+
+        // Semicolon and tab separated files, as spreadsheets with European locale settings export them
+        let semicolon = b"address;amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491;100,25\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc;1,5";
+        let tab = b"address\tamount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491\t\"100,25\"\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc\t1,5";
+        let semicolon = handler(2, options(b';'), None, None, semicolon).await;
+        let tab = handler(2, options(b'\t'), None, None, tab).await;
+
+        let dot = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.25\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,1.5";
+        let parsed = CampaignCsvParsed::build_ethereum(ReaderBuilder::new().from_reader(&dot[..]), 2).unwrap();
+        for response in [semicolon, tab] {
+            assert_eq!(response.status, 200);
+            assert_eq!(response.message["total"], "10175");
+            assert_eq!(response.message["root"], build_tree(&parsed.records).root());
+        }
+        mock.assert();
+        drop(server);
+    }
+
+    #[test]
+    fn test_decimal_separator_matches_solana() {
+        let amounts = ["100,25", "0,5", "7"];
+        let options = ParseOptions { delimiter: b';', decimal_separator: ',', ..ParseOptions::default() };
+        let parse = |address_type: AddressType, addresses: [&str; 3]| {
+            let rows: Vec<String> = addresses.iter().zip(amounts).map(|(a, amount)| format!("{a};{amount}")).collect();
+            let csv_data = format!("address;amount\n{}", rows.join("\n"));
+            let parsed =
+                CampaignCsvParsed::build_with_options(options.reader(csv_data.as_bytes()), 2, address_type, &options);
+            parsed.unwrap().records.iter().map(|record| record.amount).collect::<Vec<u128>>()
+        };
+
+        let evm = parse(
+            AddressType::Ethereum,
+            [
+                "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491",
+                "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc",
+                "0x0x8ad7CAD4F10D0c3f875b8a2fd292590490c9f491",
+            ],
+        );
+        let solana = parse(
+            AddressType::Solana,
+            [
+                "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y",
+                "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E",
+                "AG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE",
+            ],
+        );
+        assert_eq!(evm, [10025, 50, 700]);
+        assert_eq!(evm, solana);
+    }
+
+    #[tokio::test]
+    async fn test_decimals_range() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "test_hash", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .create();
+
+        let csv_data = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200";
+        let response = handler(0, ParseOptions::default(), None, None, csv_data).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["total"], "300");
+
+        let response = handler(19, ParseOptions::default(), None, None, csv_data).await;
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["message"], "decimals must be between 0 and 18");
+        assert_eq!(response.message["code"], "INVALID_DECIMALS");
+
+        mock.assert();
+        drop(server);
+    }
+
+    #[test]
+    fn test_decimals_query() {
+        let query = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+
+        assert_eq!(decimals(&query(&[("decimals", "0")])), Ok(Some(0)));
+        assert_eq!(decimals(&query(&[("token_address", "0x0")])), Ok(None));
+        assert_eq!(
+            decimals(&query(&[("decimals", "six")])).unwrap_err(),
+            "Decimals query parameter should be a valid integer in order to create a valid campaign!"
+        );
+        assert!(decimals(&query(&[("decimals", "-1")])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_canonical_root_ignores_file_order() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "test_hash", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .expect(4)
+            .create();
+
+        let csv_data = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let shuffled = b"address,amount\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0";
+        let canonical = || ParseOptions { canonical: true, ..ParseOptions::default() };
+
+        let first = handler(2, canonical(), None, None, csv_data).await;
+        let second = handler(2, canonical(), None, None, shuffled).await;
+        assert_eq!(first.status, 200);
+        assert_eq!(first.message["root"], second.message["root"]);
+        assert_eq!(first.message["canonical"], true);
+
+        let first = handler(2, ParseOptions::default(), None, None, csv_data).await;
+        let second = handler(2, ParseOptions::default(), None, None, shuffled).await;
+        assert_ne!(first.message["root"], second.message["root"]);
+        assert_eq!(first.message["canonical"], false);
+
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_pin() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let data_mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .match_body(Matcher::Regex("merkle_tree".to_string()))
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "data_hash", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .create();
+        let metadata_mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::Regex(r#""chain":"ethereum""#.to_string()),
+                Matcher::Regex(r#""number_of_recipients":2"#.to_string()),
+                Matcher::Regex(r#""total_amount":"30000""#.to_string()),
+                Matcher::Regex(r#""token":"0xtoken""#.to_string()),
+                Matcher::Regex(r#""created_at":"#.to_string()),
+                Matcher::Regex(r#""root":"0x"#.to_string()),
+            ]))
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "metadata_hash", "PinSize": 12, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .create();
+
+        let csv_data = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let metadata = MetadataPin { token: Some("0xtoken".to_string()) };
+        let response = handler(2, ParseOptions::default(), Some(metadata), None, csv_data).await;
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["cid"], "data_hash");
+        assert_eq!(response.message["metadata_cid"], "metadata_hash");
+        data_mock.assert();
+        metadata_mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_expires_at_is_pinned() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .match_body(Matcher::Regex(r#""expires_at":1700000000"#.to_string()))
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "test_hash", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .create();
+
+        let csv_data = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let response = handler(2, ParseOptions::default(), None, Some(1_700_000_000), csv_data).await;
+
+        assert_eq!(response.status, 200);
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_csv_with_too_many_rows() {
+        let mut csv_data = String::from("address,amount\n");
+        for i in 0..100_000 {
+            csv_data.push_str(&format!("0x{i:040x},1.0\n"));
+        }
+        let options = ParseOptions { max_recipients: 3, ..ParseOptions::default() };
+        let response = handler(2, options, None, None, csv_data.as_bytes()).await;
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["code"], "CSV_VALIDATION_FAILED");
+        assert_eq!(response.message["errors"][0]["row"], 5);
+        assert_eq!(response.message["errors"][0]["message"], "Campaign exceeds maximum of 3 recipients");
+    }
+
     #[tokio::test]
     async fn test_csv_with_wrong_header() {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data =b"address,amount_invalid\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
+        assert_eq!(response.message["code"], "CSV_VALIDATION_FAILED");
         drop(server);
     }
 
@@ -195,7 +658,7 @@ mod tests {
 
         let csv_data =
             b"address\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -206,9 +669,42 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data =b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
+
+        assert_eq!(response.status, 400);
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_csv_with_unterminated_quote() {
+        let server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let csv_data = b"address,amount\n\"0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
+        assert_eq!(response.message["errors"][0]["row"], 2);
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_upload_not_utf8_csv() {
+        let server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let xlsx = b"PK\x03\x04\x14\x00\x06\x00\x08\x00\x00\x00!\x00\xb2\x8c\x1f";
+        let latin1 = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0 \xe9";
+        // An address with a Latin-1 byte must not reach the parser as a replacement character
+        let latin1_address = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5b\xe9,200.0";
+
+        for (csv_data, offset) in [(&xlsx[..], 14), (&latin1[..], 113), (&latin1_address[..], 105)] {
+            let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
+            assert_eq!(response.status, 400);
+            assert_eq!(response.message["code"], "INVALID_FILE");
+            assert_eq!(
+                response.message["message"],
+                format!("Uploaded file is not a UTF-8 CSV: invalid byte sequence at offset {offset}")
+            );
+        }
         drop(server);
     }
 
@@ -217,7 +713,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data = b"address,amount\n0xThisIsNotAnAddress,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -228,7 +724,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data =b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -240,7 +736,7 @@ mod tests {
         setup_env_vars(&server);
 
         let csv_data = b"address,amount\n0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,alphanumeric_amount\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -251,7 +747,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data = b"address,amount\n0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -262,7 +758,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data = b"address,amount\n0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,-1\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -273,7 +769,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data = b"address,amount\n0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,1.1234\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
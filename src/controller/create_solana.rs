@@ -1,78 +1,219 @@
 use crate::{
-    csv_campaign_parser::CampaignCsvParsed,
+    csv_campaign_parser::{CampaignCsvParsed, ParseOptions},
     data_objects::{
-        dto::{PersistentCampaignDto, RecipientDto},
-        response::{self, UploadSuccessResponse, ValidationErrorResponse},
+        dto::{
+            CampaignMetadataDto, CampaignParamsDto, CampaignUpload, PersistentCampaignDto, RecipientDto,
+            SOLANA_MERKLE_VERSION,
+        },
+        query_param::{Create, MetadataPin},
+        response::{self, ErrorCode, GeneralErrorResponse, UploadSuccessResponse, ValidationErrorResponse},
     },
-    services::ipfs::{try_deserialize_pinata_response, upload_to_ipfs},
+    services::ipfs::{pin_everywhere, pin_json, pinners},
     utils::{
-        auth, request,
-        solana_merkle::{MerkleLeaf, MerkleTree},
+        auth,
+        build_pool::BUILDS,
+        csv_validator::AddressType,
+        logging,
+        request::{self, UploadError},
+        solana_merkle::{HashAlgorithm, MerkleLeaf, MerkleTree, TreeOptions},
     },
 };
 
-use csv::ReaderBuilder;
-use http_body_util::BodyExt;
-use std::io::Read;
+use std::collections::HashMap;
 
 use serde_json::json;
 use vercel_runtime as Vercel;
 
+/// Build the merkle tree of a validated CSV campaign and the campaign data to pin. The leaves are hashed as the
+/// tree `options` say, which are stored alongside the tree and in the campaign params.
+pub fn campaign_dto(parsed_csv: &CampaignCsvParsed, decimals: usize, options: &TreeOptions) -> PersistentCampaignDto {
+    let leaves: Vec<MerkleLeaf> = parsed_csv
+        .records
+        .iter()
+        .enumerate()
+        .map(|(i, r)| MerkleLeaf { index: i as u32, recipient: r.address.clone(), amount: r.amount as u64 })
+        .collect();
+
+    let tree = MerkleTree::build_tree_with_domain(
+        leaves,
+        options.leaf_order,
+        options.hash_algorithm,
+        options.domain.as_deref(),
+    );
+
+    PersistentCampaignDto {
+        total_amount: parsed_csv.total_amount.to_string(),
+        number_of_recipients: parsed_csv.number_of_recipients,
+        merkle_tree: tree.dump().unwrap(),
+        merkle_version: Some(SOLANA_MERKLE_VERSION.to_string()),
+        root: tree.root_hex(),
+        recipients: parsed_csv
+            .records
+            .iter()
+            .map(|x| RecipientDto { address: x.address.clone(), amount: x.amount.to_string(), label: x.label.clone() })
+            .collect(),
+        params: Some(CampaignParamsDto::solana(decimals, options)),
+        expires_at: None,
+    }
+}
+
+/// Decimals of a campaign created without `decimals` when `SOLANA_DEFAULT_DECIMALS` is unset, those of most SPL tokens
+pub const DEFAULT_SOLANA_DECIMALS: u16 = 9;
+
+/// Read `decimals` from the request query parameters. A missing parameter falls back to the `SOLANA_DEFAULT_DECIMALS`
+/// env var, or `DEFAULT_SOLANA_DECIMALS` when it is unset or invalid, while a given one must be an integer.
+pub fn decimals(query: &HashMap<String, String>) -> Result<u16, String> {
+    decimals_with_default(query, std::env::var("SOLANA_DEFAULT_DECIMALS").ok().as_deref())
+}
+
+/// `decimals` with the value of the `SOLANA_DEFAULT_DECIMALS` env var passed in, so it can be tested without touching
+/// the process environment
+fn decimals_with_default(query: &HashMap<String, String>, default_decimals: Option<&str>) -> Result<u16, String> {
+    match query.get("decimals") {
+        Some(decimals) => {
+            decimals.parse().map_err(|_| "The decimals query parameter should be a valid integer".to_string())
+        }
+        None => Ok(default_decimals.and_then(|decimals| decimals.parse().ok()).unwrap_or(DEFAULT_SOLANA_DECIMALS)),
+    }
+}
+
+/// Read the tree hashing options, `leaf_order`, `hash_algorithm` and the hex `domain` tag, from the request query
+/// parameters. Missing parameters keep the defaults.
+pub fn tree_options(query: &HashMap<String, String>) -> Result<TreeOptions, String> {
+    let leaf_order = match query.get("leaf_order") {
+        None => Default::default(),
+        Some(order) => order.parse()?,
+    };
+    let hash_algorithm = match query.get("hash_algorithm") {
+        None => Default::default(),
+        Some(algorithm) => algorithm.parse()?,
+    };
+    let domain = match query.get("domain") {
+        None => None,
+        Some(domain) => match hex::decode(domain.trim_start_matches("0x")) {
+            Ok(domain) if !domain.is_empty() => Some(domain),
+            _ => return Err("The domain query parameter should be a hex string".to_string()),
+        },
+    };
+
+    Ok(TreeOptions { leaf_order, hash_algorithm, domain })
+}
+
 /// Create request common handler. It validates the received data, creates the merkle tree and uploads it to ipfs.
-async fn handler(decimals: usize, buffer: &[u8]) -> response::R {
-    let rdr = ReaderBuilder::new().from_reader(buffer);
-    let parsed_csv = match CampaignCsvParsed::build_solana(rdr, decimals) {
+async fn handler(
+    decimals: usize,
+    tree: TreeOptions,
+    options: ParseOptions,
+    metadata: Option<MetadataPin>,
+    expires_at: Option<i64>,
+    buffer: &[u8],
+) -> response::R {
+    if let Err(error) = AddressType::Solana.check_decimals(decimals) {
+        return response::error(400, ErrorCode::InvalidDecimals, error);
+    }
+    if let Err(error) = request::check_csv(buffer) {
+        return response::error(400, ErrorCode::InvalidFile, error);
+    }
+    let Ok(_build) = BUILDS.acquire().await else {
+        return response::error(503, ErrorCode::ServerBusy, "Server busy, retry later");
+    };
+
+    let rdr = options.reader(buffer);
+    let parsed_csv = match CampaignCsvParsed::build_with_options(rdr, decimals, AddressType::Solana, &options) {
         Ok(parsed) => parsed,
         Err(error) => {
-            return response::message(500, format!("There was a problem in csv file parsing process: {error}"));
+            return response::error(
+                500,
+                ErrorCode::CsvParsingFailed,
+                format!("There was a problem in csv file parsing process: {error}"),
+            );
         }
     };
 
     if !parsed_csv.validation_errors.is_empty() {
         let response_json = json!(ValidationErrorResponse {
             status: "Invalid csv file.".to_string(),
+            code: ErrorCode::CsvValidationFailed,
             errors: parsed_csv.validation_errors,
         });
 
         return response::bad_request(response_json);
     }
 
-    let leaves: Vec<MerkleLeaf> = parsed_csv
-        .records
-        .iter()
-        .enumerate()
-        .map(|(i, r)| MerkleLeaf { index: i as u32, recipient: r.address.clone(), amount: r.amount as u64 })
-        .collect();
+    publish(&parsed_csv, decimals, &tree, options.canonical, metadata, expires_at).await
+}
 
-    let tree = MerkleTree::build_tree(leaves);
+/// Integrity gate run before pinning: the pinned tree must load back and the proof of the first recipient must verify
+/// against its root. A failure means tree construction is broken, not that the input was bad.
+fn first_proof_verifies(dto: &PersistentCampaignDto) -> bool {
+    let Ok(tree) = MerkleTree::load(&dto.merkle_tree) else {
+        return false;
+    };
+    let Some(recipient) = dto.recipients.first() else {
+        return false;
+    };
+    let Ok(amount) = recipient.amount.parse() else {
+        return false;
+    };
+    let leaf = MerkleLeaf { index: 0, recipient: recipient.address.clone(), amount };
 
-    let tree_json = tree.dump().unwrap();
+    tree.root_hex() == dto.root && tree.get_proof(0).is_some_and(|proof| tree.verify_proof(&leaf, &proof))
+}
 
-    let dto = PersistentCampaignDto {
-        total_amount: parsed_csv.total_amount.to_string(),
-        number_of_recipients: parsed_csv.number_of_recipients,
-        merkle_tree: tree_json,
-        root: tree.root_hex(),
-        recipients: parsed_csv
-            .records
-            .iter()
-            .map(|x| RecipientDto { address: x.address.clone(), amount: x.amount.to_string() })
-            .collect(),
-    };
+/// Build the merkle tree of validated recipients, pin it to ipfs along with the optional metadata and answer with
+/// the campaign root and CID
+pub async fn publish(
+    parsed_csv: &CampaignCsvParsed,
+    decimals: usize,
+    tree: &TreeOptions,
+    canonical: bool,
+    metadata: Option<MetadataPin>,
+    expires_at: Option<i64>,
+) -> response::R {
+    let dto = PersistentCampaignDto { expires_at, ..campaign_dto(parsed_csv, decimals, tree) };
+    if !first_proof_verifies(&dto) {
+        let recipient =
+            dto.recipients.first().map(|recipient| logging::address(&recipient.address)).unwrap_or_default();
+        tracing::error!("the proof of the first recipient {recipient} does not verify against root {}", dto.root);
+        return response::error(500, ErrorCode::TreeVerificationFailed, "The campaign merkle tree failed verification");
+    }
 
-    let ipfs_response = match upload_to_ipfs(&dto).await {
-        Ok(response) => response,
+    let pins = match pin_everywhere(&pinners(), &dto).await {
+        Ok(pins) => pins,
         Err(error) => {
-            println!("Error: {error}");
-            return response::message(500, "There was an error uploading the campaign to ipfs");
+            tracing::error!("{error}");
+            return response::error_with_retry_after(
+                500,
+                ErrorCode::IpfsUploadFailed,
+                "There was an error uploading the campaign to ipfs",
+                error.retry_after(),
+            );
         }
     };
 
-    let deserialized_response = match try_deserialize_pinata_response(&ipfs_response) {
-        Ok(response) => response,
-        Err(error) => {
-            println!("Error: {error}");
-            return response::message(500, "There was an error uploading the campaign to ipfs");
+    let metadata_cid = match metadata {
+        None => None,
+        Some(metadata) => {
+            let metadata = CampaignMetadataDto {
+                root: dto.root.clone(),
+                total_amount: parsed_csv.total_amount.to_string(),
+                number_of_recipients: parsed_csv.number_of_recipients,
+                chain: "solana".to_string(),
+                token: metadata.token,
+                created_at: chrono::Utc::now().to_rfc3339(),
+            };
+            match pin_json(&metadata).await {
+                Ok(cid) => Some(cid),
+                Err(error) => {
+                    tracing::error!("{error}");
+                    return response::error_with_retry_after(
+                        500,
+                        ErrorCode::IpfsUploadFailed,
+                        "There was an error uploading the campaign metadata to ipfs",
+                        error.retry_after(),
+                    );
+                }
+            }
         }
     };
 
@@ -80,86 +221,194 @@ async fn handler(decimals: usize, buffer: &[u8]) -> response::R {
         status: "Upload successful".to_string(),
         total: parsed_csv.total_amount.to_string(),
         recipients: parsed_csv.number_of_recipients.to_string(),
-        root: tree.root_hex(),
-        cid: deserialized_response.ipfs_hash,
+        root: dto.root,
+        cid: pins.first().map(|pinned| pinned.cid.clone()).unwrap_or_default(),
+        cids: pins.iter().map(|pinned| pinned.cid.clone()).collect(),
+        pin_size: pins.iter().find_map(|pinned| pinned.size),
+        canonical,
+        tree_depth: UploadSuccessResponse::tree_depth(parsed_csv.records.len()),
+        rounded_rows: parsed_csv.rounded_rows,
+        skipped: parsed_csv.skipped.clone(),
+        metadata_cid,
+        params: dto.params,
+        verified: None,
     });
 
     response::ok(response_json)
 }
 
 /// Vercel specific handler for the create endpoint
+#[utoipa::path(
+    post,
+    path = "/api/create_solana",
+    summary = "Build the Solana merkle tree of a CSV campaign and pin it to IPFS",
+    params(
+        Create,
+        ("leaf_order" = Option<String>, Query, description = "Comma-separated order of `index`, `recipient` and `amount` in a leaf"),
+        ("hash_algorithm" = Option<HashAlgorithm>, Query, description = "Hash of the leaves and nodes, `keccak256` when omitted"),
+        ("domain" = Option<String>, Query, description = "Hex domain separation tag prepended to every leaf, stored as the `salt` param"),
+    ),
+    request_body(content = CampaignUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Campaign pinned", body = UploadSuccessResponse),
+        (status = 400, description = "Invalid CSV file", body = ValidationErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 500, description = "Parsing or IPFS upload failed", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
 pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
     if !auth::is_authorized(&req) {
-        return response::to_vercel_message(401, "Bad authentication process provided.");
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
     }
 
     // ------------------------------------------------------------
-    // Extract query parameters from the URL: decimals
+    // Extract query parameters from the URL: decimals, defaulting to the configured Solana decimals
     //
-    // NOTE: the missing/malformed-input branches below intentionally return status 200
+    // NOTE: the malformed-body branch below intentionally returns status 200
     // to preserve legacy client behavior. Review candidate.
     // ------------------------------------------------------------
 
     let query = request::query_params(&req);
-    let Some(decimals) = query.get("decimals").cloned() else {
-        return response::to_vercel_message(
-            200,
-            "Decimals query parameter is mandatory in order to create a valid campaign!",
-        );
+    let decimals = match decimals(&query) {
+        Ok(decimals) => decimals,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidDecimals, error),
     };
 
-    // ------------------------------------------------------------
-    // Extract form data from the body: file
-    // ------------------------------------------------------------
-
-    let Some(boundary) = req
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.strip_prefix("multipart/form-data; boundary="))
-        .map(String::from)
-    else {
-        return response::to_vercel_message(200, "Invalid content type header");
+    let expires_at = match request::expires_at(&query) {
+        Ok(expires_at) => expires_at,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
     };
 
-    let body = match req.into_body().collect().await {
-        Ok(collected) => collected.to_bytes().to_vec(),
-        Err(error) => return response::to_vercel_message(200, format!("Could not read body data {error}")),
-    };
+    let metadata =
+        query.get("metadata").is_some_and(|v| v == "true").then(|| MetadataPin { token: query.get("token").cloned() });
 
-    let mut data = multipart::server::Multipart::with_body(body.as_slice(), boundary);
-    let file = match data.read_entry() {
-        Ok(file) => file,
-        Err(error) => return response::to_vercel_message(200, error.to_string()),
-    };
+    // ------------------------------------------------------------
+    // Extract form data from the body: file
+    // ------------------------------------------------------------
 
-    let Some(mut file) = file else {
-        return response::to_vercel_message(200, "Invalid form data, missing file");
+    let buffer = match request::multipart_file(req).await {
+        Ok(buffer) => buffer,
+        Err(UploadError::TooLarge(limit)) => {
+            return response::to_vercel_error(413, ErrorCode::PayloadTooLarge, format!("File exceeds {limit} bytes"))
+        }
+        Err(UploadError::Invalid(error)) => return response::to_vercel_error(200, ErrorCode::InvalidFile, error),
     };
-    let mut buffer: Vec<u8> = vec![];
-
-    if let Err(error) = file.data.read_to_end(&mut buffer) {
-        return response::to_vercel_message(200, format!("Could not read body data {error}"));
-    }
 
     // ------------------------------------------------------------
     // Format arguments for the generic handler
     // ------------------------------------------------------------
 
-    let Ok(decimals) = decimals.parse::<u16>() else {
-        return response::to_vercel_message(
-            200,
-            "Decimals query parameter is mandatory and should be a valid integer in order to create a valid campaign!",
-        );
+    let tree = match tree_options(&query) {
+        Ok(tree) => tree,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
     };
 
-    response::to_vercel(handler(decimals.into(), &buffer).await)
+    let options = match ParseOptions::from_query(&query) {
+        Ok(options) => options,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
+    };
+    response::to_vercel(handler(decimals.into(), tree, options, metadata, expires_at, &buffer).await)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::async_test::{setup_env_vars, SERVER};
+    use crate::{
+        controller::eligibility_solana,
+        data_objects::query_param::Eligibility,
+        services::ipfs::{download_from_ipfs, with_store, InMemory},
+        utils::async_test::{setup_env_vars, SERVER},
+    };
+    use csv::ReaderBuilder;
+
+    #[tokio::test]
+    async fn test_create_then_eligibility_in_memory() {
+        let store = InMemory::default();
+        let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
+
+        let (created, eligibility) = with_store(store.clone(), async {
+            let created = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
+            let eligibility = eligibility_solana::handler(Eligibility {
+                cid: created.message["cid"].as_str().unwrap_or_default().to_string(),
+                address: "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E".to_string(),
+                attest: false,
+            })
+            .await;
+            (created, eligibility)
+        })
+        .await;
+
+        assert_eq!(created.status, 200);
+        assert!(store.contains(created.message["cid"].as_str().unwrap()));
+        assert_eq!(eligibility.status, 200);
+        assert_eq!(eligibility.message["index"], 1);
+        assert_eq!(eligibility.message["amount"], "20000");
+        assert_eq!(eligibility.message["proof"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_with_domain_round_trips_in_memory() {
+        let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
+        let query = HashMap::from([("domain".to_string(), "0x7361626c696572".to_string())]);
+        let tree = tree_options(&query).unwrap();
+        assert_eq!(tree.domain.as_deref(), Some(&b"sablier"[..]));
+
+        let (created, pinned, eligibility) = with_store(InMemory::default(), async {
+            let created = handler(2, tree, ParseOptions::default(), None, None, csv_data).await;
+            let cid = created.message["cid"].as_str().unwrap_or_default().to_string();
+            let pinned: PersistentCampaignDto = download_from_ipfs(&cid).await.unwrap();
+            let eligibility = eligibility_solana::handler(Eligibility {
+                cid,
+                address: "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E".to_string(),
+                attest: false,
+            })
+            .await;
+            (created, pinned, eligibility)
+        })
+        .await;
+
+        assert_eq!(created.status, 200);
+        let params = pinned.params.unwrap();
+        assert_eq!(params.salt.as_deref(), Some("7361626c696572"));
+        assert_eq!(created.message["params"]["salt"], "7361626c696572");
+
+        // The stored params alone rebuild the root, which the domain moved away from the untagged one
+        let leaves: Vec<MerkleLeaf> = pinned
+            .recipients
+            .iter()
+            .enumerate()
+            .map(|(i, r)| MerkleLeaf {
+                index: i as u32,
+                recipient: r.address.clone(),
+                amount: r.amount.parse().unwrap(),
+            })
+            .collect();
+        let options = params.tree_options().unwrap();
+        let rebuilt = MerkleTree::build_tree_with_domain(
+            leaves.clone(),
+            options.leaf_order,
+            options.hash_algorithm,
+            options.domain.as_deref(),
+        );
+        assert_eq!(rebuilt.root_hex(), pinned.root);
+        let parsed_csv = CampaignCsvParsed::build_solana(ReaderBuilder::new().from_reader(&csv_data[..]), 2).unwrap();
+        assert_ne!(campaign_dto(&parsed_csv, 2, &TreeOptions::default()).root, pinned.root);
+
+        assert_eq!(eligibility.status, 200);
+        let proof: Vec<String> = serde_json::from_value(eligibility.message["proof"].clone()).unwrap();
+        assert!(rebuilt.verify_proof(&leaves[1], &proof));
+        assert!(!MerkleTree::build_tree(leaves.clone(), options.leaf_order, options.hash_algorithm)
+            .verify_proof(&leaves[1], &proof));
+    }
+
+    #[test]
+    fn test_tree_options_rejects_bad_domain() {
+        for domain in ["", "0x", "zz", "abc"] {
+            let query = HashMap::from([("domain".to_string(), domain.to_string())]);
+            assert_eq!(tree_options(&query), Err("The domain query parameter should be a hex string".to_string()));
+        }
+    }
 
     #[tokio::test]
     async fn test_valid_csv_upload() {
@@ -172,19 +421,114 @@ mod tests {
             .create();
 
         let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 200);
+        assert_eq!(response.message["recipients"], "2");
+        assert_eq!(response.message["tree_depth"], 1);
+        assert_eq!(response.message["cids"], json!(["test_hash"]));
+        assert_eq!(response.message["pin_size"], 123);
         mock.assert();
         drop(server);
     }
 
+    #[tokio::test]
+    async fn test_decimals_above_solana_range() {
+        let server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
+        let response = handler(10, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["message"], "decimals must be between 0 and 9");
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_csv_with_header_only() {
+        let server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let csv_data = b"address,amount\n";
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["errors"][0]["message"], "CSV contains a header but no recipient rows");
+        drop(server);
+    }
+
+    #[test]
+    fn test_decimals_query() {
+        let query = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+
+        assert_eq!(decimals_with_default(&query(&[("decimals", "6")]), None), Ok(6));
+        assert_eq!(decimals_with_default(&query(&[]), None), Ok(DEFAULT_SOLANA_DECIMALS));
+        assert_eq!(
+            decimals_with_default(&query(&[("decimals", "six")]), None).unwrap_err(),
+            "The decimals query parameter should be a valid integer"
+        );
+        assert!(decimals_with_default(&query(&[("decimals", "-1")]), None).is_err());
+
+        assert_eq!(decimals_with_default(&query(&[]), Some("6")), Ok(6));
+        assert_eq!(decimals_with_default(&query(&[]), Some("six")), Ok(DEFAULT_SOLANA_DECIMALS));
+        assert_eq!(decimals_with_default(&query(&[("decimals", "2")]), Some("6")), Ok(2));
+    }
+
+    #[test]
+    fn test_first_proof_guard() {
+        let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,200.0\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,300.0";
+        let parsed_csv = CampaignCsvParsed::build_solana(ReaderBuilder::new().from_reader(&csv_data[..]), 2).unwrap();
+        let mut dto = campaign_dto(&parsed_csv, 2, &TreeOptions::default());
+        assert!(first_proof_verifies(&dto));
+
+        // A wrong sibling hash keeps the tree well formed, but the first proof no longer reaches the root
+        let mut tree = MerkleTree::load(&dto.merkle_tree).unwrap();
+        tree.tree[0][1] = "11".repeat(32);
+        dto.merkle_tree = tree.dump().unwrap();
+        assert!(!first_proof_verifies(&dto));
+
+        dto.merkle_tree = "{}".to_string();
+        assert!(!first_proof_verifies(&dto));
+    }
+
+    #[test]
+    fn test_root_rebuilds_from_stored_params() {
+        let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,200.0";
+        let parsed_csv = CampaignCsvParsed::build_solana(ReaderBuilder::new().from_reader(&csv_data[..]), 2).unwrap();
+        let tree = TreeOptions {
+            leaf_order: "amount,recipient,index".parse().unwrap(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            domain: None,
+        };
+        let dto = campaign_dto(&parsed_csv, 2, &tree);
+
+        // Round-trip through the pinned JSON and rebuild from nothing but the params and the recipients
+        let pinned: PersistentCampaignDto = serde_json::from_str(&serde_json::to_string(&dto).unwrap()).unwrap();
+        let params = pinned.params.unwrap();
+        assert_eq!(params, CampaignParamsDto::solana(2, &tree));
+        assert_eq!(params.tree_options(), Ok(tree));
+        assert_eq!((params.double_hash, params.odd_node.as_str(), params.index_base), (true, "duplicate", 0));
+
+        let leaves = pinned
+            .recipients
+            .iter()
+            .enumerate()
+            .map(|(i, r)| MerkleLeaf {
+                index: params.index_base + i as u32,
+                recipient: r.address.clone(),
+                amount: r.amount.parse().unwrap(),
+            })
+            .collect();
+        let rebuilt = MerkleTree::build_tree(leaves, params.leaf_order, params.hash_algorithm);
+
+        assert_eq!(rebuilt.root_hex(), pinned.root);
+    }
+
     #[tokio::test]
     async fn test_csv_with_wrong_header() {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data =b"address,amount_invalid\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -197,7 +541,7 @@ mod tests {
 
         let csv_data =
             b"address\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -208,7 +552,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data =b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -220,7 +564,7 @@ mod tests {
         setup_env_vars(&server);
         let csv_data =
             b"address,amount\n0xThisIsNotAnAddress,100.0\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -231,7 +575,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data =b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -243,7 +587,7 @@ mod tests {
         setup_env_vars(&server);
 
         let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,alphanumeric_amount\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -254,7 +598,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,0\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -265,7 +609,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,-1\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -276,7 +620,7 @@ mod tests {
         let server = SERVER.lock().await;
         setup_env_vars(&server);
         let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,1.1234\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
-        let response = handler(2, csv_data).await;
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), None, None, csv_data).await;
 
         assert_eq!(response.status, 400);
         drop(server);
@@ -0,0 +1,217 @@
+use crate::{
+    controller::create_solana::{publish, tree_options},
+    csv_campaign_parser::{CampaignCsvParsed, ParseOptions},
+    data_objects::{
+        dto::CampaignJsonUpload,
+        query_param::MetadataPin,
+        response::{self, ErrorCode, GeneralErrorResponse, UploadSuccessResponse, ValidationErrorResponse},
+    },
+    utils::{
+        auth,
+        build_pool::BUILDS,
+        csv_validator::AddressType,
+        request::{self, UploadError},
+        solana_merkle::{HashAlgorithm, TreeOptions},
+    },
+};
+
+use serde_json::json;
+use vercel_runtime as Vercel;
+
+/// JSON create request common handler. It validates the recipients like the rows of a CSV upload, creates the merkle
+/// tree and uploads it to ipfs.
+async fn handler(
+    upload: CampaignJsonUpload,
+    tree: TreeOptions,
+    options: ParseOptions,
+    metadata: Option<MetadataPin>,
+    expires_at: Option<i64>,
+) -> response::R {
+    let decimals = upload.decimals.into();
+    if let Err(error) = AddressType::Solana.check_decimals(decimals) {
+        return response::error(400, ErrorCode::InvalidDecimals, error);
+    }
+    let Ok(_build) = BUILDS.acquire().await else {
+        return response::error(503, ErrorCode::ServerBusy, "Server busy, retry later");
+    };
+
+    let parsed = CampaignCsvParsed::build_from_recipients(&upload.recipients, decimals, AddressType::Solana, &options);
+    if !parsed.validation_errors.is_empty() {
+        let response_json = json!(ValidationErrorResponse {
+            status: "Invalid recipients.".to_string(),
+            code: ErrorCode::InvalidRecipients,
+            errors: parsed.validation_errors,
+        });
+
+        return response::bad_request(response_json);
+    }
+
+    publish(&parsed, decimals, &tree, options.canonical, metadata, expires_at).await
+}
+
+/// Vercel specific handler for the JSON create endpoint
+#[utoipa::path(
+    post,
+    path = "/api/create_solana/json",
+    summary = "Build the Solana merkle tree of a campaign sent as JSON and pin it to IPFS",
+    params(
+        ("leaf_order" = Option<String>, Query, description = "Comma-separated order of `index`, `recipient` and `amount` in a leaf"),
+        ("hash_algorithm" = Option<HashAlgorithm>, Query, description = "Hash of the leaves and nodes, `keccak256` when omitted"),
+        ("domain" = Option<String>, Query, description = "Hex domain separation tag prepended to every leaf, stored as the `salt` param"),
+    ),
+    request_body(content = CampaignJsonUpload, content_type = "application/json"),
+    responses(
+        (status = 200, description = "Campaign pinned", body = UploadSuccessResponse),
+        (status = 400, description = "Invalid body or recipients, by position", body = ValidationErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 413, description = "Body larger than `MAX_UPLOAD_BYTES`", body = GeneralErrorResponse),
+        (status = 500, description = "IPFS upload failed", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: the same options as the CSV create endpoint
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    let expires_at = match request::expires_at(&query) {
+        Ok(expires_at) => expires_at,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
+    };
+
+    let tree = match tree_options(&query) {
+        Ok(tree) => tree,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
+    };
+
+    let options = match ParseOptions::from_query(&query) {
+        Ok(options) => options,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
+    };
+
+    let metadata =
+        query.get("metadata").is_some_and(|v| v == "true").then(|| MetadataPin { token: query.get("token").cloned() });
+
+    // ------------------------------------------------------------
+    // Extract the JSON body: decimals and recipients
+    // ------------------------------------------------------------
+
+    let is_json = req
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(';').next() == Some("application/json"));
+    if !is_json {
+        return response::to_vercel_error(400, ErrorCode::InvalidFile, "Invalid content type header");
+    }
+
+    let body = match request::body(req).await {
+        Ok(body) => body,
+        Err(UploadError::TooLarge(limit)) => {
+            return response::to_vercel_error(413, ErrorCode::PayloadTooLarge, format!("File exceeds {limit} bytes"))
+        }
+        Err(UploadError::Invalid(error)) => return response::to_vercel_error(400, ErrorCode::InvalidFile, error),
+    };
+
+    let upload: CampaignJsonUpload = match serde_json::from_slice(&body) {
+        Ok(upload) => upload,
+        Err(error) => {
+            return response::to_vercel_error(400, ErrorCode::InvalidFile, format!("Invalid JSON body: {error}"));
+        }
+    };
+
+    response::to_vercel(handler(upload, tree, options, metadata, expires_at).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        controller::create_solana::campaign_dto,
+        data_objects::dto::RecipientDto,
+        utils::async_test::{setup_env_vars, SERVER},
+    };
+    use csv::ReaderBuilder;
+
+    fn upload(recipients: &[(&str, &str)]) -> CampaignJsonUpload {
+        let recipients = recipients
+            .iter()
+            .map(|(address, amount)| RecipientDto {
+                address: address.to_string(),
+                amount: amount.to_string(),
+                label: None,
+            })
+            .collect();
+        CampaignJsonUpload { decimals: 2, recipients }
+    }
+
+    #[tokio::test]
+    async fn test_valid_json_upload() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "test_hash", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .create();
+
+        let recipients = [
+            ("9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y", "100.0"),
+            ("2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E", "200.0"),
+        ];
+        let response = handler(upload(&recipients), TreeOptions::default(), ParseOptions::default(), None, None).await;
+
+        // The same recipients uploaded as a CSV file give the same root
+        let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,200.0";
+        let parsed_csv = CampaignCsvParsed::build_solana(ReaderBuilder::new().from_reader(&csv_data[..]), 2).unwrap();
+        let dto = campaign_dto(&parsed_csv, 2, &TreeOptions::default());
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["recipients"], "2");
+        assert_eq!(response.message["total"], "30000");
+        assert_eq!(response.message["root"], dto.root);
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_json_upload() {
+        let server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let recipients = [
+            ("9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y", "100.0"),
+            ("0xThisIsNotAnAddress", "200.0"),
+            ("9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y", "300.0"),
+            ("2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E", "0"),
+        ];
+        let response = handler(upload(&recipients), TreeOptions::default(), ParseOptions::default(), None, None).await;
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["code"], "INVALID_RECIPIENTS");
+        let errors = response.message["errors"].as_array().unwrap();
+        let rows: Vec<u64> = errors.iter().map(|error| error["row"].as_u64().unwrap()).collect();
+        assert_eq!(rows, [1, 2, 3]);
+        assert_eq!(errors[0]["message"], "Invalid Solana address");
+        assert_eq!(errors[2]["message"], "The amount cannot be 0");
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_single_recipient() {
+        let server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let recipients = [("9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y", "100.0")];
+        let response = handler(upload(&recipients), TreeOptions::default(), ParseOptions::default(), None, None).await;
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["errors"][0]["row"], 0);
+        assert_eq!(response.message["errors"][0]["message"], "An airstream campaign must have at least 2 recipients");
+        drop(server);
+    }
+}
@@ -1,56 +1,134 @@
 use crate::{
+    csv_campaign_parser::format_amount,
     data_objects::{
-        dto::PersistentCampaignDto,
+        dto::{PersistentCampaignDto, EVM_MERKLE_VERSION},
         query_param::Eligibility,
-        response::{self, EligibilityResponse},
+        response::{self, CampaignExpiredResponse, EligibilityResponse, ErrorCode, GeneralErrorResponse},
+    },
+    services::ipfs::{download_from_ipfs, IpfsError},
+    utils::{
+        attestation::{self, AttestationPayload},
+        auth,
+        csv_validator::AddressType,
+        request,
     },
-    services::ipfs::download_from_ipfs,
-    utils::{auth, request},
 };
 use merkle_tree_rs::standard::{LeafType, StandardMerkleTree, StandardMerkleTreeData};
 
 use serde_json::json;
+use solana_sdk::signature::Keypair;
 
 use vercel_runtime as Vercel;
 
 /// Eligibility request common handler. It downloads data from IPFS and determines if an address is eligible for an
 /// airstream campaign.
 pub async fn handler(eligibility: Eligibility) -> response::R {
-    let Ok(ipfs_data) = download_from_ipfs::<PersistentCampaignDto>(&eligibility.cid).await else {
-        return response::message(500, "There was a problem processing your request: Bad CID provided");
+    let signing_key = eligibility.attest.then(attestation::signing_key).flatten();
+    handler_at(eligibility, chrono::Utc::now().timestamp(), signing_key.as_ref()).await
+}
+
+/// Same as `handler`, with the current unix time in seconds, which decides whether the campaign has expired, and the
+/// attestation `signing_key` given by the caller
+pub async fn handler_at(eligibility: Eligibility, now: i64, signing_key: Option<&Keypair>) -> response::R {
+    let ipfs_data = match download_from_ipfs::<PersistentCampaignDto>(&eligibility.cid).await {
+        Ok(ipfs_data) => ipfs_data,
+        Err(IpfsError::NotFound) => {
+            return response::error(404, ErrorCode::CampaignNotFound, "The provided CID is not pinned")
+        }
+        Err(_) => {
+            return response::error(
+                500,
+                ErrorCode::IpfsDownloadFailed,
+                "There was a problem processing your request: Bad CID provided",
+            )
+        }
     };
 
+    if ipfs_data.expires_at.is_some_and(|expires_at| now > expires_at) {
+        let expired = CampaignExpiredResponse { eligible: false, reason: "Campaign has expired".to_string() };
+        return response::ok_immutable(json!(expired));
+    }
+
+    let address = AddressType::Ethereum.address_key(&eligibility.address);
     let Some(recipient_index) =
-        ipfs_data.recipients.iter().position(|r| r.address.to_lowercase() == eligibility.address.to_lowercase())
+        ipfs_data.recipients.iter().position(|r| AddressType::Ethereum.address_key(&r.address) == address)
     else {
-        return response::message(400, "The provided address is not eligible for this campaign");
+        return response::error(
+            400,
+            ErrorCode::AddressNotEligible,
+            "The provided address is not eligible for this campaign",
+        );
     };
 
+    if let Err(error) = ipfs_data.check_merkle_version(EVM_MERKLE_VERSION) {
+        return response::error(500, ErrorCode::UnsupportedMerkleVersion, error);
+    }
     let Ok(tree_data) = serde_json::from_str::<StandardMerkleTreeData>(&ipfs_data.merkle_tree) else {
-        return response::message(500, "Malformed merkle tree in IPFS data");
+        return response::error(500, ErrorCode::MalformedCampaign, "Malformed merkle tree in IPFS data");
     };
 
     let tree = StandardMerkleTree::load(tree_data);
 
     let proof = tree.get_proof(LeafType::Number(recipient_index));
 
-    let response_json = json!(&EligibilityResponse {
+    let mut eligibility_response = EligibilityResponse {
         index: recipient_index,
         proof,
-        address: ipfs_data.recipients[recipient_index].address.clone(),
+        expected_proof_len: None,
+        address: AddressType::Ethereum.canonical_address(&ipfs_data.recipients[recipient_index].address),
         amount: ipfs_data.recipients[recipient_index].amount.clone(),
-    });
-    response::ok_immutable(response_json)
+        amount_display: ipfs_data
+            .params
+            .as_ref()
+            .and_then(|params| format_amount(&ipfs_data.recipients[recipient_index].amount, params.decimals)),
+        label: ipfs_data.recipients[recipient_index].label.clone(),
+        attestation: None,
+    };
+
+    // A campaign with a deadline must not be served from the immutable edge cache, it expires later.
+    if !eligibility.attest {
+        return match ipfs_data.expires_at {
+            None => response::ok_immutable(json!(&eligibility_response)),
+            Some(_) => response::ok(json!(&eligibility_response)),
+        };
+    }
+
+    // Attestations carry an issue time, so they must not be served from the immutable edge cache.
+    let Some(keypair) = signing_key else {
+        return response::error(500, ErrorCode::AttestationUnavailable, "Attestation signing key is not configured");
+    };
+    let payload = AttestationPayload {
+        cid: eligibility.cid,
+        address: eligibility_response.address.clone(),
+        index: eligibility_response.index,
+        amount: eligibility_response.amount.clone(),
+        root: ipfs_data.root,
+        issued_at: chrono::Utc::now().timestamp(),
+    };
+    eligibility_response.attestation = Some(attestation::sign(keypair, &payload));
+
+    response::ok(json!(&eligibility_response))
 }
 
 /// Vercel specific handler for the create eligibility
+#[utoipa::path(
+    get,
+    path = "/api/eligibility",
+    summary = "Proof of a recipient of a EVM campaign",
+    params(Eligibility),
+    responses(
+        (status = 200, description = "The address is eligible, unless the campaign expired", body = EligibilityResponse),
+        (status = 304, description = "`If-None-Match` holds the `ETag` of the cached answer"),
+        (status = 400, description = "The address is not eligible", body = GeneralErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 404, description = "The CID is not pinned", body = GeneralErrorResponse),
+        (status = 500, description = "The campaign could not be read", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
 pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
-    if !auth::is_authorized(&req) {
-        return response::to_vercel_message(401, "Bad authentication process provided.");
-    }
-
     // ------------------------------------------------------------
-    // Extract query parameters from the URL: address, cid
+    // Extract query parameters from the URL: address, cid, token
     // ------------------------------------------------------------
 
     let query = request::query_params(&req);
@@ -63,9 +141,15 @@ pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<
     let params = Eligibility {
         address: query.get("address").unwrap_or(&fallback).clone(),
         cid: query.get("cid").unwrap_or(&fallback).clone(),
+        attest: query.get("attest").is_some_and(|attest| attest == "true"),
     };
 
-    response::to_vercel(handler(params).await)
+    if !auth::is_authorized_for(&req, &params.cid) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    let if_none_match = req.headers().get("if-none-match").and_then(|v| v.to_str().ok());
+    response::to_vercel(response::not_modified_if(handler(params).await, if_none_match))
 }
 
 #[cfg(test)]
@@ -87,6 +171,7 @@ mod tests {
         let validity = Eligibility {
             cid: "valid_cid".to_string(),
             address: "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
         };
         let response = handler(validity).await;
         assert_eq!(response.status, 200);
@@ -94,6 +179,121 @@ mod tests {
         drop(server);
     }
 
+    #[tokio::test]
+    async fn handler_returns_checksummed_address() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        // Stored lowercase, as some older campaigns were
+        let mock = server
+            .mock("GET", "/lowercase_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 1, "merkle_tree":"{\"format\":\"standard-v1\",\"tree\":[\"0x23bb7a869a407bc69b27975acff039dfe6a6abe5e3da626e98623d70137eb320\"],\"values\":[{\"value\":[\"0\",\"0x9ad7cad4f10d0c3f875b8a2fd292590490c9f491\",\"5000\"],\"tree_index\":0}],\"leaf_encoding\":[\"uint\",\"address\",\"uint256\"]}", "recipients": [{ "address": "0x9ad7cad4f10d0c3f875b8a2fd292590490c9f491", "amount": "10"}]}"#)
+            .create();
+
+        let eligibility = Eligibility {
+            cid: "lowercase_cid".to_string(),
+            address: "0x9ad7cad4f10d0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
+        };
+        let response = handler(eligibility).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["address"], "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_matches_unprefixed_address() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/canonical_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 1, "merkle_tree":"{\"format\":\"standard-v1\",\"tree\":[\"0x23bb7a869a407bc69b27975acff039dfe6a6abe5e3da626e98623d70137eb320\"],\"values\":[{\"value\":[\"0\",\"0x9ad7cad4f10d0c3f875b8a2fd292590490c9f491\",\"5000\"],\"tree_index\":0}],\"leaf_encoding\":[\"uint\",\"address\",\"uint256\"]}", "recipients": [{ "address": "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}]}"#)
+            .create();
+
+        let eligibility = Eligibility {
+            cid: "canonical_cid".to_string(),
+            address: "9AD7CAD4F10D0C3F875B8A2FD292590490C9F491".to_string(),
+            attest: false,
+        };
+        let response = handler(eligibility).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["address"], "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_conditional_request() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/etag_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 1, "merkle_tree":"{\"format\":\"standard-v1\",\"tree\":[\"0x23bb7a869a407bc69b27975acff039dfe6a6abe5e3da626e98623d70137eb320\"],\"values\":[{\"value\":[\"0\",\"0x9ad7cad4f10d0c3f875b8a2fd292590490c9f491\",\"5000\"],\"tree_index\":0}],\"leaf_encoding\":[\"uint\",\"address\",\"uint256\"]}", "recipients": [{ "address": "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}]}"#)
+            .expect(3)
+            .create();
+
+        let eligibility = || Eligibility {
+            cid: "etag_cid".to_string(),
+            address: "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
+        };
+
+        let response = response::not_modified_if(handler(eligibility()).await, None);
+        assert_eq!(response.status, 200);
+        let Some(etag) = response.etag else {
+            panic!("an immutable answer should have an ETag");
+        };
+
+        let response = response::not_modified_if(handler(eligibility()).await, Some(&etag));
+        assert_eq!(response.status, 304);
+        assert!(response.message.is_null());
+        assert_eq!(response.etag, Some(etag));
+
+        let response = response::not_modified_if(handler(eligibility()).await, Some("\"stale\""));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["index"], 0);
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_expired_campaign() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/expiring_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 1, "expires_at": 1700000000, "merkle_tree":"{\"format\":\"standard-v1\",\"tree\":[\"0x23bb7a869a407bc69b27975acff039dfe6a6abe5e3da626e98623d70137eb320\"],\"values\":[{\"value\":[\"0\",\"0x9ad7cad4f10d0c3f875b8a2fd292590490c9f491\",\"5000\"],\"tree_index\":0}],\"leaf_encoding\":[\"uint\",\"address\",\"uint256\"]}", "recipients": [{ "address": "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}]}"#)
+            .expect(2)
+            .create();
+
+        let eligibility = || Eligibility {
+            cid: "expiring_cid".to_string(),
+            address: "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
+        };
+
+        let response = handler_at(eligibility(), 1_699_999_999, None).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["index"], 0);
+        assert_eq!(response.cache_control, None);
+
+        let response = handler_at(eligibility(), 1_700_000_001, None).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["eligible"], false);
+        assert_eq!(response.message["reason"], "Campaign has expired");
+        assert!(response.message.get("proof").is_none());
+        mock.assert();
+        drop(server);
+    }
+
     #[tokio::test]
     async fn handler_error_response() {
         let mut server = SERVER.lock().await;
@@ -108,10 +308,73 @@ mod tests {
         let validity = Eligibility {
             cid: "invalid_cid".to_string(),
             address: "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
         };
         let response = handler(validity).await;
         assert_eq!(response.status, 500);
         mock.assert();
         drop(server);
     }
+
+    #[tokio::test]
+    async fn handler_not_found_response() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/unpinned_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(404)
+            .with_body("Not Found")
+            .create();
+
+        let validity = Eligibility {
+            cid: "unpinned_cid".to_string(),
+            address: "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
+        };
+        let response = handler(validity).await;
+        assert_eq!(response.status, 404);
+        assert_eq!(response.message["code"], "CAMPAIGN_NOT_FOUND");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_merkle_versions() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let campaign = |version: &str| {
+            format!(
+                r#"{{"root": "root", "total_amount": "10", "number_of_recipients": 1, "merkle_version": "{version}", "merkle_tree":"{{\"format\":\"standard-v1\",\"tree\":[\"0x23bb7a869a407bc69b27975acff039dfe6a6abe5e3da626e98623d70137eb320\"],\"values\":[{{\"value\":[\"0\",\"0x9ad7cad4f10d0c3f875b8a2fd292590490c9f491\",\"5000\"],\"tree_index\":0}}],\"leaf_encoding\":[\"uint\",\"address\",\"uint256\"]}}", "recipients": [{{ "address": "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}}]}}"#
+            )
+        };
+        let v1 = server
+            .mock("GET", "/v1_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(campaign(EVM_MERKLE_VERSION))
+            .create();
+        let unknown = server
+            .mock("GET", "/unknown_version_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(campaign("keccak-sorted-v9"))
+            .create();
+
+        let eligibility = |cid: &str| Eligibility {
+            cid: cid.to_string(),
+            address: "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
+        };
+        let response = handler(eligibility("v1_cid")).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["index"], 0);
+
+        let response = handler(eligibility("unknown_version_cid")).await;
+        assert_eq!(response.status, 500);
+        assert_eq!(response.message["code"], "UNSUPPORTED_MERKLE_VERSION");
+        assert_eq!(response.message["message"], "Unsupported merkle version keccak-sorted-v9");
+        v1.assert();
+        unknown.assert();
+        drop(server);
+    }
 }
@@ -1,55 +1,196 @@
 use crate::{
+    csv_campaign_parser::format_amount,
     data_objects::{
-        dto::PersistentCampaignDto,
+        dto::{PersistentCampaignDto, SOLANA_MERKLE_VERSION},
         query_param::Eligibility,
-        response::{self, EligibilityResponse},
+        response::{self, CampaignExpiredResponse, EligibilityResponse, ErrorCode, GeneralErrorResponse},
+    },
+    services::ipfs::{download_from_ipfs, with_download_cache, IpfsError},
+    utils::{
+        attestation::{self, AttestationPayload},
+        auth,
+        csv_validator::AddressType,
+        request,
+        solana_merkle::MerkleTree,
     },
-    services::ipfs::download_from_ipfs,
-    utils::{auth, request, solana_merkle::MerkleTree},
 };
 
 use serde_json::json;
+use solana_sdk::signature::Keypair;
 
 use vercel_runtime as Vercel;
 
 /// Eligibility request common handler. It downloads data from IPFS and determines if an address is eligible for an
 /// airstream campaign.
 pub async fn handler(eligibility: Eligibility) -> response::R {
-    let Ok(ipfs_data) = download_from_ipfs::<PersistentCampaignDto>(&eligibility.cid).await else {
-        return response::message(500, "There was a problem processing your request: Bad CID provided");
+    let signing_key = eligibility.attest.then(attestation::signing_key).flatten();
+    handler_at(eligibility, chrono::Utc::now().timestamp(), signing_key.as_ref()).await
+}
+
+/// Same as `handler`, with the current unix time in seconds, which decides whether the campaign has expired, and the
+/// attestation `signing_key` given by the caller
+pub async fn handler_at(eligibility: Eligibility, now: i64, signing_key: Option<&Keypair>) -> response::R {
+    let ipfs_data = match download_from_ipfs::<PersistentCampaignDto>(&eligibility.cid).await {
+        Ok(ipfs_data) => ipfs_data,
+        Err(IpfsError::NotFound) => {
+            return response::error(404, ErrorCode::CampaignNotFound, "The provided CID is not pinned")
+        }
+        Err(_) => {
+            return response::error(
+                500,
+                ErrorCode::IpfsDownloadFailed,
+                "There was a problem processing your request: Bad CID provided",
+            )
+        }
     };
 
-    let Some(recipient_index) =
-        ipfs_data.recipients.iter().position(|r| r.address.to_lowercase() == eligibility.address.to_lowercase())
-    else {
-        return response::message(400, "The provided address is not eligible for this campaign");
+    if ipfs_data.expires_at.is_some_and(|expires_at| now > expires_at) {
+        let expired = CampaignExpiredResponse { eligible: false, reason: "Campaign has expired".to_string() };
+        return response::ok_immutable(json!(expired));
+    }
+
+    let Some(recipient_index) = recipient_index(&ipfs_data, &eligibility.address) else {
+        return response::error(
+            400,
+            ErrorCode::AddressNotEligible,
+            "The provided address is not eligible for this campaign",
+        );
     };
 
-    let Ok(tree) = MerkleTree::load(&ipfs_data.merkle_tree) else {
-        return response::message(500, "Malformed merkle tree in IPFS data");
+    if let Err(error) = ipfs_data.check_merkle_version(SOLANA_MERKLE_VERSION) {
+        return response::error(500, ErrorCode::UnsupportedMerkleVersion, error);
+    }
+    let tree = match MerkleTree::load(&ipfs_data.merkle_tree) {
+        Ok(tree) => tree,
+        Err(error) => {
+            tracing::error!("{error}");
+            return response::error(500, ErrorCode::MalformedCampaign, "Malformed merkle tree in IPFS data");
+        }
     };
+    // Leaf `i` is the one of recipient `i`, so with a corrupted pin a proof could be served for another leaf
+    if tree.leaf_count() != ipfs_data.recipients.len() {
+        return response::error(500, ErrorCode::MalformedCampaign, "Campaign data inconsistent");
+    }
 
     let Some(proof) = tree.get_proof(recipient_index as u32) else {
-        return response::message(500, "Failed to generate proof for recipient");
+        return response::error(500, ErrorCode::MalformedCampaign, "Failed to generate proof for recipient");
     };
+    let expected_proof_len = tree.expected_proof_len(recipient_index as u32);
+    if proof.len() != expected_proof_len {
+        return response::error(
+            500,
+            ErrorCode::MalformedCampaign,
+            format!("Proof has {} elements instead of {expected_proof_len}", proof.len()),
+        );
+    }
 
-    let response_json = json!(&EligibilityResponse {
+    let mut eligibility_response = EligibilityResponse {
         index: recipient_index,
         proof,
-        address: ipfs_data.recipients[recipient_index].address.clone(),
+        expected_proof_len: Some(expected_proof_len),
+        address: AddressType::Solana.canonical_address(&ipfs_data.recipients[recipient_index].address),
         amount: ipfs_data.recipients[recipient_index].amount.clone(),
-    });
-    response::ok_immutable(response_json)
+        amount_display: ipfs_data
+            .params
+            .as_ref()
+            .and_then(|params| format_amount(&ipfs_data.recipients[recipient_index].amount, params.decimals)),
+        label: ipfs_data.recipients[recipient_index].label.clone(),
+        attestation: None,
+    };
+
+    // A campaign with a deadline must not be served from the immutable edge cache, it expires later.
+    if !eligibility.attest {
+        return match ipfs_data.expires_at {
+            None => response::ok_immutable(json!(&eligibility_response)),
+            Some(_) => response::ok(json!(&eligibility_response)),
+        };
+    }
+
+    // Attestations carry an issue time, so they must not be served from the immutable edge cache.
+    let Some(keypair) = signing_key else {
+        return response::error(500, ErrorCode::AttestationUnavailable, "Attestation signing key is not configured");
+    };
+    let payload = AttestationPayload {
+        cid: eligibility.cid,
+        address: eligibility_response.address.clone(),
+        index: eligibility_response.index,
+        amount: eligibility_response.amount.clone(),
+        root: ipfs_data.root,
+        issued_at: chrono::Utc::now().timestamp(),
+    };
+    eligibility_response.attestation = Some(attestation::sign(keypair, &payload));
+
+    response::ok(json!(&eligibility_response))
 }
 
-/// Vercel specific handler for the create eligibility
-pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
-    if !auth::is_authorized(&req) {
-        return response::to_vercel_message(401, "Bad authentication process provided.");
+/// Lighter `handler` for `HEAD` requests, which only tells whether an address can claim: 200 when it is eligible, 404
+/// when it is not or the campaign has expired and 502 when the campaign cannot be downloaded. An eligible address of
+/// a campaign whose tree `handler` cannot read gets its 500. No proof is built and the response has no body.
+pub async fn exists_at(eligibility: &Eligibility, now: i64) -> response::R {
+    let Ok(ipfs_data) = download_from_ipfs::<PersistentCampaignDto>(&eligibility.cid).await else {
+        return response::empty(502, false);
+    };
+
+    let expired = ipfs_data.expires_at.is_some_and(|expires_at| now > expires_at);
+    let eligible = !expired && recipient_index(&ipfs_data, &eligibility.address).is_some();
+    if !eligible {
+        return response::empty(404, ipfs_data.expires_at.is_none());
     }
 
+    // The checks of `handler` before it builds the proof
+    let readable = ipfs_data.check_merkle_version(SOLANA_MERKLE_VERSION).is_ok() &&
+        MerkleTree::load(&ipfs_data.merkle_tree).is_ok_and(|tree| tree.leaf_count() == ipfs_data.recipients.len());
+    if !readable {
+        return response::empty(500, false);
+    }
+
+    // Same caching as the GET answer: a campaign with a deadline changes answer when it expires
+    response::empty(200, ipfs_data.expires_at.is_none())
+}
+
+/// `exists_at` for a `HEAD` request, with the caching headers of the GET answer. An eligible address gets the `ETag`
+/// of its GET answer, so the proof is built for it, from the same download, but not sent.
+pub async fn head(eligibility: Eligibility, now: i64) -> response::R {
+    with_download_cache(async {
+        let head = exists_at(&eligibility, now).await;
+        if head.status != 200 || head.cache_control.is_none() {
+            return head;
+        }
+
+        let get = response::not_modified_if(handler(eligibility).await, None);
+        if get.status != 200 {
+            return response::empty(get.status, false);
+        }
+        response::R { cache_control: get.cache_control, etag: get.etag, ..head }
+    })
+    .await
+}
+
+/// Index of `address` among the recipients of the campaign. Base58 is case-sensitive, so the keys must match exactly.
+fn recipient_index(ipfs_data: &PersistentCampaignDto, address: &str) -> Option<usize> {
+    let address = AddressType::Solana.address_key(address);
+    ipfs_data.recipients.iter().position(|r| AddressType::Solana.address_key(&r.address) == address)
+}
+
+/// Vercel specific handler for the create eligibility
+#[utoipa::path(
+    get,
+    path = "/api/eligibility_solana",
+    summary = "Proof of a recipient of a Solana campaign",
+    params(Eligibility),
+    responses(
+        (status = 200, description = "The address is eligible, unless the campaign expired", body = EligibilityResponse),
+        (status = 304, description = "`If-None-Match` holds the `ETag` of the cached answer"),
+        (status = 400, description = "The address is not eligible", body = GeneralErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 404, description = "The CID is not pinned", body = GeneralErrorResponse),
+        (status = 500, description = "The campaign could not be read", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
     // ------------------------------------------------------------
-    // Extract query parameters from the URL: address, cid
+    // Extract query parameters from the URL: address, cid, token
     // ------------------------------------------------------------
 
     let query = request::query_params(&req);
@@ -62,9 +203,20 @@ pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<
     let params = Eligibility {
         address: query.get("address").unwrap_or(&fallback).clone(),
         cid: query.get("cid").unwrap_or(&fallback).clone(),
+        attest: query.get("attest").is_some_and(|attest| attest == "true"),
     };
 
-    response::to_vercel(handler(params).await)
+    if !auth::is_authorized_for(&req, &params.cid) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    let if_none_match = req.headers().get("if-none-match").and_then(|v| v.to_str().ok());
+    if req.method() == http::Method::HEAD {
+        let answer = head(params, chrono::Utc::now().timestamp()).await;
+        return response::to_vercel_head(response::not_modified_if(answer, if_none_match));
+    }
+
+    response::to_vercel(response::not_modified_if(handler(params).await, if_none_match))
 }
 
 #[cfg(test)]
@@ -80,15 +232,79 @@ mod tests {
         let mock = server
             .mock("GET", "/valid_cid?pinataGatewayToken=mock_pinata_access_token")
             .with_status(200)
-            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 1, "merkle_tree": "{\"root\":\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\",\"tree\":[[\"410c2c7cb39bf8cc15b1e22fc5b9c26be08465174ccef0be090d9d9df86d03ad\",\"1f605d6b20676921f61532c385082aae4619ba91dfb83c71bf1bc43678626119\",\"158db4f6ff3d0547cef89e6125c2c39052d1b2b288a8db9742958cc1b80fcb43\",\"77a70b41a193dc0a1e9a07dca4a3f2fb40c37282a6d18849dd7af36b684590ca\"],[\"a6a693f5474548569bfd931d4af466a50a5eb0374d895071f647393ff6da241b\",\"35b4f28cb601668e6f89cb6eace3a2d845e700f718182bdf237f17811ead81a6\"],[\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\"]]}", "recipients": [{ "address": "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}]}"#)
+            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 4, "merkle_tree": "{\"root\":\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\",\"tree\":[[\"410c2c7cb39bf8cc15b1e22fc5b9c26be08465174ccef0be090d9d9df86d03ad\",\"1f605d6b20676921f61532c385082aae4619ba91dfb83c71bf1bc43678626119\",\"158db4f6ff3d0547cef89e6125c2c39052d1b2b288a8db9742958cc1b80fcb43\",\"77a70b41a193dc0a1e9a07dca4a3f2fb40c37282a6d18849dd7af36b684590ca\"],[\"a6a693f5474548569bfd931d4af466a50a5eb0374d895071f647393ff6da241b\",\"35b4f28cb601668e6f89cb6eace3a2d845e700f718182bdf237f17811ead81a6\"],[\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\"]]}", "recipients": [{ "address": "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}, { "address": "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E", "amount": "10"}, { "address": "4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi", "amount": "10"}, { "address": "8qbHbw2BbbTHBW1sbeqakYXVKRQM8Ne7pLK7m6CVfeR", "amount": "10"}]}"#)
             .create();
 
         let validity = Eligibility {
             cid: "valid_cid".to_string(),
             address: "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
         };
         let response = handler(validity).await;
         assert_eq!(response.status, 200);
+        assert!(response.message.get("amount_display").is_none());
+        assert_eq!(response.message["expected_proof_len"], 2);
+        assert_eq!(response.message["proof"].as_array().unwrap().len(), 2);
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_amount_display_response() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/decimals_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "123456", "number_of_recipients": 2, "params": {"chain": "solana", "decimals": 2, "hash_algorithm": "keccak256", "leaf_order": ["index", "recipient", "amount"], "double_hash": true, "odd_node": "duplicate", "salt": null, "index_base": 0}, "merkle_tree": "{\"root\":\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\",\"tree\":[[\"410c2c7cb39bf8cc15b1e22fc5b9c26be08465174ccef0be090d9d9df86d03ad\",\"1f605d6b20676921f61532c385082aae4619ba91dfb83c71bf1bc43678626119\"],[\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\"]]}", "recipients": [{ "address": "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y", "amount": "123456"}, { "address": "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E", "amount": "10"}]}"#)
+            .create();
+
+        let eligibility = Eligibility {
+            cid: "decimals_cid".to_string(),
+            address: "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y".to_string(),
+            attest: false,
+        };
+        let response = handler(eligibility).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["amount"], "123456");
+        assert_eq!(response.message["amount_display"], "1,234.56");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_attested_response() {
+        use solana_sdk::signature::Signer;
+
+        let keypair = Keypair::new();
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/attested_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 4, "merkle_tree": "{\"root\":\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\",\"tree\":[[\"410c2c7cb39bf8cc15b1e22fc5b9c26be08465174ccef0be090d9d9df86d03ad\",\"1f605d6b20676921f61532c385082aae4619ba91dfb83c71bf1bc43678626119\",\"158db4f6ff3d0547cef89e6125c2c39052d1b2b288a8db9742958cc1b80fcb43\",\"77a70b41a193dc0a1e9a07dca4a3f2fb40c37282a6d18849dd7af36b684590ca\"],[\"a6a693f5474548569bfd931d4af466a50a5eb0374d895071f647393ff6da241b\",\"35b4f28cb601668e6f89cb6eace3a2d845e700f718182bdf237f17811ead81a6\"],[\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\"]]}", "recipients": [{ "address": "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}, { "address": "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E", "amount": "10"}, { "address": "4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi", "amount": "10"}, { "address": "8qbHbw2BbbTHBW1sbeqakYXVKRQM8Ne7pLK7m6CVfeR", "amount": "10"}]}"#)
+            .expect(2)
+            .create();
+
+        let eligibility = || Eligibility {
+            cid: "attested_cid".to_string(),
+            address: "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: true,
+        };
+        let response = handler_at(eligibility(), 0, Some(&keypair)).await;
+        assert_eq!(response.status, 200);
+        assert!(response.cache_control.is_none());
+
+        let attestation: attestation::Attestation =
+            serde_json::from_value(response.message["attestation"].clone()).unwrap();
+        assert_eq!(attestation.public_key, keypair.pubkey().to_string());
+        assert!(attestation::verify(&attestation));
+
+        let unsigned = handler_at(eligibility(), 0, None).await;
+        assert_eq!(unsigned.status, 500);
+        assert_eq!(unsigned.message["code"], "ATTESTATION_UNAVAILABLE");
         mock.assert();
         drop(server);
     }
@@ -107,9 +323,233 @@ mod tests {
         let validity = Eligibility {
             cid: "invalid_cid".to_string(),
             address: "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
+        };
+        let response = handler(validity).await;
+        assert_eq!(response.status, 500);
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_not_found_response() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/unpinned_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(404)
+            .with_body("Not Found")
+            .create();
+
+        let validity = Eligibility {
+            cid: "unpinned_cid".to_string(),
+            address: "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            attest: false,
         };
         let response = handler(validity).await;
+        assert_eq!(response.status, 404);
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_honors_stored_leaf_order() {
+        use crate::{
+            data_objects::dto::RecipientDto,
+            utils::solana_merkle::{HashAlgorithm, LeafOrder, MerkleLeaf},
+        };
+
+        let leaves = vec![
+            MerkleLeaf { index: 0, recipient: "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y".to_string(), amount: 100 },
+            MerkleLeaf { index: 1, recipient: "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E".to_string(), amount: 200 },
+        ];
+        let tree =
+            MerkleTree::build_tree(leaves.clone(), "amount,index,recipient".parse().unwrap(), HashAlgorithm::default());
+        let campaign = PersistentCampaignDto {
+            total_amount: "300".to_string(),
+            number_of_recipients: 2,
+            root: tree.root_hex(),
+            merkle_tree: tree.dump().unwrap(),
+            merkle_version: None,
+            recipients: leaves
+                .iter()
+                .map(|l| RecipientDto { address: l.recipient.clone(), amount: l.amount.to_string(), label: None })
+                .collect(),
+            params: None,
+            expires_at: None,
+        };
+
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/leaf_order_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(serde_json::to_string(&campaign).unwrap())
+            .create();
+
+        let eligibility =
+            Eligibility { cid: "leaf_order_cid".to_string(), address: leaves[1].recipient.clone(), attest: false };
+        let response = handler(eligibility).await;
+        assert_eq!(response.status, 200);
+
+        let proof: Vec<String> = serde_json::from_value(response.message["proof"].clone()).unwrap();
+        assert!(tree.verify_proof(&leaves[1], &proof));
+        let default_order_tree = MerkleTree { leaf_order: LeafOrder::default(), ..tree };
+        assert!(!default_order_tree.verify_proof(&leaves[1], &proof));
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_rejects_inconsistent_campaign() {
+        use crate::{
+            data_objects::dto::RecipientDto,
+            utils::solana_merkle::{HashAlgorithm, LeafOrder, MerkleLeaf},
+        };
+
+        let recipients =
+            ["9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y", "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E"];
+        // The tree only holds the first recipient
+        let leaf = MerkleLeaf { index: 0, recipient: recipients[0].to_string(), amount: 100 };
+        let tree = MerkleTree::build_tree(vec![leaf], LeafOrder::default(), HashAlgorithm::default());
+        let campaign = PersistentCampaignDto {
+            total_amount: "200".to_string(),
+            number_of_recipients: 2,
+            root: tree.root_hex(),
+            merkle_tree: tree.dump().unwrap(),
+            merkle_version: None,
+            recipients: recipients
+                .iter()
+                .map(|address| RecipientDto { address: address.to_string(), amount: "100".to_string(), label: None })
+                .collect(),
+            params: None,
+            expires_at: None,
+        };
+
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/inconsistent_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(serde_json::to_string(&campaign).unwrap())
+            .create();
+
+        let eligibility =
+            Eligibility { cid: "inconsistent_cid".to_string(), address: recipients[1].to_string(), attest: false };
+        let response = handler(eligibility).await;
         assert_eq!(response.status, 500);
+        assert_eq!(response.message["code"], "MALFORMED_CAMPAIGN");
+        assert_eq!(response.message["message"], "Campaign data inconsistent");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn exists_status_codes() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let campaign = server
+            .mock("GET", "/head_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "20", "number_of_recipients": 2, "merkle_tree": "{\"root\":\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\",\"tree\":[[\"410c2c7cb39bf8cc15b1e22fc5b9c26be08465174ccef0be090d9d9df86d03ad\",\"1f605d6b20676921f61532c385082aae4619ba91dfb83c71bf1bc43678626119\"],[\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\"]]}", "recipients": [{ "address": "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y", "amount": "10"}, { "address": "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E", "amount": "10"}]}"#)
+            .expect(2)
+            .create();
+        let unreachable = server
+            .mock("GET", "/bad_head_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(500)
+            .with_body(r#"{"message": "Bad request"}"#)
+            .create();
+
+        let eligibility = |cid: &str, address: &str| Eligibility {
+            cid: cid.to_string(),
+            address: address.to_string(),
+            attest: false,
+        };
+        let eligible = exists_at(&eligibility("head_cid", "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y"), 0).await;
+        let ineligible = exists_at(&eligibility("head_cid", "4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi"), 0).await;
+        let bad_cid = exists_at(&eligibility("bad_head_cid", "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y"), 0).await;
+
+        assert_eq!(eligible.status, 200);
+        assert!(eligible.message.is_null());
+        assert_eq!(ineligible.status, 404);
+        assert_eq!(bad_cid.status, 502);
+        assert!(bad_cid.cache_control.is_none());
+        campaign.assert();
+        unreachable.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn head_status_matches_get_for_unreadable_campaigns() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let recipients =
+            r#""recipients": [{ "address": "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y", "amount": "10"}]"#;
+        let unsupported = server
+            .mock("GET", "/future_version_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(format!(r#"{{"root": "root", "total_amount": "10", "number_of_recipients": 1, "merkle_version": "solana-v99", "merkle_tree": "", {recipients}}}"#))
+            .expect(2)
+            .create();
+        let unreadable = server
+            .mock("GET", "/unreadable_tree_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(format!(r#"{{"root": "root", "total_amount": "10", "number_of_recipients": 1, "merkle_tree": "", {recipients}}}"#))
+            .expect(2)
+            .create();
+
+        for cid in ["future_version_cid", "unreadable_tree_cid"] {
+            let eligibility = || Eligibility {
+                cid: cid.to_string(),
+                address: "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y".to_string(),
+                attest: false,
+            };
+            let get = handler(eligibility()).await;
+            let bodyless = head(eligibility(), 0).await;
+            assert_eq!(get.status, 500, "{cid}");
+            assert_eq!(bodyless.status, get.status, "{cid}");
+            assert!(bodyless.message.is_null());
+        }
+        unsupported.assert();
+        unreadable.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn head_etag_matches_get() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        // One download per HEAD answer, shared with the proof behind its ETag, and one for the GET answer
+        let mock = server
+            .mock("GET", "/head_etag_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "10", "number_of_recipients": 4, "merkle_tree": "{\"root\":\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\",\"tree\":[[\"410c2c7cb39bf8cc15b1e22fc5b9c26be08465174ccef0be090d9d9df86d03ad\",\"1f605d6b20676921f61532c385082aae4619ba91dfb83c71bf1bc43678626119\",\"158db4f6ff3d0547cef89e6125c2c39052d1b2b288a8db9742958cc1b80fcb43\",\"77a70b41a193dc0a1e9a07dca4a3f2fb40c37282a6d18849dd7af36b684590ca\"],[\"a6a693f5474548569bfd931d4af466a50a5eb0374d895071f647393ff6da241b\",\"35b4f28cb601668e6f89cb6eace3a2d845e700f718182bdf237f17811ead81a6\"],[\"e51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93\"]]}", "recipients": [{ "address": "0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}, { "address": "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E", "amount": "10"}, { "address": "4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi", "amount": "10"}, { "address": "8qbHbw2BbbTHBW1sbeqakYXVKRQM8Ne7pLK7m6CVfeR", "amount": "10"}]}"#)
+            .expect(3)
+            .create();
+
+        let eligibility = || Eligibility {
+            cid: "head_etag_cid".to_string(),
+            address: "2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E".to_string(),
+            attest: false,
+        };
+        let bodyless = response::not_modified_if(head(eligibility(), 0).await, None);
+        assert_eq!(bodyless.status, 200);
+        assert!(bodyless.message.is_null());
+        assert!(bodyless.cache_control.is_some());
+
+        let get = response::not_modified_if(handler(eligibility()).await, None);
+        assert_eq!(bodyless.etag, get.etag);
+
+        let Some(etag) = bodyless.etag else {
+            panic!("an immutable HEAD answer should have an ETag");
+        };
+        let cached = response::not_modified_if(head(eligibility(), 0).await, Some(&etag));
+        assert_eq!(cached.status, 304);
+        assert_eq!(cached.etag, Some(etag));
         mock.assert();
         drop(server);
     }
@@ -0,0 +1,210 @@
+use crate::{
+    controller::eligibility_solana,
+    data_objects::{
+        dto::MultiEligibility,
+        query_param::Eligibility,
+        response::{self, ErrorCode, GeneralErrorResponse, MultiEligibilityEntry},
+    },
+    services::ipfs::with_download_cache,
+    utils::{
+        auth,
+        request::{self, UploadError},
+    },
+};
+use futures::stream::{self, StreamExt};
+
+use serde_json::json;
+use vercel_runtime as Vercel;
+
+/// Largest number of campaigns checked in one request
+pub const MAX_CIDS: usize = 50;
+
+/// Campaigns downloaded at the same time
+const CONCURRENT_DOWNLOADS: usize = 8;
+
+/// Multi-campaign eligibility request common handler. It checks the address against every campaign concurrently,
+/// downloading each CID once, and answers with one entry per CID in request order. A campaign that cannot be read is
+/// reported in its entry and does not fail the others.
+async fn handler(multi: MultiEligibility) -> response::R {
+    if multi.cids.len() > MAX_CIDS {
+        return response::error(400, ErrorCode::BatchTooLarge, format!("A request can check at most {MAX_CIDS} CIDs"));
+    }
+
+    let address = multi.address;
+    let mut entries: Vec<(usize, MultiEligibilityEntry)> = with_download_cache(
+        stream::iter(multi.cids.into_iter().enumerate())
+            .map(|(position, cid)| {
+                let eligibility = Eligibility { cid: cid.clone(), address: address.clone(), attest: false };
+                async move { (position, entry(cid, eligibility_solana::handler(eligibility).await)) }
+            })
+            .buffer_unordered(CONCURRENT_DOWNLOADS)
+            .collect(),
+    )
+    .await;
+    entries.sort_by_key(|(position, _)| *position);
+
+    let entries: Vec<MultiEligibilityEntry> = entries.into_iter().map(|(_, entry)| entry).collect();
+    response::ok(json!(entries))
+}
+
+/// Read the answer of the single campaign endpoint as an entry of the multi-campaign one
+fn entry(cid: String, response: response::R) -> MultiEligibilityEntry {
+    let message = response.message;
+    let mut entry = MultiEligibilityEntry { cid, eligible: false, index: None, amount: None, proof: None, error: None };
+
+    match response.status {
+        // Either a proof, or an expired campaign
+        200 => {
+            entry.proof = serde_json::from_value(message["proof"].clone()).ok();
+            entry.eligible = entry.proof.is_some();
+            entry.index = message["index"].as_u64().map(|index| index as usize);
+            entry.amount = message["amount"].as_str().map(str::to_string);
+        }
+        400 if message["code"] == json!(ErrorCode::AddressNotEligible) => {}
+        _ => entry.error = Some(message["message"].as_str().unwrap_or("Campaign could not be checked").to_string()),
+    }
+    entry
+}
+
+/// Vercel specific handler for the multi-campaign Solana eligibility endpoint
+#[utoipa::path(
+    post,
+    path = "/api/eligibility_solana/multi",
+    summary = "Proofs of a recipient across several Solana campaigns",
+    request_body(content = MultiEligibility, content_type = "application/json"),
+    responses(
+        (status = 200, description = "One entry per CID, in request order", body = [MultiEligibilityEntry]),
+        (status = 400, description = "Invalid JSON body, or more than `MAX_CIDS` CIDs", body = GeneralErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 405, description = "Not a POST request", body = GeneralErrorResponse),
+        (status = 413, description = "Body larger than `MAX_UPLOAD_BYTES`", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    if req.method() != http::Method::POST {
+        return response::to_vercel_error(405, ErrorCode::MethodNotAllowed, "Only POST is supported on this endpoint");
+    }
+
+    // ------------------------------------------------------------
+    // Extract the JSON body: address and cids
+    // ------------------------------------------------------------
+
+    let body = match request::body(req).await {
+        Ok(body) => body,
+        Err(UploadError::TooLarge(limit)) => {
+            return response::to_vercel_error(413, ErrorCode::PayloadTooLarge, format!("File exceeds {limit} bytes"))
+        }
+        Err(UploadError::Invalid(error)) => return response::to_vercel_error(400, ErrorCode::InvalidFile, error),
+    };
+
+    let multi: MultiEligibility = match serde_json::from_slice(&body) {
+        Ok(multi) => multi,
+        Err(error) => {
+            return response::to_vercel_error(400, ErrorCode::InvalidFile, format!("Invalid JSON body: {error}"));
+        }
+    };
+
+    response::to_vercel(handler(multi).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_objects::dto::{PersistentCampaignDto, RecipientDto},
+        utils::{
+            async_test::{setup_env_vars, SERVER},
+            solana_merkle::{HashAlgorithm, LeafOrder, MerkleLeaf, MerkleTree},
+        },
+    };
+
+    /// A pinned campaign of the given recipients, each with an amount of 100
+    fn campaign(recipients: &[&str]) -> String {
+        let leaves: Vec<MerkleLeaf> = recipients
+            .iter()
+            .enumerate()
+            .map(|(index, recipient)| MerkleLeaf { index: index as u32, recipient: recipient.to_string(), amount: 100 })
+            .collect();
+        let tree = MerkleTree::build_tree(leaves, LeafOrder::default(), HashAlgorithm::default());
+        let campaign = PersistentCampaignDto {
+            total_amount: (100 * recipients.len()).to_string(),
+            number_of_recipients: recipients.len() as i32,
+            root: tree.root_hex(),
+            merkle_tree: tree.dump().unwrap(),
+            merkle_version: None,
+            recipients: recipients
+                .iter()
+                .map(|address| RecipientDto { address: address.to_string(), amount: "100".to_string(), label: None })
+                .collect(),
+            params: None,
+            expires_at: None,
+        };
+        serde_json::to_string(&campaign).unwrap()
+    }
+
+    #[tokio::test]
+    async fn handler_reports_each_campaign() {
+        let address = "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y";
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let member = server
+            .mock("GET", "/multi_member_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(campaign(&["2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E", address]))
+            .create();
+        let other = server
+            .mock("GET", "/multi_other_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(campaign(&["2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E"]))
+            .create();
+        let unreachable = server
+            .mock("GET", "/multi_unreachable_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(500)
+            .create();
+
+        let multi = MultiEligibility {
+            address: address.to_string(),
+            cids: vec![
+                "multi_member_cid".to_string(),
+                "multi_other_cid".to_string(),
+                "multi_unreachable_cid".to_string(),
+            ],
+        };
+        let response = handler(multi).await;
+
+        assert_eq!(response.status, 200);
+        let entries = response.message.as_array().unwrap();
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0]["cid"], "multi_member_cid");
+        assert_eq!(entries[0]["eligible"], true);
+        assert_eq!(entries[0]["index"], 1);
+        assert_eq!(entries[0]["amount"], "100");
+        assert_eq!(entries[0]["proof"].as_array().unwrap().len(), 1);
+
+        assert_eq!(entries[1], json!({ "cid": "multi_other_cid", "eligible": false }));
+
+        assert_eq!(entries[2]["cid"], "multi_unreachable_cid");
+        assert_eq!(entries[2]["eligible"], false);
+        assert!(entries[2]["error"].is_string());
+        member.assert();
+        other.assert();
+        unreachable.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_limits_cids() {
+        let multi = MultiEligibility { address: String::new(), cids: vec![String::new(); MAX_CIDS + 1] };
+        let response = handler(multi).await;
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["code"], "BATCH_TOO_LARGE");
+    }
+}
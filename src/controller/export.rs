@@ -0,0 +1,165 @@
+use crate::{
+    data_objects::{
+        dto::{PersistentCampaignDto, RecipientDto},
+        query_param::Export,
+        response::{self, ErrorCode, GeneralErrorResponse},
+    },
+    services::ipfs::{download_from_ipfs, IpfsError},
+    utils::{auth, request},
+};
+
+use vercel_runtime as Vercel;
+
+/// Export request common handler. It downloads the campaign from IPFS so its recipients can be written back as CSV.
+pub async fn handler(export: &Export) -> Result<PersistentCampaignDto, response::R> {
+    match download_from_ipfs::<PersistentCampaignDto>(&export.cid).await {
+        Ok(ipfs_data) => Ok(ipfs_data),
+        Err(IpfsError::NotFound) => {
+            Err(response::error(404, ErrorCode::CampaignNotFound, "The provided CID is not pinned"))
+        }
+        Err(_) => Err(response::error(
+            500,
+            ErrorCode::IpfsDownloadFailed,
+            "There was a problem processing your request: Bad CID provided",
+        )),
+    }
+}
+
+/// Encode a single CSV record, quoting the fields when needed
+fn csv_row(fields: &[&str]) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    if writer.write_record(fields).is_err() {
+        return vec![];
+    }
+    writer.into_inner().unwrap_or_default()
+}
+
+/// Lazily encode the recipients as `address,amount` CSV rows, header first. A `label` column is added when any
+/// recipient has a label.
+pub fn csv_rows(recipients: Vec<RecipientDto>) -> impl Iterator<Item = Vec<u8>> + Send + Sync + 'static {
+    let labelled = recipients.iter().any(|recipient| recipient.label.is_some());
+    let header: &[&str] = if labelled { &["address", "amount", "label"] } else { &["address", "amount"] };
+
+    std::iter::once(csv_row(header)).chain(recipients.into_iter().map(move |recipient| {
+        let fields = [recipient.address.as_str(), &recipient.amount, recipient.label.as_deref().unwrap_or_default()];
+        csv_row(&fields[..header.len()])
+    }))
+}
+
+/// Vercel specific handler for the export endpoint
+#[utoipa::path(
+    get,
+    path = "/api/export",
+    summary = "Campaign recipients as an `address,amount` CSV download",
+    params(Export),
+    responses(
+        (status = 200, description = "CSV attachment", content_type = "text/csv", body = String),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 404, description = "The CID is not pinned", body = GeneralErrorResponse),
+        (status = 500, description = "The campaign could not be read", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: cid
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    // ------------------------------------------------------------
+    // Format arguments for the generic handler
+    // ------------------------------------------------------------
+
+    let fallback = String::new();
+    let params = Export { cid: query.get("cid").unwrap_or(&fallback).clone() };
+
+    match handler(&params).await {
+        Ok(ipfs_data) => response::to_vercel_csv(&format!("{}.csv", params.cid), csv_rows(ipfs_data.recipients)),
+        Err(error) => response::to_vercel(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::async_test::{setup_env_vars, SERVER};
+
+    #[tokio::test]
+    async fn handler_exports_recipients_as_csv() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/export_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "root", "total_amount": "30", "number_of_recipients": 2, "merkle_tree": "tree", "recipients": [{ "address": "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}, { "address": "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc", "amount": "20"}]}"#)
+            .create();
+
+        let params = Export { cid: "export_cid".to_string() };
+        let Ok(ipfs_data) = handler(&params).await else {
+            panic!("the campaign should be downloaded");
+        };
+        let csv = String::from_utf8(csv_rows(ipfs_data.recipients).flatten().collect()).unwrap();
+
+        assert_eq!(
+            csv,
+            "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,10\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,20\n"
+        );
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_not_found_response() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock =
+            server.mock("GET", "/missing_cid?pinataGatewayToken=mock_pinata_access_token").with_status(404).create();
+
+        let params = Export { cid: "missing_cid".to_string() };
+        let Err(response) = handler(&params).await else {
+            panic!("the campaign should not be found");
+        };
+
+        assert_eq!(response.status, 404);
+        mock.assert();
+        drop(server);
+    }
+
+    #[test]
+    fn csv_rows_quote_unsafe_fields() {
+        let recipients = vec![RecipientDto { address: "a,b".to_string(), amount: "1".to_string(), label: None }];
+        let rows: Vec<_> = csv_rows(recipients).collect();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], b"\"a,b\",1\n");
+    }
+
+    #[test]
+    fn csv_rows_keep_amount_strings() {
+        let amount = "123456789123456789012345678";
+        let recipients = vec![RecipientDto {
+            address: "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+            amount: amount.to_string(),
+            label: None,
+        }];
+        let rows: Vec<_> = csv_rows(recipients).collect();
+
+        assert_eq!(rows[1], format!("0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,{amount}\n").into_bytes());
+    }
+
+    #[test]
+    fn csv_rows_add_label_column() {
+        let recipients = vec![
+            RecipientDto { address: "0xa".to_string(), amount: "1".to_string(), label: Some("team".to_string()) },
+            RecipientDto { address: "0xb".to_string(), amount: "2".to_string(), label: None },
+        ];
+        let csv = String::from_utf8(csv_rows(recipients).flatten().collect()).unwrap();
+
+        assert_eq!(csv, "address,amount,label\n0xa,1,team\n0xb,2,\n");
+    }
+}
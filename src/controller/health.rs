@@ -1,19 +1,108 @@
-use crate::data_objects::response;
+use crate::{
+    data_objects::{
+        query_param::Health,
+        response::{self, HealthResponse},
+    },
+    services::ipfs::check_pinata_auth,
+    utils::request,
+};
 use serde_json::json;
 
 use vercel_runtime as Vercel;
 
-/// Health request common handler. Returns a hardcoded message to signal that the server is up.
-pub async fn handler() -> response::R {
-    response::ok(json!({
-        "status": "success",
-        "message": "Server up and running",
+/// Health request common handler. Returns a hardcoded message to signal that the server is up. When `deep` is set it
+/// also checks that Pinata is reachable and answers 503 if it isn't.
+pub async fn handler(health: Health) -> response::R {
+    if !health.deep {
+        return response::ok(json!(HealthResponse {
+            status: "success".to_string(),
+            message: "Server up and running".to_string(),
+            ipfs: None,
+        }));
+    }
+
+    if let Err(error) = check_pinata_auth().await {
+        tracing::error!("{error}");
+        return response::service_unavailable(json!(HealthResponse {
+            status: "error".to_string(),
+            message: "A dependency is unavailable".to_string(),
+            ipfs: Some("error".to_string()),
+        }));
+    }
+
+    response::ok(json!(HealthResponse {
+        status: "success".to_string(),
+        message: "Server up and running".to_string(),
+        ipfs: Some("ok".to_string()),
     }))
 }
 
 /// Vercel specific handler for the health endpoint
-pub async fn handler_to_vercel() -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
-    let result = handler().await;
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    summary = "Liveness probe, checking Pinata too when `deep` is set",
+    params(Health),
+    responses(
+        (status = 200, description = "The server is up", body = HealthResponse),
+        (status = 503, description = "Pinata is unreachable", body = HealthResponse),
+    ),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: deep
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    // ------------------------------------------------------------
+    // Format arguments for the generic handler
+    // ------------------------------------------------------------
+
+    let params = Health { deep: query.get("deep").is_some_and(|deep| deep == "true") };
+
+    response::to_vercel(handler(params).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::async_test::{setup_env_vars, SERVER};
+
+    #[tokio::test]
+    async fn shallow_health_does_not_call_dependencies() {
+        let response = handler(Health { deep: false }).await;
+        assert_eq!(response.status, 200);
+        assert!(response.message.get("ipfs").is_none());
+    }
+
+    #[tokio::test]
+    async fn deep_health_reports_ipfs_ok() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/data/testAuthentication")
+            .with_status(200)
+            .with_body(r#"{"message": "Congratulations! You are communicating with the Pinata API!"}"#)
+            .create();
+
+        let response = handler(Health { deep: true }).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["ipfs"], "ok");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn deep_health_reports_ipfs_error() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server.mock("GET", "/data/testAuthentication").with_status(401).create();
 
-    response::to_vercel(result)
+        let response = handler(Health { deep: true }).await;
+        assert_eq!(response.status, 503);
+        assert_eq!(response.message["ipfs"], "error");
+        mock.assert();
+        drop(server);
+    }
 }
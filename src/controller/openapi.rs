@@ -0,0 +1,143 @@
+use crate::{
+    controller::{
+        append, attestation_key, batch, campaign, campaign_exists, claim_url, create, create_solana,
+        create_solana_json, eligibility, eligibility_solana, eligibility_solana_multi, export, health, pin_verified,
+        proofs, root, sign, validate_solana, validate_stream, validity,
+    },
+    data_objects::response,
+};
+use serde_json::json;
+use utoipa::{
+    openapi::{
+        security::{Http, HttpAuthScheme, SecurityScheme},
+        OpenApi as OpenApiDocument,
+    },
+    Modify, OpenApi,
+};
+
+use vercel_runtime as Vercel;
+
+/// Registers the `Authorization: Bearer <token>` scheme referenced by the protected endpoints
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("bearer", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+    }
+}
+
+/// OpenAPI description of the campaign endpoints, generated from their query parameter and response types
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Sablier Merkle API"),
+    paths(
+        append::handler_to_vercel,
+        attestation_key::handler_to_vercel,
+        batch::handler_to_vercel,
+        campaign::handler_to_vercel,
+        campaign_exists::handler_to_vercel,
+        claim_url::handler_to_vercel,
+        create::handler_to_vercel,
+        create_solana::handler_to_vercel,
+        create_solana_json::handler_to_vercel,
+        eligibility::handler_to_vercel,
+        eligibility_solana::handler_to_vercel,
+        eligibility_solana_multi::handler_to_vercel,
+        export::handler_to_vercel,
+        health::handler_to_vercel,
+        handler_to_vercel,
+        pin_verified::handler_to_vercel,
+        proofs::handler_to_vercel,
+        root::handler_to_vercel,
+        sign::handler_to_vercel,
+        validate_solana::handler_to_vercel,
+        validate_stream::handler_to_vercel,
+        validity::handler_to_vercel,
+    ),
+    modifiers(&BearerAuth)
+)]
+pub struct ApiDoc;
+
+/// OpenAPI request common handler. Returns the generated OpenAPI document.
+pub fn handler() -> response::R {
+    response::ok(json!(ApiDoc::openapi()))
+}
+
+/// Vercel specific handler for the openapi endpoint
+#[utoipa::path(
+    get,
+    path = "/api/openapi",
+    summary = "This OpenAPI document, also served at `/api/openapi.json`",
+    responses((status = 200, description = "The OpenAPI document", body = Object)),
+)]
+pub async fn handler_to_vercel(_req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    response::to_vercel(handler())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::collections::{BTreeSet, HashMap};
+
+    /// Public path of every `api/*.rs` function: `/api/<name>`, or the source of the `vercel.json` rewrite to it
+    fn deployed_paths() -> BTreeSet<String> {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let vercel: Value = serde_json::from_str(&std::fs::read_to_string(root.join("vercel.json")).unwrap()).unwrap();
+        let rewrites: HashMap<&str, &str> = vercel["rewrites"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rewrite| (rewrite["destination"].as_str().unwrap(), rewrite["source"].as_str().unwrap()))
+            .collect();
+
+        std::fs::read_dir(root.join("api"))
+            .unwrap()
+            .map(|entry| {
+                let name = entry.unwrap().path().file_stem().unwrap().to_string_lossy().into_owned();
+                let path = format!("/api/{name}");
+                // `openapi` is rewritten from `openapi.json` too, but it is described at its own path
+                match rewrites.get(path.as_str()) {
+                    Some(source) if !source.ends_with(".json") => source.to_string(),
+                    _ => path,
+                }
+            })
+            .collect()
+    }
+
+    /// Collect every `$ref` of the document
+    fn refs<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    found.push(reference);
+                }
+                map.values().for_each(|v| refs(v, found));
+            }
+            Value::Array(items) => items.iter().for_each(|v| refs(v, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn document_parses_and_references_resolve() {
+        let response = handler();
+        assert_eq!(response.status, 200);
+
+        let document: OpenApiDocument = serde_json::from_value(response.message.clone()).unwrap();
+        let paths: BTreeSet<String> = document.paths.paths.keys().cloned().collect();
+        assert_eq!(paths, deployed_paths());
+
+        let mut found = Vec::new();
+        refs(&response.message, &mut found);
+        assert!(found.contains(&"#/components/schemas/UploadSuccessResponse"));
+        for reference in found {
+            let Some(name) = reference.strip_prefix("#/components/schemas/") else {
+                panic!("unexpected reference {reference}");
+            };
+            assert!(response.message["components"]["schemas"].get(name).is_some(), "{name} is not defined");
+        }
+        assert!(response.message["components"]["securitySchemes"].get("bearer").is_some());
+    }
+}
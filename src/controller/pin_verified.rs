@@ -0,0 +1,175 @@
+use crate::{
+    controller::create::publish,
+    csv_campaign_parser::{CampaignCsvParsed, ParseOptions},
+    data_objects::{
+        dto::VerifiedCampaignUpload,
+        response::{self, ErrorCode, GeneralErrorResponse, UploadSuccessResponse, ValidationErrorResponse},
+    },
+    utils::{
+        auth,
+        build_pool::BUILDS,
+        csv_validator::AddressType,
+        evm_merkle::build_tree,
+        request::{self, UploadError},
+    },
+};
+use merkle_tree_rs::standard::StandardMerkleTreeData;
+
+use serde_json::json;
+use vercel_runtime as Vercel;
+
+/// Verified pin request common handler. It rebuilds the merkle tree from the recipients and pins the campaign only if
+/// the rebuilt tree has the root the client computed. The pinned tree is the rebuilt one, the client tree is never
+/// trusted.
+async fn handler(upload: VerifiedCampaignUpload) -> response::R {
+    let decimals = upload.decimals.into();
+    if let Err(error) = AddressType::Ethereum.check_decimals(decimals) {
+        return response::error(400, ErrorCode::InvalidDecimals, error);
+    }
+    if serde_json::from_str::<StandardMerkleTreeData>(&upload.merkle_tree).is_err() {
+        return response::error(400, ErrorCode::InvalidFile, "Malformed merkle tree");
+    }
+    let Ok(_build) = BUILDS.acquire().await else {
+        return response::error(503, ErrorCode::ServerBusy, "Server busy, retry later");
+    };
+
+    let parsed = CampaignCsvParsed::build_from_recipients(
+        &upload.recipients,
+        decimals,
+        AddressType::Ethereum,
+        &ParseOptions::default(),
+    );
+    if !parsed.validation_errors.is_empty() {
+        let response_json = json!(ValidationErrorResponse {
+            status: "Invalid recipients.".to_string(),
+            code: ErrorCode::InvalidRecipients,
+            errors: parsed.validation_errors,
+        });
+
+        return response::bad_request(response_json);
+    }
+
+    let tree = build_tree(&parsed.records);
+    if !tree.root().eq_ignore_ascii_case(&upload.root) {
+        return response::error(400, ErrorCode::RootMismatch, "Provided root does not match recipients");
+    }
+
+    publish(&parsed, &tree, decimals, false, None, None).await
+}
+
+/// Vercel specific handler for the verified pin endpoint
+#[utoipa::path(
+    post,
+    path = "/api/pin_verified",
+    summary = "Pin an EVM campaign built by the client, once its root is checked against the recipients",
+    request_body(content = VerifiedCampaignUpload, content_type = "application/json"),
+    responses(
+        (status = 200, description = "Campaign pinned", body = UploadSuccessResponse),
+        (status = 400, description = "Invalid body or recipients, or a root that does not match them", body = ValidationErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 413, description = "Body larger than `MAX_UPLOAD_BYTES`", body = GeneralErrorResponse),
+        (status = 500, description = "IPFS upload failed", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    if req.method() != http::Method::POST {
+        return response::to_vercel_error(405, ErrorCode::MethodNotAllowed, "Only POST is supported on this endpoint");
+    }
+
+    // ------------------------------------------------------------
+    // Extract the JSON body: decimals, recipients, root and merkle_tree
+    // ------------------------------------------------------------
+
+    let is_json = req
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(';').next() == Some("application/json"));
+    if !is_json {
+        return response::to_vercel_error(400, ErrorCode::InvalidFile, "Invalid content type header");
+    }
+
+    let body = match request::body(req).await {
+        Ok(body) => body,
+        Err(UploadError::TooLarge(limit)) => {
+            return response::to_vercel_error(413, ErrorCode::PayloadTooLarge, format!("File exceeds {limit} bytes"))
+        }
+        Err(UploadError::Invalid(error)) => return response::to_vercel_error(400, ErrorCode::InvalidFile, error),
+    };
+
+    let upload: VerifiedCampaignUpload = match serde_json::from_slice(&body) {
+        Ok(upload) => upload,
+        Err(error) => {
+            return response::to_vercel_error(400, ErrorCode::InvalidFile, format!("Invalid JSON body: {error}"));
+        }
+    };
+
+    response::to_vercel(handler(upload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_objects::dto::RecipientDto,
+        services::ipfs::{with_store, InMemory},
+    };
+
+    /// An upload of two recipients whose client tree is the one the server builds
+    fn upload() -> VerifiedCampaignUpload {
+        let recipients = vec![
+            RecipientDto {
+                address: "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491".to_string(),
+                amount: "100.0".to_string(),
+                label: None,
+            },
+            RecipientDto {
+                address: "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc".to_string(),
+                amount: "200.0".to_string(),
+                label: None,
+            },
+        ];
+        let parsed =
+            CampaignCsvParsed::build_from_recipients(&recipients, 2, AddressType::Ethereum, &ParseOptions::default());
+        let tree = build_tree(&parsed.records);
+
+        VerifiedCampaignUpload {
+            decimals: 2,
+            recipients,
+            root: tree.root(),
+            merkle_tree: serde_json::to_string(&tree.dump()).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_pins_matching_root() {
+        let store = InMemory::default();
+        let upload = upload();
+        let root = upload.root.clone();
+
+        let response = with_store(store.clone(), handler(upload)).await;
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["root"], root);
+        assert!(store.contains(response.message["cid"].as_str().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn handler_rejects_mismatching_root() {
+        let mut upload = upload();
+        // Recipients other than the ones the client tree was built from
+        upload.recipients[1].amount = "300.0".to_string();
+
+        let response = with_store(InMemory::default(), handler(upload)).await;
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["code"], "ROOT_MISMATCH");
+        assert_eq!(response.message["message"], "Provided root does not match recipients");
+        assert!(response.message.get("cid").is_none());
+    }
+}
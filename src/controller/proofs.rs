@@ -0,0 +1,245 @@
+use crate::{
+    data_objects::{
+        dto::{PersistentCampaignDto, EVM_MERKLE_VERSION},
+        query_param::Proofs,
+        response::{self, ErrorCode, GeneralErrorResponse, ProofEntry},
+    },
+    services::ipfs::{download_from_ipfs, IpfsError},
+    utils::{auth, csv_validator::AddressType, request},
+};
+use merkle_tree_rs::standard::{LeafType, StandardMerkleTree, StandardMerkleTreeData};
+
+use vercel_runtime as Vercel;
+
+/// Proof entries a response can hold when the `MAX_PROOF_ENTRIES` env var is unset or invalid
+pub const DEFAULT_MAX_PROOF_ENTRIES: usize = 10_000;
+
+/// Largest number of proofs returned by one request, read from the `MAX_PROOF_ENTRIES` env var
+pub fn max_proof_entries() -> usize {
+    std::env::var("MAX_PROOF_ENTRIES").ok().and_then(|max| max.parse().ok()).unwrap_or(DEFAULT_MAX_PROOF_ENTRIES)
+}
+
+/// Proofs request common handler. It downloads the campaign from IPFS and returns an iterator that computes the proof
+/// of each recipient of the requested range only when it is reached, so the whole dump is never held in memory. A
+/// range of more than `max_entries` recipients is rejected, so the client pages through large campaigns.
+pub async fn handler(
+    proofs: &Proofs,
+    max_entries: usize,
+) -> Result<impl Iterator<Item = ProofEntry> + Send + Sync + 'static, response::R> {
+    let ipfs_data = match download_from_ipfs::<PersistentCampaignDto>(&proofs.cid).await {
+        Ok(ipfs_data) => ipfs_data,
+        Err(IpfsError::NotFound) => {
+            return Err(response::error(404, ErrorCode::CampaignNotFound, "The provided CID is not pinned"))
+        }
+        Err(_) => {
+            return Err(response::error(
+                500,
+                ErrorCode::IpfsDownloadFailed,
+                "There was a problem processing your request: Bad CID provided",
+            ))
+        }
+    };
+
+    if let Err(error) = ipfs_data.check_merkle_version(EVM_MERKLE_VERSION) {
+        return Err(response::error(500, ErrorCode::UnsupportedMerkleVersion, error));
+    }
+    let entries = ipfs_data.recipients.len().saturating_sub(proofs.offset).min(proofs.limit.unwrap_or(usize::MAX));
+    if entries > max_entries {
+        return Err(response::error(
+            400,
+            ErrorCode::ResponseTooLarge,
+            format!(
+                "The request covers {entries} recipients, more than the {max_entries} a response can hold. Page \
+                 through them with the offset and limit query parameters, at most {max_entries} at a time"
+            ),
+        ));
+    }
+    let Ok(tree_data) = serde_json::from_str::<StandardMerkleTreeData>(&ipfs_data.merkle_tree) else {
+        return Err(response::error(500, ErrorCode::MalformedCampaign, "Malformed merkle tree in IPFS data"));
+    };
+
+    let tree = StandardMerkleTree::load(tree_data);
+
+    let recipients = ipfs_data.recipients.into_iter().enumerate().skip(proofs.offset).take(entries);
+    Ok(recipients.map(move |(index, recipient)| ProofEntry {
+        index,
+        address: AddressType::Ethereum.canonical_address(&recipient.address),
+        amount: recipient.amount,
+        proof: tree.get_proof(LeafType::Number(index)),
+    }))
+}
+
+/// Vercel specific handler for the proofs endpoint
+#[utoipa::path(
+    get,
+    path = "/api/proofs",
+    summary = "Proof of every recipient of a EVM campaign, streamed as one JSON array",
+    params(Proofs),
+    responses(
+        (status = 200, description = "One entry per recipient of the range, in index order", body = [ProofEntry]),
+        (status = 400, description = "Bad offset or limit, or a range of more than `MAX_PROOF_ENTRIES` recipients", body = GeneralErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 404, description = "The CID is not pinned", body = GeneralErrorResponse),
+        (status = 500, description = "The campaign could not be read", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: cid, offset, limit
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    // ------------------------------------------------------------
+    // Format arguments for the generic handler
+    // ------------------------------------------------------------
+
+    let Ok(offset) = query.get("offset").map_or(Ok(0), |offset| offset.parse()) else {
+        return response::to_vercel_error(
+            400,
+            ErrorCode::InvalidQuery,
+            "The offset query parameter should be an index",
+        );
+    };
+    let Ok(limit) = query.get("limit").map(|limit| limit.parse()).transpose() else {
+        return response::to_vercel_error(400, ErrorCode::InvalidQuery, "The limit query parameter should be a count");
+    };
+    let fallback = String::new();
+    let params = Proofs { cid: query.get("cid").unwrap_or(&fallback).clone(), offset, limit };
+
+    match handler(&params, max_proof_entries()).await {
+        Ok(entries) => response::to_vercel_json_array(entries),
+        Err(error) => response::to_vercel(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        csv_campaign_parser::CampaignCsvRecord,
+        data_objects::dto::RecipientDto,
+        services::ipfs::{pin_json, with_store, InMemory},
+        utils::{
+            async_test::{setup_env_vars, SERVER},
+            evm_merkle::build_tree,
+        },
+    };
+    use sha3::{Digest, Keccak256};
+
+    /// Fold a proof into the root it implies, hashing each pair in sorted order like OpenZeppelin's `MerkleProof`
+    fn implied_root(leaf: &str, proof: &[String]) -> String {
+        let decode = |node: &str| hex::decode(node.trim_start_matches("0x")).unwrap();
+        let root = proof.iter().fold(decode(leaf), |hash, sibling| {
+            let mut pair = [hash, decode(sibling)];
+            pair.sort();
+            Keccak256::digest(pair.concat()).to_vec()
+        });
+        format!("0x{}", hex::encode(root))
+    }
+
+    /// The records of a campaign of five recipients, and the campaign pinned for them
+    fn campaign() -> (Vec<CampaignCsvRecord>, PersistentCampaignDto) {
+        let records: Vec<CampaignCsvRecord> = (0..5u128)
+            .map(|i| CampaignCsvRecord {
+                address: format!("0x{:040x}", 0xabc0 + i),
+                amount: 1000 * (i + 1),
+                label: None,
+            })
+            .collect();
+        let tree = build_tree(&records);
+        let campaign = PersistentCampaignDto {
+            total_amount: "15000".to_string(),
+            number_of_recipients: 5,
+            root: tree.root(),
+            merkle_tree: serde_json::to_string(&tree.dump()).unwrap(),
+            merkle_version: Some(EVM_MERKLE_VERSION.to_string()),
+            recipients: records
+                .iter()
+                .map(|r| RecipientDto { address: r.address.clone(), amount: r.amount.to_string(), label: None })
+                .collect(),
+            params: None,
+            expires_at: None,
+        };
+        (records, campaign)
+    }
+
+    #[tokio::test]
+    async fn handler_dumps_verifiable_proofs() {
+        let (records, campaign) = campaign();
+        let tree = build_tree(&records);
+
+        let entries: Vec<ProofEntry> = with_store(InMemory::default(), async {
+            let cid = pin_json(&campaign).await.unwrap();
+            let Ok(entries) = handler(&Proofs { cid, offset: 0, limit: None }, DEFAULT_MAX_PROOF_ENTRIES).await else {
+                panic!("the campaign should be downloaded");
+            };
+            entries.collect()
+        })
+        .await;
+
+        assert_eq!(entries.len(), records.len());
+        for (i, entry) in entries.iter().enumerate() {
+            assert_eq!(entry.index, i);
+            assert_eq!(entry.amount, records[i].amount.to_string());
+            let leaf = tree.leaf_hash(&[i.to_string(), entry.address.clone(), entry.amount.clone()]);
+            assert_eq!(implied_root(&leaf, &entry.proof), tree.root());
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_limits_entries() {
+        let (_, campaign) = campaign();
+        let proofs = |cid: &str, offset, limit| Proofs { cid: cid.to_string(), offset, limit };
+
+        let (whole, tail, page) = with_store(InMemory::default(), async {
+            let cid = pin_json(&campaign).await.unwrap();
+            let whole = handler(&proofs(&cid, 0, None), 2).await.err();
+            let tail = handler(&proofs(&cid, 3, None), 2).await.map(|entries| entries.count()).ok();
+            let page = handler(&proofs(&cid, 1, Some(2)), 2)
+                .await
+                .map(|entries| entries.map(|entry| entry.index).collect::<Vec<_>>())
+                .ok();
+            (whole, tail, page)
+        })
+        .await;
+
+        let Some(whole) = whole else {
+            panic!("five recipients should not fit in a response of two");
+        };
+        assert_eq!(whole.status, 400);
+        assert_eq!(whole.message["code"], "RESPONSE_TOO_LARGE");
+        assert_eq!(
+            whole.message["message"],
+            "The request covers 5 recipients, more than the 2 a response can hold. Page through them with the offset \
+             and limit query parameters, at most 2 at a time"
+        );
+        assert_eq!(tail, Some(2));
+        assert_eq!(page, Some(vec![1, 2]));
+    }
+
+    #[tokio::test]
+    async fn handler_not_found_response() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/missing_proofs_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(404)
+            .create();
+
+        let proofs = Proofs { cid: "missing_proofs_cid".to_string(), offset: 0, limit: None };
+        let Err(response) = handler(&proofs, DEFAULT_MAX_PROOF_ENTRIES).await else {
+            panic!("the campaign should not be found");
+        };
+
+        assert_eq!(response.status, 404);
+        assert_eq!(response.message["code"], "CAMPAIGN_NOT_FOUND");
+        mock.assert();
+        drop(server);
+    }
+}
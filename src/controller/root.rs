@@ -0,0 +1,108 @@
+use crate::{
+    data_objects::{
+        dto::CampaignRootDto,
+        query_param::Root,
+        response::{self, ErrorCode, GeneralErrorResponse, RootResponse},
+    },
+    services::ipfs::{download_from_ipfs, IpfsError},
+    utils::{auth, request},
+};
+
+use serde_json::json;
+
+use vercel_runtime as Vercel;
+
+/// Root request common handler. It downloads the campaign from IPFS and returns only its merkle root, skipping the
+/// recipients and the tree.
+pub async fn handler(root: Root) -> response::R {
+    let ipfs_data = match download_from_ipfs::<CampaignRootDto>(&root.cid).await {
+        Ok(ipfs_data) => ipfs_data,
+        Err(IpfsError::NotFound) => {
+            return response::error(404, ErrorCode::CampaignNotFound, "The provided CID is not pinned")
+        }
+        Err(_) => {
+            return response::error(
+                500,
+                ErrorCode::IpfsDownloadFailed,
+                "There was a problem processing your request: Bad CID provided",
+            )
+        }
+    };
+
+    response::ok_immutable(json!(&RootResponse { root: ipfs_data.root }))
+}
+
+/// Vercel specific handler for the root endpoint
+#[utoipa::path(
+    get,
+    path = "/api/root",
+    summary = "Merkle root of a pinned campaign",
+    params(Root),
+    responses(
+        (status = 200, description = "The campaign root", body = RootResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 404, description = "The CID is not pinned", body = GeneralErrorResponse),
+        (status = 500, description = "The campaign could not be read", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: cid
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    // ------------------------------------------------------------
+    // Format arguments for the generic handler
+    // ------------------------------------------------------------
+
+    let fallback = String::new();
+    let params = Root { cid: query.get("cid").unwrap_or(&fallback).clone() };
+
+    response::to_vercel(handler(params).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::async_test::{setup_env_vars, SERVER};
+
+    #[tokio::test]
+    async fn handler_returns_stored_root() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/root_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(r#"{"root": "0xstored_root", "total_amount": "30", "number_of_recipients": 2, "merkle_tree": "tree", "recipients": [{ "address": "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "amount": "10"}, { "address": "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc", "amount": "20"}]}"#)
+            .create();
+
+        let response = handler(Root { cid: "root_cid".to_string() }).await;
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message, json!({ "root": "0xstored_root" }));
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn handler_not_pinned() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("GET", "/missing_root_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(404)
+            .create();
+
+        let response = handler(Root { cid: "missing_root_cid".to_string() }).await;
+
+        assert_eq!(response.status, 404);
+        mock.assert();
+        drop(server);
+    }
+}
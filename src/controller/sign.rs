@@ -0,0 +1,120 @@
+use crate::{
+    data_objects::{
+        query_param::Sign,
+        response::{self, ErrorCode, GeneralErrorResponse, SignResponse},
+    },
+    utils::{access_token, auth, request},
+};
+
+use serde_json::json;
+
+use vercel_runtime as Vercel;
+
+/// Lifetime in seconds of a token requested without `ttl`
+pub const DEFAULT_TTL: i64 = 3600;
+
+/// Longest lifetime in seconds a token can be requested for. Tokens cannot be revoked, so they stay short-lived.
+pub const MAX_TTL: i64 = 86_400;
+
+/// Sign request common handler. It issues a token that grants access to the eligibility endpoints of one campaign
+/// until it expires, so the bearer token does not have to be shared. Tokens are signed with `secret`, the configured
+/// `access_token::secret()`, and none is issued without it.
+pub fn handler(sign: Sign, now: i64, secret: Option<&str>) -> response::R {
+    let Some(secret) = secret else {
+        return response::error(404, ErrorCode::AccessTokensUnavailable, "Access tokens are not enabled");
+    };
+    if sign.cid.is_empty() {
+        return response::error(400, ErrorCode::InvalidQuery, "The cid query parameter is required");
+    }
+
+    let ttl = match sign.ttl.as_str() {
+        "" => DEFAULT_TTL,
+        ttl => match ttl.parse::<i64>() {
+            Ok(ttl) if (1..=MAX_TTL).contains(&ttl) => ttl,
+            _ => {
+                return response::error(
+                    400,
+                    ErrorCode::InvalidQuery,
+                    format!("The ttl query parameter should be a number of seconds between 1 and {MAX_TTL}"),
+                )
+            }
+        },
+    };
+
+    let expires_at = now + ttl;
+    let token = access_token::sign(secret, &sign.cid, expires_at);
+
+    response::ok(json!(&SignResponse { token, expires_at }))
+}
+
+/// Vercel specific handler for the sign endpoint
+#[utoipa::path(
+    post,
+    path = "/api/sign",
+    summary = "Issue a short-lived token granting access to the eligibility endpoints of one campaign",
+    params(Sign),
+    responses(
+        (status = 200, description = "Token issued", body = SignResponse),
+        (status = 400, description = "Missing cid, or a ttl out of range", body = GeneralErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 404, description = "Access tokens are not enabled", body = GeneralErrorResponse),
+        (status = 405, description = "Not a POST request", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    if req.method() != http::Method::POST {
+        return response::to_vercel_error(405, ErrorCode::MethodNotAllowed, "Only POST is supported on this endpoint");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: cid, ttl
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+
+    // ------------------------------------------------------------
+    // Format arguments for the generic handler
+    // ------------------------------------------------------------
+
+    let fallback = String::new();
+    let params =
+        Sign { cid: query.get("cid").unwrap_or(&fallback).clone(), ttl: query.get("ttl").unwrap_or(&fallback).clone() };
+
+    response::to_vercel(handler(params, chrono::Utc::now().timestamp(), access_token::secret().as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn sign(cid: &str, ttl: &str) -> Sign {
+        Sign { cid: cid.to_string(), ttl: ttl.to_string() }
+    }
+
+    const SECRET: Option<&str> = Some("mock_access_token_secret");
+
+    #[test]
+    fn handler_issues_token() {
+        let response = handler(sign("sign_cid", "60"), NOW, SECRET);
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["expires_at"], NOW + 60);
+        let token = response.message["token"].as_str().unwrap();
+        assert!(access_token::verify("mock_access_token_secret", token, "sign_cid", NOW + 59));
+        assert!(!access_token::verify("mock_access_token_secret", token, "sign_cid", NOW + 60));
+
+        assert_eq!(handler(sign("sign_cid", ""), NOW, SECRET).message["expires_at"], NOW + DEFAULT_TTL);
+        assert_eq!(handler(sign("sign_cid", "0"), NOW, SECRET).status, 400);
+        assert_eq!(handler(sign("sign_cid", &(MAX_TTL + 1).to_string()), NOW, SECRET).status, 400);
+        assert_eq!(handler(sign("", "60"), NOW, SECRET).status, 400);
+
+        assert_eq!(handler(sign("sign_cid", "60"), NOW, None).message["code"], "ACCESS_TOKENS_UNAVAILABLE");
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    controller::create_solana::{campaign_dto, decimals, tree_options},
+    csv_campaign_parser::{CampaignCsvParsed, ParseOptions},
+    data_objects::{
+        dto::CampaignUpload,
+        response::{self, DryRunResponse, ErrorCode, GeneralErrorResponse},
+    },
+    utils::{
+        auth,
+        csv_validator::AddressType,
+        request::{self, UploadError},
+        solana_merkle::{HashAlgorithm, TreeOptions},
+    },
+};
+
+use serde_json::json;
+use vercel_runtime as Vercel;
+
+/// Dry-run validation request common handler. It validates the received data and builds the merkle tree exactly like
+/// `create_solana`, but returns the campaign summary instead of uploading it to ipfs.
+pub fn handler(decimals: usize, tree: TreeOptions, options: ParseOptions, buffer: &[u8]) -> response::R {
+    if let Err(error) = AddressType::Solana.check_decimals(decimals) {
+        return response::error(400, ErrorCode::InvalidDecimals, error);
+    }
+    if let Err(error) = request::check_csv(buffer) {
+        return response::error(400, ErrorCode::InvalidFile, error);
+    }
+
+    let rdr = options.reader(buffer);
+    let parsed_csv = match CampaignCsvParsed::build_with_options(rdr, decimals, AddressType::Solana, &options) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            return response::error(
+                500,
+                ErrorCode::CsvParsingFailed,
+                format!("There was a problem in csv file parsing process: {error}"),
+            );
+        }
+    };
+
+    if !parsed_csv.validation_errors.is_empty() {
+        return response::bad_request(json!(DryRunResponse {
+            valid: false,
+            root: None,
+            total: None,
+            recipients: None,
+            errors: parsed_csv.validation_errors,
+        }));
+    }
+
+    let dto = campaign_dto(&parsed_csv, decimals, &tree);
+
+    response::ok(json!(DryRunResponse {
+        valid: true,
+        root: Some(dto.root),
+        total: Some(dto.total_amount),
+        recipients: Some(dto.number_of_recipients.to_string()),
+        errors: vec![],
+    }))
+}
+
+/// Vercel specific handler for the dry-run validation endpoint
+#[utoipa::path(
+    post,
+    path = "/api/validate_solana",
+    summary = "Validate a Solana CSV campaign and compute its root without pinning it",
+    params(
+        ("decimals" = Option<u16>, Query, description = "Decimals of the campaign token, `SOLANA_DEFAULT_DECIMALS` when omitted"),
+        ("leaf_order" = Option<String>, Query, description = "Comma-separated order of `index`, `recipient` and `amount` in a leaf"),
+        ("hash_algorithm" = Option<HashAlgorithm>, Query, description = "Hash of the leaves and nodes, `keccak256` when omitted"),
+        ("domain" = Option<String>, Query, description = "Hex domain separation tag prepended to every leaf"),
+    ),
+    request_body(content = CampaignUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "The file is valid", body = DryRunResponse),
+        (status = 400, description = "Invalid query parameters or CSV file", body = DryRunResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 413, description = "File larger than `MAX_UPLOAD_BYTES`", body = GeneralErrorResponse),
+        (status = 500, description = "Parsing failed", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: decimals, leaf_order, hash_algorithm, domain
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+    let decimals = match decimals(&query) {
+        Ok(decimals) => decimals,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidDecimals, error),
+    };
+
+    let tree = match tree_options(&query) {
+        Ok(tree) => tree,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
+    };
+
+    let options = match ParseOptions::from_query(&query) {
+        Ok(options) => options,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
+    };
+
+    // ------------------------------------------------------------
+    // Extract form data from the body: file
+    // ------------------------------------------------------------
+
+    let buffer = match request::multipart_file(req).await {
+        Ok(buffer) => buffer,
+        Err(UploadError::TooLarge(limit)) => {
+            return response::to_vercel_error(413, ErrorCode::PayloadTooLarge, format!("File exceeds {limit} bytes"))
+        }
+        Err(UploadError::Invalid(error)) => return response::to_vercel_error(400, ErrorCode::InvalidFile, error),
+    };
+
+    response::to_vercel(handler(decimals.into(), tree, options, &buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handler_previews_valid_campaign() {
+        let csv_data = b"address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100.0\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,200.0";
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), csv_data);
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.message["valid"], true);
+        assert_eq!(response.message["total"], "30000");
+        assert_eq!(response.message["recipients"], "2");
+        assert!(response.message["root"].as_str().unwrap().starts_with("0x"));
+        assert_eq!(response.message["errors"].as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn handler_reports_invalid_campaign() {
+        let csv_data =
+            b"address,amount\n0xThisIsNotAnAddress,100.0\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,200.0";
+        let response = handler(2, TreeOptions::default(), ParseOptions::default(), csv_data);
+
+        assert_eq!(response.status, 400);
+        assert_eq!(response.message["valid"], false);
+        assert!(response.message.get("root").is_none());
+        assert_eq!(response.message["errors"][0]["message"], "Invalid Solana address");
+        assert_eq!(response.message["errors"][0]["row"], 2);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{
+    csv_campaign_parser::{CampaignCsvParsed, ParseOptions, ValidationErrors},
+    data_objects::{
+        dto::CampaignUpload,
+        response::{self, ErrorCode, GeneralErrorResponse},
+    },
+    utils::{
+        auth,
+        csv_validator::{AddressType, ValidationError},
+        request::{self, UploadError},
+    },
+};
+
+use std::io::Cursor;
+use vercel_runtime as Vercel;
+
+/// Validate stream request common handler. It returns an iterator that validates the file row by row and yields each
+/// validation error as soon as it is found.
+pub fn handler(
+    decimals: usize,
+    address_type: AddressType,
+    options: ParseOptions,
+    buffer: Vec<u8>,
+) -> Result<ValidationErrors<Cursor<Vec<u8>>>, response::R> {
+    address_type.check_decimals(decimals).map_err(|error| response::error(400, ErrorCode::InvalidDecimals, error))?;
+    request::check_csv(&buffer).map_err(|error| response::error(400, ErrorCode::InvalidFile, error))?;
+
+    let rdr = options.reader(Cursor::new(buffer));
+    CampaignCsvParsed::validation_errors(rdr, decimals, address_type, &options).map_err(|error| {
+        response::error(
+            500,
+            ErrorCode::CsvParsingFailed,
+            format!("There was a problem in csv file parsing process: {error}"),
+        )
+    })
+}
+
+/// Vercel specific handler for the validate stream endpoint. The validation errors are streamed as NDJSON, one
+/// `{"row": .., "message": ..}` object per line. An empty body means the file is valid.
+#[utoipa::path(
+    post,
+    path = "/api/validate_stream",
+    summary = "Stream the validation errors of a CSV campaign as they are found",
+    params(
+        ("decimals" = u16, Query, description = "Decimals of the campaign token, used to scale the CSV amounts"),
+        ("chain" = Option<String>, Query, description = "`ethereum`, the default, or `solana`"),
+    ),
+    request_body(content = CampaignUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "One validation error per line, none for a valid file", content_type = "application/x-ndjson", body = ValidationError),
+        (status = 400, description = "Invalid query parameters or file", body = GeneralErrorResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 413, description = "File larger than `MAX_UPLOAD_BYTES`", body = GeneralErrorResponse),
+        (status = 500, description = "Parsing failed", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    if !auth::is_authorized(&req) {
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
+    }
+
+    // ------------------------------------------------------------
+    // Extract query parameters from the URL: decimals, chain
+    // ------------------------------------------------------------
+
+    let query = request::query_params(&req);
+    let Some(decimals) = query.get("decimals").and_then(|d| d.parse::<u16>().ok()) else {
+        return response::to_vercel_error(
+            400,
+            ErrorCode::InvalidDecimals,
+            "Decimals query parameter is mandatory and should be a valid integer",
+        );
+    };
+
+    let address_type = match query.get("chain").map(String::as_str) {
+        None | Some("ethereum") => AddressType::Ethereum,
+        Some("solana") => AddressType::Solana,
+        Some(_) => {
+            return response::to_vercel_error(
+                400,
+                ErrorCode::InvalidQuery,
+                "The chain query parameter should be `ethereum` or `solana`",
+            )
+        }
+    };
+
+    let options = match ParseOptions::from_query(&query) {
+        Ok(options) => options,
+        Err(error) => return response::to_vercel_error(400, ErrorCode::InvalidQuery, error),
+    };
+
+    // ------------------------------------------------------------
+    // Extract form data from the body: file
+    // ------------------------------------------------------------
+
+    let buffer = match request::multipart_file(req).await {
+        Ok(buffer) => buffer,
+        Err(UploadError::TooLarge(limit)) => {
+            return response::to_vercel_error(413, ErrorCode::PayloadTooLarge, format!("File exceeds {limit} bytes"))
+        }
+        Err(UploadError::Invalid(error)) => return response::to_vercel_error(400, ErrorCode::InvalidFile, error),
+    };
+
+    match handler(decimals.into(), address_type, options, buffer) {
+        Ok(errors) => response::to_vercel_ndjson(errors),
+        Err(error) => response::to_vercel(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handler_yields_validation_errors() {
+        let csv_data = b"address,amount\n0xThisIsNotAnAddress,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let Ok(errors) = handler(2, AddressType::Ethereum, ParseOptions::default(), csv_data.to_vec()) else {
+            panic!("the csv should be readable");
+        };
+        let errors: Vec<_> = errors.collect();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].row, 2);
+        assert_eq!(errors[0].message, "Invalid Ethereum address");
+    }
+
+    #[test]
+    fn handler_yields_nothing_for_valid_file() {
+        let csv_data = b"address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let Ok(mut errors) = handler(2, AddressType::Ethereum, ParseOptions::default(), csv_data.to_vec()) else {
+            panic!("the csv should be readable");
+        };
+
+        assert!(errors.next().is_none());
+    }
+}
@@ -2,7 +2,7 @@ use crate::{
     data_objects::{
         dto::PersistentCampaignDto,
         query_param::Validity,
-        response::{self, ValidResponse},
+        response::{self, ErrorCode, GeneralErrorResponse, ValidResponse},
     },
     services::ipfs::download_from_ipfs,
     utils::{auth, request},
@@ -16,22 +16,35 @@ use vercel_runtime as Vercel;
 /// `PersistentCampaignDto` struct.
 pub async fn handler(validity: Validity) -> response::R {
     let Ok(ipfs_data) = download_from_ipfs::<PersistentCampaignDto>(&validity.cid).await else {
-        return response::message(500, "Bad CID or invalid file format provided.");
+        return response::error(500, ErrorCode::MalformedCampaign, "Bad CID or invalid file format provided.");
     };
 
     let response_json = json!(&ValidResponse {
         root: ipfs_data.root,
         total: ipfs_data.total_amount,
         recipients: ipfs_data.number_of_recipients.to_string(),
-        cid: validity.cid
+        cid: validity.cid,
+        params: ipfs_data.params,
     });
     response::ok_immutable(response_json)
 }
 
 /// Vercel specific handler for the validity endpoint
+#[utoipa::path(
+    get,
+    path = "/api/validity",
+    summary = "Check that a CID holds a readable campaign",
+    params(Validity),
+    responses(
+        (status = 200, description = "The campaign is valid", body = ValidResponse),
+        (status = 401, description = "Missing or wrong bearer token", body = GeneralErrorResponse),
+        (status = 500, description = "Bad CID or invalid file format", body = GeneralErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
 pub async fn handler_to_vercel(req: Vercel::Request) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
     if !auth::is_authorized(&req) {
-        return response::to_vercel_message(401, "Bad authentication process provided.");
+        return response::to_vercel_error(401, ErrorCode::Unauthorized, "Bad authentication process provided.");
     }
 
     // ------------------------------------------------------------
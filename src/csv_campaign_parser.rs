@@ -1,19 +1,182 @@
-use csv::Reader;
-use ethers_rs::{Address, Eip55};
-use regex::Regex;
+use csv::{ByteRecord, ErrorKind, Position, Reader, ReaderBuilder, StringRecord};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, error::Error};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    error::Error,
+    io::Read,
+};
 
-use crate::utils::csv_validator::{
-    validate_csv_header, validate_csv_row, AddressColumnValidator, AddressType, AmountColumnValidator, ColumnValidator,
-    ValidationError,
+use crate::{
+    data_objects::dto::RecipientDto,
+    utils::csv_validator::{
+        canonical_eth_address, locate_csv_columns, AddressColumnValidator, AddressType, AmountColumnValidator,
+        ColumnValidator, ValidationError,
+    },
 };
 
+/// Maximum number of validation errors reported for a single CSV file
+pub const MAX_VALIDATION_ERRORS: usize = 100;
+
+/// Longest prefix of a malformed line quoted back in its validation error
+const MAX_LINE_CONTENT_CHARS: usize = 200;
+
+/// Largest exponent accepted by `expand_scientific`, well past the digits of any valid amount
+const MAX_SCIENTIFIC_EXPONENT: i64 = 100;
+
+/// Header names of the optional recipient annotation column
+const LABEL_HEADERS: [&str; 2] = ["label", "notes"];
+
+/// Recipient cap used when the `MAX_RECIPIENTS` env var is unset or invalid
+pub const DEFAULT_MAX_RECIPIENTS: usize = 500_000;
+
+/// How an amount with more fractional digits than the token decimals is handled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Report the row as invalid
+    #[default]
+    Reject,
+    /// Drop the extra digits
+    Floor,
+    /// Round half-up to the token precision
+    Round,
+}
+
+/// Optional, stricter checks applied while parsing a CSV campaign
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// Require every amount to have exactly `decimals` fractional digits
+    pub uniform_precision: bool,
+    /// Sort the records by address, so the same recipients always get the same indices regardless of file order
+    pub canonical: bool,
+    /// Maximum number of recipient rows. Parsing stops at the first row past the cap.
+    pub max_recipients: usize,
+    /// Minimum number of recipient rows, 1 when the rows extend an existing campaign
+    pub min_recipients: usize,
+    /// Expand amounts in scientific notation, e.g. `1.2E7`, to plain decimals before they are validated
+    pub accept_scientific: bool,
+    /// Ceiling on the campaign total in base units, e.g. the supply the claim contract can hold. The total must fit
+    /// in `u128` either way.
+    pub max_total: Option<u128>,
+    /// Raw supply of the token in base units. A recipient amount or a total above it is almost certainly a file
+    /// error, e.g. amounts already scaled by the decimals, so it is reported.
+    pub max_supply: Option<u128>,
+    /// Smallest accepted recipient amount, inclusive, in token units, e.g. `0.5`
+    pub min_amount: Option<String>,
+    /// Largest accepted recipient amount, inclusive, in token units
+    pub max_amount: Option<String>,
+    /// Field delimiter of the file, e.g. `;` for CSVs exported with European locale settings
+    pub delimiter: u8,
+    /// Decimal separator of the amounts, `.` or `,`. It is replaced with `.` before the amounts are validated.
+    pub decimal_separator: char,
+    /// Accept amounts grouped in thousands, e.g. `1,234.56`, with whichever of `,` and `.` is not the decimal
+    /// separator. With a `,` delimiter only quoted amounts can contain the grouping commas.
+    pub thousands_separator: bool,
+    /// Leave the invalid rows out of the campaign instead of rejecting the file. They are reported in `skipped`, while
+    /// errors about the file as a whole still reject it.
+    pub skip_invalid: bool,
+    /// Handling of amounts more precise than the token decimals. Rounded amounts are counted in `rounded_rows`.
+    pub rounding: Rounding,
+}
+
+impl Default for ParseOptions {
+    /// All optional checks disabled, with the recipient cap read from the `MAX_RECIPIENTS` env var
+    fn default() -> Self {
+        let max_recipients =
+            std::env::var("MAX_RECIPIENTS").ok().and_then(|max| max.parse().ok()).unwrap_or(DEFAULT_MAX_RECIPIENTS);
+
+        ParseOptions {
+            uniform_precision: false,
+            canonical: false,
+            max_recipients,
+            min_recipients: 2,
+            accept_scientific: false,
+            max_total: None,
+            max_supply: None,
+            min_amount: None,
+            max_amount: None,
+            delimiter: b',',
+            decimal_separator: '.',
+            thousands_separator: false,
+            skip_invalid: false,
+            rounding: Rounding::Reject,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Read the parse options from the request query parameters. Missing parameters keep the default behavior. The
+    /// error is a message that can be returned to the caller as is.
+    pub fn from_query(query: &HashMap<String, String>) -> Result<Self, String> {
+        let flag = |name: &str| query.get(name).is_some_and(|v| v == "true");
+
+        let delimiter = match query.get("delimiter").map(String::as_bytes) {
+            None => b',',
+            Some(&[delimiter]) if delimiter.is_ascii() && !matches!(delimiter, b'"' | b'\n' | b'\r') => delimiter,
+            Some(_) => return Err("The delimiter query parameter should be a single ASCII character".to_string()),
+        };
+        let decimal_separator = match query.get("decimal_separator").map(String::as_str) {
+            None | Some(".") => '.',
+            Some(",") => ',',
+            Some(_) => return Err("The decimal_separator query parameter should be `.` or `,`".to_string()),
+        };
+        if decimal_separator as u32 == u32::from(delimiter) {
+            return Err("The delimiter and the decimal separator should be different".to_string());
+        }
+        let bound = |name: &str| match query.get(name) {
+            None => Ok(None),
+            Some(bound) if is_plain_decimal(bound) => Ok(Some(bound.clone())),
+            Some(_) => Err(format!("The {name} query parameter should be a non-negative decimal number")),
+        };
+        let min_amount = bound("min_amount")?;
+        let max_amount = bound("max_amount")?;
+        let limit = |name: &str| {
+            query
+                .get(name)
+                .map(|limit| limit.parse())
+                .transpose()
+                .map_err(|_| format!("The {name} query parameter should be a non-negative integer of base units"))
+        };
+        let max_total = limit("max_total")?;
+        let max_supply = limit("max_supply")?;
+        let rounding = match query.get("rounding").map(String::as_str) {
+            None | Some("reject") => Rounding::Reject,
+            Some("floor") => Rounding::Floor,
+            Some("round") => Rounding::Round,
+            Some(_) => return Err("The rounding query parameter should be `reject`, `floor` or `round`".to_string()),
+        };
+
+        Ok(ParseOptions {
+            uniform_precision: flag("uniform_precision"),
+            canonical: flag("canonical"),
+            accept_scientific: flag("accept_scientific"),
+            thousands_separator: flag("thousands_separator"),
+            skip_invalid: flag("skip_invalid"),
+            max_total,
+            max_supply,
+            min_amount,
+            max_amount,
+            delimiter,
+            decimal_separator,
+            rounding,
+            ..Self::default()
+        })
+    }
+
+    /// CSV reader over `rdr` using the configured `delimiter`. It is flexible so that rows ending with empty columns,
+    /// e.g. from a trailing comma, can be accepted; `CampaignCsvRows` checks the column count itself.
+    pub fn reader<R: Read>(&self, rdr: R) -> Reader<R> {
+        ReaderBuilder::new().delimiter(self.delimiter).flexible(true).from_reader(rdr)
+    }
+}
+
 /// Record inside a CSV airstream campaign
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CampaignCsvRecord {
     pub address: String,
     pub amount: u128,
+    /// Value of the optional `label` column, stored with the recipient but never hashed into its leaf
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 /// The abstraction of a CSV airstream campaign
@@ -22,6 +185,10 @@ pub struct CampaignCsvParsed {
     pub validation_errors: Vec<ValidationError>,
     pub number_of_recipients: i32,
     pub total_amount: u128,
+    /// Number of amounts rounded to the token precision, always 0 with `Rounding::Reject`
+    pub rounded_rows: usize,
+    /// Errors of the rows left out of the campaign with `skip_invalid`
+    pub skipped: Vec<ValidationError>,
 }
 
 impl CampaignCsvParsed {
@@ -66,84 +233,730 @@ impl CampaignCsvParsed {
         decimals: usize,
         address_type: AddressType,
     ) -> Result<CampaignCsvParsed, Box<dyn Error + Send + Sync>> {
-        let mut rdr = rdr;
+        Self::build_with_options(rdr, decimals, address_type, &ParseOptions::default())
+    }
+
+    /// Same as `build`, with the optional checks enabled in `options`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sablier_merkle_api::csv_campaign_parser::{CampaignCsvParsed, ParseOptions};
+    /// use sablier_merkle_api::utils::csv_validator::AddressType;
+    /// use csv::ReaderBuilder;
+    /// let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.00\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.5";
+    /// let reader = ReaderBuilder::new().from_reader(csv_data.as_bytes());
+    /// let options = ParseOptions { uniform_precision: true, ..ParseOptions::default() };
+    /// let result = CampaignCsvParsed::build_with_options(reader, 2, AddressType::Ethereum, &options).unwrap();
+    /// assert_eq!(result.validation_errors.len(), 1);
+    /// assert_eq!(result.validation_errors[0].row, 3);
+    /// ```
+    pub fn build_with_options(
+        rdr: Reader<&[u8]>,
+        decimals: usize,
+        address_type: AddressType,
+        options: &ParseOptions,
+    ) -> Result<CampaignCsvParsed, Box<dyn Error + Send + Sync>> {
+        let mut rows = CampaignCsvRows::new(rdr, decimals, address_type, options.clone())?;
+        if let Some(error) = rows.header_error.take() {
+            let validation_errors = vec![error];
+            return Ok(CampaignCsvParsed {
+                total_amount: 0,
+                number_of_recipients: 0,
+                records: vec![],
+                validation_errors,
+                rounded_rows: 0,
+                skipped: vec![],
+            });
+        }
+
+        let mut parsed = Self::collect(rows.by_ref(), 2, options);
+        if options.skip_invalid && rows.record_count > options.max_recipients {
+            // The last row read is the one past the cap, which rejects the file rather than being skipped
+            parsed.validation_errors.extend(parsed.skipped.pop());
+        }
+        parsed.validation_errors.extend(rows.recipients_error());
+        parsed.rounded_rows = rows.checker.rounded_rows;
+        Ok(parsed)
+    }
+
+    /// Same as `build_with_options`, for recipients sent as a list rather than a CSV file. Validation errors refer to
+    /// the position of the recipient in `recipients`, and errors about the list as a whole to row 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sablier_merkle_api::csv_campaign_parser::{CampaignCsvParsed, ParseOptions};
+    /// use sablier_merkle_api::data_objects::dto::RecipientDto;
+    /// use sablier_merkle_api::utils::csv_validator::AddressType;
+    /// let recipient = |address: &str, amount: &str| RecipientDto { address: address.into(), amount: amount.into(), label: None };
+    /// let recipients = [
+    ///     recipient("0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "100.0"),
+    ///     recipient("0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc", "0"),
+    /// ];
+    /// let result = CampaignCsvParsed::build_from_recipients(&recipients, 2, AddressType::Ethereum, &ParseOptions::default());
+    /// assert_eq!(result.validation_errors.len(), 1);
+    /// assert_eq!(result.validation_errors[0].row, 1);
+    /// ```
+    pub fn build_from_recipients(
+        recipients: &[RecipientDto],
+        decimals: usize,
+        address_type: AddressType,
+        options: &ParseOptions,
+    ) -> CampaignCsvParsed {
+        let mut checker = RecipientChecker::new(decimals, address_type, options.clone());
+        let max_recipients = options.max_recipients;
+        let rows = recipients.iter().take(max_recipients.saturating_add(1)).enumerate().map(|(row, recipient)| {
+            if row >= max_recipients {
+                let message = format!("Campaign exceeds maximum of {max_recipients} recipients");
+                return Err(vec![ValidationError { row, message }]);
+            }
+            let label = recipient.label.as_deref().map(str::trim).filter(|label| !label.is_empty()).map(String::from);
+            checker.check(row, recipient.address.trim(), recipient.amount.trim(), label)
+        });
+
+        let mut parsed = Self::collect(rows, 0, options);
+        if options.skip_invalid && recipients.len() > max_recipients {
+            parsed.validation_errors.extend(parsed.skipped.pop());
+        }
+        parsed.rounded_rows = checker.rounded_rows;
+        if recipients.len() < options.min_recipients {
+            let message = format!("An airstream campaign must have at least {} recipients", options.min_recipients);
+            parsed.validation_errors.push(ValidationError { row: 0, message });
+        }
+        parsed
+    }
+
+    /// Sums up the checked recipients, which sit on consecutive rows starting at `first_row`. Records after the first
+    /// validation error are checked but not kept, and at most `MAX_VALIDATION_ERRORS` errors are collected. With
+    /// `skip_invalid`, the errors go to `skipped` instead and every valid record is kept.
+    fn collect<I>(rows: I, first_row: usize, options: &ParseOptions) -> CampaignCsvParsed
+    where
+        I: Iterator<Item = Result<CampaignCsvRecord, Vec<ValidationError>>>,
+    {
         let mut validation_errors = Vec::new();
+        let mut skipped = Vec::new();
+        let mut skipped_rows = 0;
         let mut records: Vec<CampaignCsvRecord> = Vec::new();
         let mut total_amount: u128 = 0;
         let mut number_of_recipients: i32 = 0;
-        let pattern = format!(r"^[+]?\d*\.?\d{{0,{decimals}}}$");
-        let amount_regex = Regex::new(&pattern).unwrap();
 
-        let amount_validator = AmountColumnValidator { regex: amount_regex };
-        let address_validator = AddressColumnValidator::new(address_type);
+        for row in rows {
+            if validation_errors.len() >= MAX_VALIDATION_ERRORS {
+                break;
+            }
 
-        let validators: Vec<&dyn ColumnValidator> = vec![&address_validator, &amount_validator];
-        let mut unique_addresses: HashSet<String> = HashSet::new();
+            match row {
+                Ok(record) if validation_errors.is_empty() => {
+                    // Every row before this one was valid or skipped, so it sits right after them
+                    let row = first_row + number_of_recipients as usize + skipped_rows;
+                    let errors = if options.skip_invalid { &mut skipped } else { &mut validation_errors };
+                    let total = total_amount.checked_add(record.amount);
+                    let Some(total) = total.filter(|total| options.max_total.is_none_or(|max| *total <= max)) else {
+                        let message = String::from("Total amount exceeds representable range");
+                        errors.push(ValidationError { row, message });
+                        skipped_rows += 1;
+                        continue;
+                    };
+                    if let Some(max_supply) = options.max_supply.filter(|max| total > *max) {
+                        let message = format!("Total amount exceeds the token supply of {max_supply} base units");
+                        errors.push(ValidationError { row, message });
+                        skipped_rows += 1;
+                        continue;
+                    }
+                    total_amount = total;
+                    number_of_recipients += 1;
+                    records.push(record);
+                }
+                Ok(_) => {}
+                Err(errors) if options.skip_invalid => {
+                    skipped.extend(errors);
+                    skipped_rows += 1;
+                }
+                Err(errors) => validation_errors.extend(errors),
+            }
+        }
 
-        // Validate the CSV header
-        let header = rdr.headers()?;
-        let header_errors = validate_csv_header(header, &validators);
-        if let Some(error) = header_errors {
-            validation_errors.push(error);
-            return Ok(CampaignCsvParsed { total_amount, number_of_recipients, records, validation_errors });
+        if !skipped.is_empty() && records.len() < options.min_recipients {
+            let message =
+                format!("An airstream campaign must have at least {} valid recipients", options.min_recipients);
+            validation_errors.push(ValidationError { row: first_row.saturating_sub(1), message });
         }
 
-        let mut record_count = 0;
+        if options.canonical {
+            records.sort_by(|a, b| a.address.cmp(&b.address));
+        }
+        CampaignCsvParsed { total_amount, number_of_recipients, records, validation_errors, rounded_rows: 0, skipped }
+    }
 
-        for (row_index, result) in rdr.records().enumerate() {
-            record_count += 1;
-            let row = row_index + 2;
-            if result.is_err() {
-                validation_errors.push(ValidationError { row, message: String::from("Invalid row") });
-                continue;
-            }
-            let record = result.unwrap();
+    /// Lazily validates a CSV campaign, yielding each validation error as soon as the row containing it is read. The
+    /// checks are the same ones `build` performs and at most `MAX_VALIDATION_ERRORS` errors are yielded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sablier_merkle_api::csv_campaign_parser::{CampaignCsvParsed, ParseOptions};
+    /// use sablier_merkle_api::utils::csv_validator::AddressType;
+    /// use csv::ReaderBuilder;
+    /// let csv_data = "address,amount\n0xThisIsNotAnAddress,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,0";
+    /// let reader = ReaderBuilder::new().from_reader(csv_data.as_bytes());
+    /// let errors: Vec<_> = CampaignCsvParsed::validation_errors(reader, 2, AddressType::Ethereum, &ParseOptions::default()).unwrap().collect();
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(errors[0].row, 2);
+    /// assert_eq!(errors[1].message, "The amount cannot be 0");
+    /// ```
+    pub fn validation_errors<R: Read>(
+        rdr: Reader<R>,
+        decimals: usize,
+        address_type: AddressType,
+        options: &ParseOptions,
+    ) -> Result<ValidationErrors<R>, Box<dyn Error + Send + Sync>> {
+        let mut rows = CampaignCsvRows::new(rdr, decimals, address_type, options.clone())?;
+        let pending: VecDeque<ValidationError> = rows.header_error.take().into_iter().collect();
+        let rows = if pending.is_empty() { Some(rows) } else { None };
 
-            if validation_errors.len() >= 100 {
-                break;
+        Ok(ValidationErrors { rows, pending, emitted: 0 })
+    }
+}
+
+/// Validates campaign recipients one at a time, whatever format they were sent in. Addresses are checked for
+/// uniqueness across all the recipients checked so far.
+struct RecipientChecker {
+    address_validator: AddressColumnValidator,
+    amount_validator: AmountColumnValidator,
+    address_type: AddressType,
+    decimals: usize,
+    options: ParseOptions,
+    unique_addresses: HashSet<String>,
+    rounded_rows: usize,
+    /// `min_amount` and `max_amount` in base units
+    min_base_units: Option<u128>,
+    max_base_units: Option<u128>,
+}
+
+impl RecipientChecker {
+    fn new(decimals: usize, address_type: AddressType, options: ParseOptions) -> Self {
+        RecipientChecker {
+            min_base_units: options.min_amount.as_deref().map(|min| bound_base_units(min, decimals, true)),
+            max_base_units: options.max_amount.as_deref().map(|max| bound_base_units(max, decimals, false)),
+            address_validator: AddressColumnValidator::new(address_type),
+            amount_validator: AmountColumnValidator { decimals },
+            address_type,
+            decimals,
+            options,
+            unique_addresses: HashSet::new(),
+            rounded_rows: 0,
+        }
+    }
+
+    /// The amount rewritten as a plain `.`-separated decimal: thousands separators are stripped when
+    /// `thousands_separator` is set, the `decimal_separator` is replaced and, when `accept_scientific` is set,
+    /// scientific notation is expanded. Returns `None` when the thousands separators are misplaced.
+    fn normalize_amount(&self, amount: &str) -> Option<String> {
+        let decimal_separator = self.options.decimal_separator;
+        let amount = if self.options.thousands_separator {
+            let separator = if decimal_separator == ',' { '.' } else { ',' };
+            strip_thousands(amount, separator, decimal_separator)?
+        } else {
+            amount.to_string()
+        };
+
+        let amount = amount.replace(decimal_separator, ".");
+        if !self.options.accept_scientific {
+            return Some(amount);
+        }
+        Some(expand_scientific(&amount, self.decimals).unwrap_or(amount))
+    }
+
+    /// Checks the recipient found on `row`, with its address and amount already trimmed
+    fn check(
+        &mut self,
+        row: usize,
+        address_field: &str,
+        amount_field: &str,
+        label: Option<String>,
+    ) -> Result<CampaignCsvRecord, Vec<ValidationError>> {
+        let normalized = self.normalize_amount(amount_field);
+        let rounded =
+            normalized.as_deref().and_then(|amount| round_amount(amount, self.decimals, self.options.rounding));
+        let amount_error = match rounded.as_deref().or(normalized.as_deref()) {
+            Some(scaled) if normalized.as_deref().is_some_and(|amount| is_dust(amount, scaled, self.decimals)) => {
+                Some(ValidationError { row, message: String::from("Amount is below one base unit (dust)") })
             }
+            Some(amount) => self.amount_validator.validate_cel(amount, row),
+            None => Some(ValidationError { row, message: String::from("Misplaced thousands separator") }),
+        };
+        let amount_field = rounded.as_deref().or(normalized.as_deref()).unwrap_or(amount_field);
+        let mut errors: Vec<ValidationError> = [self.address_validator.validate_cel(address_field, row), amount_error]
+            .into_iter()
+            .flatten()
+            .map(|error| ValidationError { row, ..error })
+            .collect();
+
+        let max_amount_digits = self.address_type.max_amount_digits();
+        if errors.is_empty() && amount_digits(amount_field, self.decimals) > max_amount_digits {
+            errors.push(ValidationError {
+                row,
+                message: format!(
+                    "Amount too large. With {} decimals an amount can have at most {} digits before the decimal point",
+                    self.decimals,
+                    max_amount_digits.saturating_sub(self.decimals)
+                ),
+            });
+        } else if self.options.uniform_precision && fraction_digits(amount_field) != self.decimals {
+            errors.push(ValidationError {
+                row,
+                message: format!("Every amount should have exactly {} decimals", self.decimals),
+            });
+        }
 
-            let address_field = record[0].trim();
-            let amount_field = record[1].trim();
-            let row_errors = validate_csv_row(&record, row_index, &validators);
-            if !row_errors.is_empty() {
-                validation_errors.extend(row_errors);
+        let amount = errors.is_empty().then(|| pad_value(amount_field, self.decimals));
+        if let (Some(amount), Some(min)) = (amount, self.min_base_units) {
+            if amount < min {
+                let min_amount = self.options.min_amount.as_deref().unwrap_or_default();
+                errors.push(ValidationError { row, message: format!("Amount is below the minimum of {min_amount}") });
             }
+        }
+        if let (Some(amount), Some(max)) = (amount, self.max_base_units) {
+            if amount > max {
+                let max_amount = self.options.max_amount.as_deref().unwrap_or_default();
+                errors.push(ValidationError { row, message: format!("Amount exceeds the maximum of {max_amount}") });
+            }
+        }
+        if let Some(max_supply) = self.options.max_supply.filter(|max| amount.is_some_and(|amount| amount > *max)) {
+            errors.push(ValidationError {
+                row,
+                message: format!("Amount exceeds the token supply of {max_supply} base units"),
+            });
+        }
+
+        let address = self.address_type.address_key(address_field);
+        if self.unique_addresses.contains(&address) {
+            errors.push(ValidationError {
+                row,
+                message: String::from(
+                    "Each recipient should have an unique address. This address was already specified in file",
+                ),
+            });
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        self.unique_addresses.insert(address);
+        if rounded.is_some() {
+            self.rounded_rows += 1;
+        }
+
+        let formatted_address = match self.address_type {
+            // Validated above, so the address has a canonical form
+            AddressType::Ethereum => canonical_eth_address(address_field).unwrap_or_default(),
+            AddressType::Solana => address_field.to_string(), // Solana addresses don't need checksum
+        };
 
-            if unique_addresses.contains(&address_field.to_lowercase()) {
-                validation_errors.push(ValidationError {
-                    row,
-                    message: String::from(
-                        "Each recipient should have an unique address. This address was already specified in file",
-                    ),
-                });
+        Ok(CampaignCsvRecord { address: formatted_address, amount: amount.unwrap_or_default(), label })
+    }
+}
+
+/// Validates the rows of a CSV campaign one at a time. Each item is either the parsed record or the validation
+/// errors of that row. Addresses are checked for uniqueness across all the rows read so far.
+pub struct CampaignCsvRows<R> {
+    rdr: Reader<R>,
+    record: ByteRecord,
+    header_len: usize,
+    header_error: Option<ValidationError>,
+    /// Index of the address and amount columns in the file
+    columns: Vec<usize>,
+    /// Index of the optional `label` column
+    label_column: Option<usize>,
+    checker: RecipientChecker,
+    record_count: usize,
+}
+
+impl<R: Read> CampaignCsvRows<R> {
+    /// Reads and validates the CSV header. A header validation error is kept in `header_error`.
+    pub fn new(
+        mut rdr: Reader<R>,
+        decimals: usize,
+        address_type: AddressType,
+        options: ParseOptions,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let checker = RecipientChecker::new(decimals, address_type, options);
+
+        let header_len = rdr.headers().map_or(2, |header| non_empty_len(header.iter()));
+        let label_column = rdr.headers().ok().and_then(|header| {
+            header.iter().position(|head| LABEL_HEADERS.contains(&head.trim().to_lowercase().as_str()))
+        });
+        let (columns, header_error) = match rdr.headers() {
+            Ok(header) => match locate_csv_columns(header, &[&checker.address_validator, &checker.amount_validator]) {
+                Ok(columns) => (columns, None),
+                Err(error) => (vec![0, 1], Some(error)),
+            },
+            Err(error) => {
+                let line = error.position().map_or(1, |position| position.line() as usize);
+                let message = format!("Invalid row: {}", malformed_reason(&error, 0));
+                (vec![0, 1], Some(ValidationError { row: line, message }))
             }
+        };
 
-            if validation_errors.is_empty() {
-                let address = address_field.to_string().to_lowercase();
-                let padded_amount = pad_value(amount_field, decimals);
-                total_amount += padded_amount;
-                number_of_recipients += 1;
-                unique_addresses.insert(address.clone());
+        Ok(CampaignCsvRows {
+            rdr,
+            record: ByteRecord::new(),
+            header_len,
+            header_error,
+            columns,
+            label_column,
+            checker,
+            record_count: 0,
+        })
+    }
 
-                let formatted_address = match address_type {
-                    AddressType::Ethereum => Address::to_checksum_string(&Address::from_str(&address, false).unwrap()),
-                    AddressType::Solana => address_field.to_string(), // Solana addresses don't need checksum
-                };
+    /// The error to report when fewer than two recipient rows were read
+    fn recipients_error(&self) -> Option<ValidationError> {
+        let min = self.checker.options.min_recipients;
+        let message = match self.record_count {
+            0 => String::from("CSV contains a header but no recipient rows"),
+            count if count < min => format!("An airstream campaign must have at least {min} recipients"),
+            _ => return None,
+        };
+        Some(ValidationError { row: 1, message })
+    }
 
-                records.push(CampaignCsvRecord { address: formatted_address, amount: padded_amount });
+    /// The record with its address and amount moved to the first two columns, when the header lists them differently
+    fn reorder_columns(&self, record: &StringRecord) -> Option<StringRecord> {
+        if self.columns == [0, 1] {
+            return None;
+        }
+        Some(self.columns.iter().filter_map(|&column| record.get(column)).collect())
+    }
+
+    fn check(&mut self, row_index: usize, record: &StringRecord) -> Result<CampaignCsvRecord, Vec<ValidationError>> {
+        let row = row_index + 2;
+        let label = self.label_column.and_then(|column| record.get(column)).map(str::trim).filter(|l| !l.is_empty());
+        let label = label.map(String::from);
+        let reordered = self.reorder_columns(record);
+        let record = reordered.as_ref().unwrap_or(record);
+        if record.len() < 2 {
+            return Err(vec![ValidationError { row, message: String::from("Insufficient columns") }]);
+        }
+        self.checker.check(row, record[0].trim(), record[1].trim(), label)
+    }
+}
+
+impl<R: Read> Iterator for CampaignCsvRows<R> {
+    type Item = Result<CampaignCsvRecord, Vec<ValidationError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.record_count > self.checker.options.max_recipients {
+            return None;
+        }
+        let result = self.rdr.read_byte_record(&mut self.record);
+        if let Ok(false) = result {
+            return None;
+        }
+        let row_index = self.record_count;
+        self.record_count += 1;
+
+        if self.record_count > self.checker.options.max_recipients {
+            let message = format!("Campaign exceeds maximum of {} recipients", self.checker.options.max_recipients);
+            return Some(Err(vec![ValidationError { row: row_index + 2, message }]));
+        }
+
+        if let Err(error) = result {
+            let reason = malformed_reason(&error, self.header_len);
+            return Some(Err(vec![self.malformed_row_error(row_index, error.position(), reason)]));
+        }
+        if let Some(reason) = self.column_count_reason() {
+            return Some(Err(vec![self.malformed_row_error(row_index, None, reason)]));
+        }
+        match StringRecord::from_byte_record(self.record.clone()) {
+            Ok(record) => Some(self.check(row_index, &record)),
+            Err(_) => Some(Err(vec![self.malformed_row_error(row_index, None, String::from("not valid UTF-8"))])),
+        }
+    }
+}
+
+impl<R> CampaignCsvRows<R> {
+    /// Why the current row does not have as many columns as the header, in the words of `malformed_reason`. Empty
+    /// columns past the header, e.g. from a trailing comma, are not counted, while one holding data is reported.
+    fn column_count_reason(&self) -> Option<String> {
+        let len = self.record.len();
+        let len = if len > self.header_len { non_empty_len(self.record.iter()).max(self.header_len) } else { len };
+        (len != self.header_len).then(|| format!("expected {} columns, found {len}", self.header_len))
+    }
+
+    /// Builds the error for a row the CSV reader rejected, quoting the raw line so the user can find it
+    fn malformed_row_error(&self, row_index: usize, position: Option<&Position>, reason: String) -> ValidationError {
+        let position = position.or(self.record.position());
+        let line = position.map_or(row_index + 2, |position| position.line() as usize);
+        let content = String::from_utf8_lossy(&self.record.iter().collect::<Vec<_>>().join(&b","[..])).into_owned();
+        let content: String = content.chars().take(MAX_LINE_CONTENT_CHARS).collect();
+        ValidationError { row: line, message: format!("Invalid row: {reason}. Line {line}: `{content}`") }
+    }
+}
+
+/// Number of columns up to the last one that is not blank
+fn non_empty_len<F: AsRef<[u8]>>(fields: impl DoubleEndedIterator<Item = F>) -> usize {
+    fields.rev().skip_while(|field| field.as_ref().trim_ascii().is_empty()).count()
+}
+
+/// Human readable reason for a record the CSV reader could not parse
+fn malformed_reason(error: &csv::Error, expected_fields: usize) -> String {
+    match error.kind() {
+        ErrorKind::UnequalLengths { len, .. } => format!("expected {expected_fields} columns, found {len}"),
+        ErrorKind::Utf8 { .. } => String::from("not valid UTF-8"),
+        _ => error.to_string(),
+    }
+}
+
+/// Iterator over the validation errors of a CSV campaign, see `CampaignCsvParsed::validation_errors`
+pub struct ValidationErrors<R> {
+    rows: Option<CampaignCsvRows<R>>,
+    pending: VecDeque<ValidationError>,
+    emitted: usize,
+}
+
+impl<R: Read> Iterator for ValidationErrors<R> {
+    type Item = ValidationError;
+
+    fn next(&mut self) -> Option<ValidationError> {
+        loop {
+            if self.emitted >= MAX_VALIDATION_ERRORS {
+                return None;
+            }
+            if let Some(error) = self.pending.pop_front() {
+                self.emitted += 1;
+                return Some(error);
+            }
+
+            let rows = self.rows.as_mut()?;
+            match rows.next() {
+                Some(Err(errors)) => self.pending.extend(errors),
+                Some(Ok(_)) => {}
+                None => {
+                    self.pending.extend(rows.recipients_error());
+                    self.rows = None;
+                }
             }
         }
+    }
+}
+
+/// Number of significant digits of an amount once scaled to base units
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::csv_campaign_parser::amount_digits;
+///
+/// assert_eq!(amount_digits("480.5", 3), 6);
+/// assert_eq!(amount_digits("+0.05", 2), 1);
+/// assert_eq!(amount_digits("0012", 0), 2);
+/// ```
+pub fn amount_digits(s: &str, no_decimals: usize) -> usize {
+    let s = s.trim_start_matches('+');
+    let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let integer = integer.trim_start_matches('0');
+    if !integer.is_empty() {
+        return integer.len() + no_decimals;
+    }
+
+    let significant_fraction = fraction.trim_start_matches('0');
+    if significant_fraction.is_empty() {
+        return 0;
+    }
+    no_decimals - (fraction.len() - significant_fraction.len())
+}
+
+/// Number of digits after the decimal point of an amount
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::csv_campaign_parser::fraction_digits;
+///
+/// assert_eq!(fraction_digits("480.50"), 2);
+/// assert_eq!(fraction_digits("123."), 0);
+/// assert_eq!(fraction_digits("613"), 0);
+/// ```
+pub fn fraction_digits(s: &str) -> usize {
+    s.split_once('.').map_or(0, |(_, fraction)| fraction.len())
+}
 
-        if record_count <= 1 {
-            let error = ValidationError {
-                row: 1,
-                message: String::from("An airstream campaign must have at least 2 recipients"),
-            };
-            validation_errors.push(error);
+/// Expand an amount in scientific notation to a plain decimal, padding the fraction to `decimals` digits. Returns
+/// `None` when `s` is not in scientific notation or its exponent is out of range, leaving it to the amount validation.
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::csv_campaign_parser::expand_scientific;
+///
+/// assert_eq!(expand_scientific("1.2E7", 2).as_deref(), Some("12000000.00"));
+/// assert_eq!(expand_scientific("25e-3", 4).as_deref(), Some("0.0250"));
+/// assert_eq!(expand_scientific("1200", 2), None);
+/// ```
+pub fn expand_scientific(s: &str, decimals: usize) -> Option<String> {
+    let (mantissa, exponent) = s.split_once(['e', 'E'])?;
+    let exponent: i64 = exponent.parse().ok().filter(|e: &i64| e.abs() <= MAX_SCIENTIFIC_EXPONENT)?;
+    let mantissa = mantissa.strip_prefix('+').unwrap_or(mantissa);
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{integer}{fraction}");
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let point = integer.len() as i64 + exponent;
+    let (integer, fraction) = if point <= 0 {
+        (String::from("0"), format!("{}{digits}", "0".repeat(point.unsigned_abs() as usize)))
+    } else if point as usize >= digits.len() {
+        (format!("{digits:0<width$}", width = point as usize), String::new())
+    } else {
+        let (integer, fraction) = digits.split_at(point as usize);
+        (integer.to_string(), fraction.to_string())
+    };
+
+    Some(format!("{integer}.{fraction:0<decimals$}").trim_end_matches('.').to_string())
+}
+
+/// Whether `s` is a non-negative decimal without sign or exponent, e.g. `12`, `0.5` or `.5`
+fn is_plain_decimal(s: &str) -> bool {
+    let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+    !(integer.is_empty() && fraction.is_empty()) && integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit())
+}
+
+/// Whether the non-zero plain decimal `amount` is worth 0 base units with `decimals`, once `scaled` to the token
+/// precision, i.e. truncated or rounded
+fn is_dust(amount: &str, scaled: &str, decimals: usize) -> bool {
+    let non_zero = |s: &str| s.bytes().any(|b| (b'1'..=b'9').contains(&b));
+    let (integer, fraction) = scaled.split_once('.').unwrap_or((scaled, ""));
+    let kept = fraction.get(..decimals.min(fraction.len())).unwrap_or(fraction);
+
+    is_plain_decimal(amount) && is_plain_decimal(scaled) && non_zero(amount) && !non_zero(integer) && !non_zero(kept)
+}
+
+/// A plain decimal amount bound in base units. Extra fractional digits are rounded towards the inside of the range,
+/// up for a minimum and down for a maximum, and a bound beyond `u128` saturates.
+fn bound_base_units(bound: &str, decimals: usize, round_up: bool) -> u128 {
+    let (integer, fraction) = bound.split_once('.').unwrap_or((bound, ""));
+    let (kept, dropped) = fraction.split_at(fraction.len().min(decimals));
+    let base_units = format!("{integer}{kept:0<decimals$}").parse().unwrap_or(u128::MAX);
+    if round_up && dropped.bytes().any(|b| b != b'0') {
+        base_units.saturating_add(1)
+    } else {
+        base_units
+    }
+}
+
+/// Remove the thousands `separator` from the integer part of an amount. Returns `None` when the separator is
+/// misplaced, i.e. it does not split the integer part in groups of 3 digits or it appears after the decimal separator.
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::csv_campaign_parser::strip_thousands;
+///
+/// assert_eq!(strip_thousands("1,234.56", ',', '.').as_deref(), Some("1234.56"));
+/// assert_eq!(strip_thousands("1.234.567,8", '.', ',').as_deref(), Some("1234567,8"));
+/// assert_eq!(strip_thousands("1234.56", ',', '.').as_deref(), Some("1234.56"));
+/// assert_eq!(strip_thousands("12,34.5", ',', '.'), None);
+/// assert_eq!(strip_thousands("1.234,5", ',', '.'), None);
+/// ```
+pub fn strip_thousands(s: &str, separator: char, decimal_separator: char) -> Option<String> {
+    let (integer, fraction) = s.split_once(decimal_separator).unwrap_or((s, ""));
+    if fraction.contains(separator) {
+        return None;
+    }
+
+    let mut groups = integer.strip_prefix(['+', '-']).unwrap_or(integer).split(separator);
+    let leading = groups.next().unwrap_or_default();
+    let grouped = groups.all(|group| group.len() == 3);
+    if !grouped || (integer.contains(separator) && (leading.is_empty() || leading.len() > 3)) {
+        return None;
+    }
+    Some(s.replace(separator, ""))
+}
+
+/// Round an amount with more than `decimals` fractional digits to `decimals` digits. Returns `None` when the amount
+/// needs no rounding, `rounding` is `Reject` or the amount is not a plain positive decimal, leaving it to the amount
+/// validation.
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::csv_campaign_parser::{round_amount, Rounding};
+///
+/// assert_eq!(round_amount("1.235", 2, Rounding::Floor).as_deref(), Some("1.23"));
+/// assert_eq!(round_amount("1.235", 2, Rounding::Round).as_deref(), Some("1.24"));
+/// assert_eq!(round_amount("9.999", 2, Rounding::Round).as_deref(), Some("10.00"));
+/// assert_eq!(round_amount("1.235", 2, Rounding::Reject), None);
+/// assert_eq!(round_amount("1.23", 2, Rounding::Round), None);
+/// ```
+pub fn round_amount(s: &str, decimals: usize, rounding: Rounding) -> Option<String> {
+    let (integer, fraction) = s.split_once('.')?;
+    let integer = integer.strip_prefix('+').unwrap_or(integer);
+    if rounding == Rounding::Reject ||
+        fraction.len() <= decimals ||
+        !integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let (kept, dropped) = fraction.split_at(decimals);
+    let mut digits = format!("{integer}{kept}").into_bytes();
+    if rounding == Rounding::Round && dropped.as_bytes()[0] >= b'5' {
+        let carry = digits.iter_mut().rev().all(|digit| {
+            let overflows = *digit == b'9';
+            *digit = if overflows { b'0' } else { *digit + 1 };
+            overflows
+        });
+        if carry {
+            digits.insert(0, b'1');
         }
-        Ok(CampaignCsvParsed { total_amount, number_of_recipients, records, validation_errors })
     }
+
+    // Only ASCII digits were written to `digits`
+    let digits = String::from_utf8(digits).unwrap_or_default();
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+    let integer = if integer.is_empty() { "0" } else { integer };
+    Some(format!("{integer}.{fraction}").trim_end_matches('.').to_string())
+}
+
+/// Format an amount given in base units for display. The last `decimals` digits become the fractional part, trailing
+/// fractional zeros are dropped and the integer part is grouped in thousands. Returns `None` when `base_units` is not
+/// a non-negative integer.
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::csv_campaign_parser::format_amount;
+///
+/// assert_eq!(format_amount("1234567890", 6).as_deref(), Some("1,234.56789"));
+/// assert_eq!(format_amount("5", 2).as_deref(), Some("0.05"));
+/// assert_eq!(format_amount("abc", 2), None);
+/// ```
+pub fn format_amount(base_units: &str, decimals: usize) -> Option<String> {
+    if base_units.is_empty() || !base_units.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let padded = format!("{base_units:0>width$}", width = decimals + 1);
+    let (integer, fraction) = padded.split_at(padded.len() - decimals);
+    let integer = integer.trim_start_matches('0');
+    let integer = if integer.is_empty() { "0" } else { integer };
+
+    let mut grouped = String::with_capacity(integer.len() + integer.len() / 3);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        return Some(grouped);
+    }
+    Some(format!("{grouped}.{fraction}"))
 }
 
 /// Pad a number with the specified number of decimals
@@ -191,6 +1004,18 @@ mod tests {
         assert_eq!(pad_value("123.", 1), 1230);
     }
 
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(format_amount("1000000", 6).as_deref(), Some("1"));
+        assert_eq!(format_amount("1234567000000", 6).as_deref(), Some("1,234,567"));
+        assert_eq!(format_amount("123456", 6).as_deref(), Some("0.123456"));
+        assert_eq!(format_amount("100000000000000000001", 18).as_deref(), Some("100.000000000000000001"));
+        assert_eq!(format_amount("999", 0).as_deref(), Some("999"));
+        assert_eq!(format_amount("0", 9).as_deref(), Some("0"));
+        assert_eq!(format_amount("-1", 2), None);
+        assert_eq!(format_amount("", 2), None);
+    }
+
     #[test]
     fn test_valid_csv_ethereum() {
         let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
@@ -220,6 +1045,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_csv_swapped_columns() {
+        let csv_data = "amount,address\n100.0,0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491\n200.0,0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc";
+        let result = CampaignCsvParsed::build_ethereum(create_reader(csv_data), 2).unwrap();
+
+        assert!(result.validation_errors.is_empty());
+        assert_eq!(result.records[0].address, "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491");
+        assert_eq!(result.records[0].amount, 10000);
+        assert_eq!(result.total_amount, 30000);
+
+        let csv_data = "amount,address\n100.0,0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491\n200.0,0xThisIsNotAnAddress";
+        let result = CampaignCsvParsed::build_ethereum(create_reader(csv_data), 2).unwrap();
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 3);
+        assert_eq!(result.validation_errors[0].message, "Invalid Ethereum address");
+    }
+
+    #[test]
+    fn test_csv_padded_header() {
+        let csv_data = " Address ,  AMOUNT\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let result = CampaignCsvParsed::build_ethereum(create_reader(csv_data), 2).unwrap();
+
+        assert!(result.validation_errors.is_empty());
+        assert_eq!(result.total_amount, 30000);
+    }
+
     #[test]
     fn test_csv_missing_header() {
         let csv_data =
@@ -242,7 +1093,25 @@ mod tests {
         let result = result.unwrap();
 
         assert!(!result.validation_errors.is_empty());
-        assert_eq!(result.validation_errors[0].message, "Invalid row");
+        assert_eq!(result.validation_errors[0].row, 2);
+        assert_eq!(
+            result.validation_errors[0].message,
+            "Invalid row: expected 2 columns, found 1. Line 2: `0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491`"
+        );
+    }
+
+    #[test]
+    fn test_csv_row_unterminated_quote() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0\n\"0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
+        let reader = create_reader(csv_data);
+        let result = CampaignCsvParsed::build_ethereum(reader, 2).unwrap();
+
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 3);
+        assert_eq!(
+            result.validation_errors[0].message,
+            "Invalid row: expected 2 columns, found 1. Line 3: `0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0`"
+        );
     }
 
     #[test]
@@ -287,6 +1156,30 @@ mod tests {
         assert_eq!(result.validation_errors[0].row, 3);
     }
 
+    #[test]
+    fn test_csv_eth_addresses_are_canonicalized() {
+        let csv_data = "address,amount\n9ad7cad4f10d0c3f875b8a2fd292590490c9f491,1\n0xf976af93b0a5a9f55a7f285a3b5355b8575eb5bc,2\n0x9AD7CAD4F10D0C3F875B8A2FD292590490C9F491,3";
+        let result = CampaignCsvParsed::build_ethereum(create_reader(csv_data), 0).unwrap();
+
+        let addresses: Vec<&str> = result.records.iter().map(|record| record.address.as_str()).collect();
+        assert_eq!(
+            addresses,
+            ["0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc"]
+        );
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 4);
+    }
+
+    #[test]
+    fn test_csv_solana_addresses_are_case_sensitive() {
+        let csv_data = "address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,1\n9JDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,2\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,3";
+        let result = CampaignCsvParsed::build_solana(create_reader(csv_data), 0).unwrap();
+
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 4);
+        assert_eq!(result.records.len(), 2);
+    }
+
     #[test]
     fn test_csv_row_alphanumeric_amount() {
         let csv_data = "address,amount\n0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491, alphanumeric_amount\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0";
@@ -296,7 +1189,7 @@ mod tests {
         let result = result.unwrap();
 
         assert!(!result.validation_errors.is_empty());
-        assert_eq!(result.validation_errors[0].message, "Amounts should be positive, in normal notation, with an optional decimal point and a maximum number of decimals as provided by the query parameter.");
+        assert_eq!(result.validation_errors[0].message, "Amount is not a valid number");
         assert_eq!(result.validation_errors[0].row, 2);
     }
 
@@ -322,7 +1215,7 @@ mod tests {
         let result = result.unwrap();
 
         assert!(!result.validation_errors.is_empty());
-        assert_eq!(result.validation_errors[0].message, "Amounts should be positive, in normal notation, with an optional decimal point and a maximum number of decimals as provided by the query parameter.");
+        assert_eq!(result.validation_errors[0].message, "Amount must be positive");
         assert_eq!(result.validation_errors[0].row, 2);
     }
 
@@ -335,7 +1228,7 @@ mod tests {
         let result = result.unwrap();
 
         assert!(!result.validation_errors.is_empty());
-        assert_eq!(result.validation_errors[0].message, "Amounts should be positive, in normal notation, with an optional decimal point and a maximum number of decimals as provided by the query parameter.");
+        assert_eq!(result.validation_errors[0].message, "Amount has more than 2 decimal places");
         assert_eq!(result.validation_errors[0].row, 2);
     }
 
@@ -352,4 +1245,517 @@ mod tests {
         assert_eq!(result.number_of_recipients, 2);
         assert!(result.validation_errors.is_empty());
     }
+
+    #[test]
+    fn test_csv_header_only() {
+        let result = CampaignCsvParsed::build_solana(create_reader("address,amount\n"), 2).unwrap();
+
+        assert!(result.records.is_empty());
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].message, "CSV contains a header but no recipient rows");
+    }
+
+    #[test]
+    fn test_csv_too_many_recipients() {
+        let csv_data = "address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,1\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,2\n0xThisIsNotAnAddress,3\n0xThisIsNotAnAddress,4";
+        let options = ParseOptions { max_recipients: 1, ..ParseOptions::default() };
+
+        let result = CampaignCsvParsed::build_with_options(create_reader(csv_data), 0, AddressType::Solana, &options);
+        let result = result.unwrap();
+
+        // Parsing stops at the row past the cap, so the invalid rows after it are never reported
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 3);
+        assert_eq!(result.validation_errors[0].message, "Campaign exceeds maximum of 1 recipients");
+    }
+
+    #[test]
+    fn test_csv_row_amount_too_large() {
+        let csv_data = format!(
+            "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,{}\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0",
+            "9".repeat(100)
+        );
+        let reader = create_reader(&csv_data);
+        let result = CampaignCsvParsed::build_ethereum(reader, 2).unwrap();
+
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(
+            result.validation_errors[0].message,
+            "Amount too large. With 2 decimals an amount can have at most 36 digits before the decimal point"
+        );
+        assert_eq!(result.validation_errors[0].row, 2);
+    }
+
+    #[test]
+    fn test_csv_amount_keeps_18_decimals() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,123456789.123456789012345678\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,0.000000000000000001";
+        let reader = create_reader(csv_data);
+        let result = CampaignCsvParsed::build_ethereum(reader, 18).unwrap();
+
+        assert!(result.validation_errors.is_empty());
+        assert_eq!(result.records[0].amount, 123_456_789_123_456_789_012_345_678);
+        assert_eq!(result.records[1].amount, 1);
+        assert_eq!(result.total_amount, 123_456_789_123_456_789_012_345_679);
+    }
+
+    #[test]
+    fn test_csv_total_amount_overflow() {
+        let addresses = [
+            "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491",
+            "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc",
+            "0x1111111111111111111111111111111111111111",
+            "0x2222222222222222222222222222222222222222",
+        ];
+        let rows: Vec<String> = addresses.iter().map(|a| format!("{a},{}", "9".repeat(38))).collect();
+        let csv_data = format!("address,amount\n{}", rows.join("\n"));
+        let reader = create_reader(&csv_data);
+        let result = CampaignCsvParsed::build_ethereum(reader, 0).unwrap();
+
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].message, "Total amount exceeds representable range");
+        assert_eq!(result.validation_errors[0].row, 5);
+    }
+
+    #[test]
+    fn test_csv_row_amount_too_large_solana() {
+        let csv_data = "address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100000000000.0\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,200.0";
+        let reader = create_reader(csv_data);
+        let result = CampaignCsvParsed::build_solana(reader, 9).unwrap();
+
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 2);
+        assert!(result.validation_errors[0].message.starts_with("Amount too large"));
+    }
+
+    /// Reader that records how many bytes were consumed from the underlying data
+    struct CountingReader<'a> {
+        data: &'a [u8],
+        consumed: std::rc::Rc<std::cell::Cell<usize>>,
+    }
+
+    impl Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.data.read(buf)?;
+            self.consumed.set(self.consumed.get() + read);
+            Ok(read)
+        }
+    }
+
+    #[test]
+    fn test_validation_errors_are_yielded_incrementally() {
+        let mut csv_data = String::from("address,amount\n0xThisIsNotAnAddress,100.0\n");
+        for i in 0..20_000 {
+            csv_data.push_str(&format!("0x{i:040x},1.0\n"));
+        }
+        let consumed = std::rc::Rc::new(std::cell::Cell::new(0));
+        let reader =
+            ReaderBuilder::new().from_reader(CountingReader { data: csv_data.as_bytes(), consumed: consumed.clone() });
+
+        let mut errors =
+            CampaignCsvParsed::validation_errors(reader, 2, AddressType::Ethereum, &ParseOptions::default()).unwrap();
+        let first = errors.next().unwrap();
+
+        assert_eq!(first.row, 2);
+        assert_eq!(first.message, "Invalid Ethereum address");
+        assert!(consumed.get() < csv_data.len() / 10);
+        assert!(errors.next().is_none());
+        assert_eq!(consumed.get(), csv_data.len());
+    }
+
+    #[test]
+    fn test_recipient_cap_stops_reading() {
+        let mut csv_data = String::from("address,amount\n");
+        for i in 0..100_000 {
+            csv_data.push_str(&format!("0x{i:040x},1.0\n"));
+        }
+        let consumed = std::rc::Rc::new(std::cell::Cell::new(0));
+        let reader =
+            ReaderBuilder::new().from_reader(CountingReader { data: csv_data.as_bytes(), consumed: consumed.clone() });
+        let options = ParseOptions { max_recipients: 10, ..ParseOptions::default() };
+
+        let errors: Vec<ValidationError> =
+            CampaignCsvParsed::validation_errors(reader, 2, AddressType::Ethereum, &options).unwrap().collect();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].row, 12);
+        assert_eq!(errors[0].message, "Campaign exceeds maximum of 10 recipients");
+        assert!(consumed.get() < csv_data.len() / 100);
+    }
+
+    #[test]
+    fn test_validation_errors_are_capped() {
+        let mut csv_data = String::from("address,amount\n");
+        for _ in 0..(MAX_VALIDATION_ERRORS + 50) {
+            csv_data.push_str("0xThisIsNotAnAddress,1.0\n");
+        }
+        let reader = create_reader(&csv_data);
+
+        let errors =
+            CampaignCsvParsed::validation_errors(reader, 2, AddressType::Ethereum, &ParseOptions::default()).unwrap();
+        assert_eq!(errors.count(), MAX_VALIDATION_ERRORS);
+    }
+
+    #[test]
+    fn test_csv_total_amount_ceiling() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200";
+        let ceiling = |max_total| ParseOptions { max_total: Some(max_total), ..ParseOptions::default() };
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 0, AddressType::Ethereum, &ceiling(299));
+        let result = result.unwrap();
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 3);
+        assert_eq!(result.validation_errors[0].message, "Total amount exceeds representable range");
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 0, AddressType::Ethereum, &ceiling(300));
+        assert!(result.unwrap().validation_errors.is_empty());
+
+        let query = HashMap::from([("max_total".to_string(), "1e30".to_string())]);
+        assert_eq!(
+            ParseOptions::from_query(&query).unwrap_err(),
+            "The max_total query parameter should be a non-negative integer of base units"
+        );
+        let query = HashMap::from([("max_total".to_string(), "300".to_string())]);
+        assert_eq!(ParseOptions::from_query(&query).unwrap().max_total, Some(300));
+    }
+
+    #[test]
+    fn test_csv_amount_above_token_supply() {
+        let csv_data = "address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,100\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,1000000.5\n2wSs9UdwwnLjsjk9bMpErZ81BxaVAqXhtvdGnbNQPs6E,900";
+        let supply = |max_supply| ParseOptions { max_supply: Some(max_supply), ..ParseOptions::default() };
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 1, AddressType::Solana, &supply(10_000_000));
+        let result = result.unwrap();
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 3);
+        assert_eq!(result.validation_errors[0].message, "Amount exceeds the token supply of 10000000 base units");
+
+        // Every amount fits, but together they don't
+        let csv_data = "address,amount\n9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y,600\nAG9DgbRCHNgBMN9sUCJVcMCZBq5Lm5gAQmqmKMPrKfuE,600";
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 1, AddressType::Solana, &supply(10_000));
+        let result = result.unwrap();
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 3);
+        assert_eq!(result.validation_errors[0].message, "Total amount exceeds the token supply of 10000 base units");
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 1, AddressType::Solana, &supply(12_000));
+        assert!(result.unwrap().validation_errors.is_empty());
+
+        let query = HashMap::from([("max_supply".to_string(), "10k".to_string())]);
+        assert_eq!(
+            ParseOptions::from_query(&query).unwrap_err(),
+            "The max_supply query parameter should be a non-negative integer of base units"
+        );
+    }
+
+    #[test]
+    fn test_csv_semicolon_delimiter() {
+        let csv_data = "address;amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491;100,25\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc;200";
+        let query = HashMap::from([
+            ("delimiter".to_string(), ";".to_string()),
+            ("decimal_separator".to_string(), ",".to_string()),
+        ]);
+        let options = ParseOptions::from_query(&query).unwrap();
+
+        let result = CampaignCsvParsed::build_with_options(
+            options.reader(csv_data.as_bytes()),
+            2,
+            AddressType::Ethereum,
+            &options,
+        );
+        let result = result.unwrap();
+        assert!(result.validation_errors.is_empty());
+        assert_eq!(result.records[0].amount, 10025);
+        assert_eq!(result.total_amount, 30025);
+
+        let comma_only = ParseOptions { delimiter: b';', ..ParseOptions::default() };
+        let result = CampaignCsvParsed::build_with_options(
+            comma_only.reader(csv_data.as_bytes()),
+            2,
+            AddressType::Ethereum,
+            &comma_only,
+        );
+        assert_eq!(result.unwrap().validation_errors[0].message, "Amount is not a valid number");
+    }
+
+    #[test]
+    fn test_delimiter_query_validation() {
+        let options = |pairs: &[(&str, &str)]| {
+            ParseOptions::from_query(&pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect())
+        };
+
+        assert_eq!(options(&[]).unwrap().delimiter, b',');
+        assert_eq!(options(&[("delimiter", "\t")]).unwrap().delimiter, b'\t');
+        assert!(options(&[("delimiter", ";;")]).is_err());
+        assert!(options(&[("delimiter", "\"")]).is_err());
+        assert!(options(&[("decimal_separator", "_")]).is_err());
+        assert_eq!(
+            options(&[("decimal_separator", ",")]).unwrap_err(),
+            "The delimiter and the decimal separator should be different"
+        );
+        assert!(options(&[("delimiter", ";"), ("decimal_separator", ",")]).is_ok());
+    }
+
+    #[test]
+    fn test_csv_mixed_precision() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.25\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.5";
+        let uniform = ParseOptions { uniform_precision: true, ..ParseOptions::default() };
+
+        let result = CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &uniform);
+        let result = result.unwrap();
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 3);
+        assert_eq!(result.validation_errors[0].message, "Every amount should have exactly 2 decimals");
+
+        let result = CampaignCsvParsed::build_ethereum(create_reader(csv_data), 2).unwrap();
+        assert!(result.validation_errors.is_empty());
+        assert_eq!(result.total_amount, 30075);
+    }
+
+    #[test]
+    fn test_csv_uniform_precision() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.25\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.50";
+        let uniform = ParseOptions { uniform_precision: true, ..ParseOptions::default() };
+
+        let result = CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &uniform);
+        assert!(result.unwrap().validation_errors.is_empty());
+    }
+
+    #[test]
+    fn test_csv_scientific_notation() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,1e3\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,1.5E2";
+        let scientific = ParseOptions { accept_scientific: true, uniform_precision: true, ..ParseOptions::default() };
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &scientific);
+        let result = result.unwrap();
+        assert!(result.validation_errors.is_empty());
+        assert_eq!(result.records[0].amount, 100000);
+        assert_eq!(result.records[1].amount, 15000);
+
+        let result = CampaignCsvParsed::build_ethereum(create_reader(csv_data), 2).unwrap();
+        assert_eq!(result.validation_errors.len(), 2);
+    }
+
+    #[test]
+    fn test_csv_scientific_notation_out_of_range() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,1e40\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,1.234E1";
+        let scientific = ParseOptions { accept_scientific: true, ..ParseOptions::default() };
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &scientific);
+        let result = result.unwrap();
+        assert_eq!(result.validation_errors.len(), 1);
+        assert!(result.validation_errors[0].message.starts_with("Amount too large"));
+
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,1.2345E1\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,1e999";
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &scientific);
+        assert_eq!(result.unwrap().validation_errors.len(), 2);
+    }
+
+    #[test]
+    fn test_csv_rounding_modes() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.125\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.5";
+        let parse = |rounding| {
+            let options = ParseOptions { rounding, ..ParseOptions::default() };
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &options).unwrap()
+        };
+
+        let rejected = parse(Rounding::Reject);
+        assert_eq!(rejected.validation_errors.len(), 1);
+        assert_eq!(rejected.validation_errors[0].row, 2);
+        assert_eq!(rejected.validation_errors[0].message, "Amount has more than 2 decimal places");
+        assert_eq!(rejected.rounded_rows, 0);
+
+        let floored = parse(Rounding::Floor);
+        assert!(floored.validation_errors.is_empty());
+        assert_eq!(floored.records[0].amount, 10012);
+        assert_eq!(floored.total_amount, 30062);
+        assert_eq!(floored.rounded_rows, 1);
+
+        let rounded = parse(Rounding::Round);
+        assert!(rounded.validation_errors.is_empty());
+        assert_eq!(rounded.records[0].amount, 10013);
+        assert_eq!(rounded.total_amount, 30063);
+        assert_eq!(rounded.rounded_rows, 1);
+    }
+
+    #[test]
+    fn test_csv_rounding_to_zero() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,0.004\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,0.005";
+        let floor = ParseOptions { rounding: Rounding::Floor, ..ParseOptions::default() };
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &floor).unwrap();
+        let messages: Vec<&str> = result.validation_errors.iter().map(|error| error.message.as_str()).collect();
+        assert_eq!(messages, ["Amount is below one base unit (dust)", "Amount is below one base unit (dust)"]);
+
+        let round = ParseOptions { rounding: Rounding::Round, ..ParseOptions::default() };
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &round).unwrap();
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 2);
+    }
+
+    #[test]
+    fn test_csv_trailing_comma() {
+        let csv_data = "address,amount,\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0,\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0\n0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f492,300.0,, ";
+        let options = ParseOptions::default();
+        let result = CampaignCsvParsed::build_with_options(
+            options.reader(csv_data.as_bytes()),
+            2,
+            AddressType::Ethereum,
+            &options,
+        )
+        .unwrap();
+
+        assert!(result.validation_errors.is_empty());
+        assert_eq!(result.number_of_recipients, 3);
+        assert_eq!(result.total_amount, 60000);
+    }
+
+    #[test]
+    fn test_csv_extra_column_with_data() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100.0,\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0,,extra";
+        let options = ParseOptions::default();
+        let result = CampaignCsvParsed::build_with_options(
+            options.reader(csv_data.as_bytes()),
+            2,
+            AddressType::Ethereum,
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 3);
+        assert_eq!(
+            result.validation_errors[0].message,
+            "Invalid row: expected 2 columns, found 4. Line 3: `0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200.0,,extra`"
+        );
+    }
+
+    #[test]
+    fn test_csv_dust_amount() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,0.001\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,0.011\n0x0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f492,0";
+        let result = CampaignCsvParsed::build(create_reader(csv_data), 2, AddressType::Ethereum).unwrap();
+
+        let errors: Vec<(usize, &str)> =
+            result.validation_errors.iter().map(|error| (error.row, error.message.as_str())).collect();
+        assert_eq!(
+            errors,
+            [
+                (2, "Amount is below one base unit (dust)"),
+                (3, "Amount has more than 2 decimal places"),
+                (4, "The amount cannot be 0")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_thousands_separator() {
+        let grouped = ParseOptions { thousands_separator: true, ..ParseOptions::default() };
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,\"1,234.56\"\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,\"12,34.5\"";
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &grouped).unwrap();
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].row, 3);
+        assert_eq!(result.validation_errors[0].message, "Misplaced thousands separator");
+
+        let csv_data = "address;amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491;1,234.56\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc;1,000,000";
+        let options = ParseOptions { delimiter: b';', ..grouped };
+        let result = CampaignCsvParsed::build_with_options(
+            options.reader(csv_data.as_bytes()),
+            2,
+            AddressType::Ethereum,
+            &options,
+        );
+        let result = result.unwrap();
+        assert!(result.validation_errors.is_empty());
+        assert_eq!(result.records[0].amount, 123456);
+        assert_eq!(result.records[1].amount, 100000000);
+
+        // Without the flag the grouped amount is still rejected
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,\"1,234.56\"\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,1";
+        let result = CampaignCsvParsed::build_ethereum(create_reader(csv_data), 2).unwrap();
+        assert_eq!(result.validation_errors[0].message, "Amount is not a valid number");
+    }
+
+    #[test]
+    fn test_csv_amount_bounds() {
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,0.0001\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,1000000000000\n0x0x8ad7CAD4F10D0c3f875b8a2fd292590490c9f491,10\n0x0x7ad7CAD4F10D0c3f875b8a2fd292590490c9f491,1000";
+        let bounded = ParseOptions {
+            min_amount: Some(String::from("10")),
+            max_amount: Some(String::from("1000")),
+            ..ParseOptions::default()
+        };
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 4, AddressType::Ethereum, &bounded).unwrap();
+        let errors: Vec<(usize, &str)> =
+            result.validation_errors.iter().map(|error| (error.row, error.message.as_str())).collect();
+        assert_eq!(errors, [(2, "Amount is below the minimum of 10"), (3, "Amount exceeds the maximum of 1000")]);
+
+        let min_only = ParseOptions { min_amount: Some(String::from("0.00005")), ..ParseOptions::default() };
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 4, AddressType::Ethereum, &min_only)
+                .unwrap();
+        assert!(result.validation_errors.is_empty());
+    }
+
+    #[test]
+    fn test_amount_bounds_query_validation() {
+        let options = |pairs: &[(&str, &str)]| {
+            ParseOptions::from_query(&pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect())
+        };
+
+        assert_eq!(options(&[("min_amount", "0.5")]).unwrap().min_amount.as_deref(), Some("0.5"));
+        assert_eq!(options(&[("max_amount", "1000")]).unwrap().max_amount.as_deref(), Some("1000"));
+        assert!(options(&[("min_amount", "-1")]).is_err());
+        assert!(options(&[("max_amount", "1e12")]).is_err());
+    }
+
+    #[test]
+    fn test_csv_skip_invalid() {
+        let skip_invalid = ParseOptions { skip_invalid: true, ..ParseOptions::default() };
+        let csv_data = "address,amount\n0xThisIsNotAnAddress,100\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,0";
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &skip_invalid);
+        let result = result.unwrap();
+        assert_eq!(result.records.len(), 1);
+        assert_eq!(result.skipped.len(), 2);
+        assert_eq!(result.validation_errors.len(), 1);
+        assert_eq!(result.validation_errors[0].message, "An airstream campaign must have at least 2 valid recipients");
+
+        // Going over the recipient cap still rejects the file
+        let capped = ParseOptions { max_recipients: 2, ..skip_invalid };
+        let csv_data = "address,amount\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200\n0x0x8ad7CAD4F10D0c3f875b8a2fd292590490c9f491,300";
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 2, AddressType::Ethereum, &capped).unwrap();
+        assert!(result.skipped.is_empty());
+        assert_eq!(result.validation_errors[0].message, "Campaign exceeds maximum of 2 recipients");
+    }
+
+    #[test]
+    fn test_csv_canonical_order() {
+        let csv_data = "address,amount\n0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc,200\n0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491,100";
+        let canonical = ParseOptions { canonical: true, ..ParseOptions::default() };
+
+        let result =
+            CampaignCsvParsed::build_with_options(create_reader(csv_data), 0, AddressType::Ethereum, &canonical);
+        let addresses: Vec<_> = result.unwrap().records.into_iter().map(|r| r.address).collect();
+        assert_eq!(
+            addresses,
+            ["0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc"]
+        );
+
+        let result = CampaignCsvParsed::build_ethereum(create_reader(csv_data), 0).unwrap();
+        assert_eq!(result.records[0].address, "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc");
+    }
 }
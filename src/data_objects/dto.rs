@@ -1,12 +1,27 @@
+use crate::{
+    data_objects::query_param::{ClaimUrl, Eligibility, Root, Validity},
+    utils::solana_merkle::{HashAlgorithm, LeafOrder, TreeOptions},
+};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 /// Struct that represents the abstraction of an airstream campaign recipient
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct RecipientDto {
     pub address: String,
     pub amount: String,
+    /// Operator annotation from the optional `label` column. It is not part of the leaf, so it doesn't affect the
+    /// root, but it is pinned and served publicly with the campaign.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
+/// Tree format of EVM campaigns: OpenZeppelin's `StandardMerkleTree` dump
+pub const EVM_MERKLE_VERSION: &str = "oz-standard-v1";
+
+/// Tree format of Solana campaigns: a `solana_merkle::MerkleTree` dump, hashed as described by the campaign params
+pub const SOLANA_MERKLE_VERSION: &str = "solana-v1";
+
 /// Struct that represents the abstraction of an airstream campaign
 #[derive(Deserialize, Serialize, Debug)]
 pub struct PersistentCampaignDto {
@@ -14,5 +29,151 @@ pub struct PersistentCampaignDto {
     pub number_of_recipients: i32,
     pub root: String,
     pub merkle_tree: String,
+    /// Format of `merkle_tree`, which decides how proofs are read from it. Missing for campaigns created before it
+    /// was recorded, whose trees are in the first format of their chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merkle_version: Option<String>,
+    pub recipients: Vec<RecipientDto>,
+    /// Parameters the campaign was built with. Missing for campaigns created before they were recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<CampaignParamsDto>,
+    /// Unix time in seconds after which the campaign can no longer be claimed, if it has a deadline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+impl PersistentCampaignDto {
+    /// Check that the tree of the campaign is in the `expected` format, the one the caller reads proofs from
+    pub fn check_merkle_version(&self, expected: &str) -> Result<(), String> {
+        match self.merkle_version.as_deref() {
+            None => Ok(()),
+            Some(version) if version == expected => Ok(()),
+            Some(version) => Err(format!("Unsupported merkle version {version}")),
+        }
+    }
+}
+
+/// Only the root of a pinned campaign. The other fields of the campaign JSON are skipped instead of deserialized, so
+/// large recipient lists and trees are never allocated.
+#[derive(Deserialize, Debug)]
+pub struct CampaignRootDto {
+    pub root: String,
+}
+
+/// Body of the create and append endpoints: a `multipart/form-data` form whose `data` part holds the `address,amount`
+/// CSV file
+#[derive(ToSchema)]
+pub struct CampaignUpload {
+    #[schema(value_type = String, format = Binary)]
+    pub data: Vec<u8>,
+}
+
+/// Body of the JSON create endpoint: the recipients that would otherwise be sent as a CSV file. Amounts are decimal
+/// strings, e.g. `"100.5"`, so they are not rounded through floating point.
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct CampaignJsonUpload {
+    pub decimals: u16,
     pub recipients: Vec<RecipientDto>,
 }
+
+/// Body of the verified pin endpoint: the recipients of an EVM campaign along with the tree the client built from
+/// them. Amounts are decimal strings scaled by `decimals`, as in `CampaignJsonUpload`.
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct VerifiedCampaignUpload {
+    pub decimals: u16,
+    pub recipients: Vec<RecipientDto>,
+    /// Root of the client tree, which the tree rebuilt from `recipients` must have
+    pub root: String,
+    /// The client `StandardMerkleTree` dump, as a JSON string like the `merkle_tree` of a pinned campaign
+    pub merkle_tree: String,
+}
+
+/// Every parameter that affects a campaign root, so anyone can rebuild the tree from the recipients alone
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct CampaignParamsDto {
+    pub chain: String,
+    /// Decimals the CSV amounts were scaled by
+    pub decimals: usize,
+    pub hash_algorithm: HashAlgorithm,
+    pub leaf_order: LeafOrder,
+    /// Whether the encoded leaf is hashed a second time, to protect against second pre-image attacks
+    pub double_hash: bool,
+    /// How a level with an odd number of nodes is handled: `duplicate` hashes the last node with itself, while
+    /// `standard-v1` is OpenZeppelin's `StandardMerkleTree` layout
+    pub odd_node: String,
+    /// Hex salt mixed into the leaves, if any. For Solana campaigns it is the `domain` tag prepended to every leaf.
+    pub salt: Option<String>,
+    /// Index of the first recipient
+    pub index_base: u32,
+}
+
+impl CampaignParamsDto {
+    /// Parameters of an EVM campaign, built with OpenZeppelin's `StandardMerkleTree`
+    pub fn ethereum(decimals: usize) -> Self {
+        CampaignParamsDto {
+            chain: "ethereum".to_string(),
+            decimals,
+            hash_algorithm: HashAlgorithm::Keccak256,
+            leaf_order: LeafOrder::default(),
+            double_hash: true,
+            odd_node: "standard-v1".to_string(),
+            salt: None,
+            index_base: 0,
+        }
+    }
+
+    /// Parameters of a Solana campaign, built with `solana_merkle::MerkleTree`
+    pub fn solana(decimals: usize, tree: &TreeOptions) -> Self {
+        CampaignParamsDto {
+            chain: "solana".to_string(),
+            decimals,
+            hash_algorithm: tree.hash_algorithm,
+            leaf_order: tree.leaf_order,
+            double_hash: true,
+            odd_node: "duplicate".to_string(),
+            salt: tree.domain.as_ref().map(hex::encode),
+            index_base: 0,
+        }
+    }
+
+    /// Options to rebuild the Solana tree of the campaign with. Fails when the salt is not hex.
+    pub fn tree_options(&self) -> Result<TreeOptions, String> {
+        let domain = match &self.salt {
+            None => None,
+            Some(salt) => Some(hex::decode(salt).map_err(|_| format!("Invalid campaign salt `{salt}`"))?),
+        };
+
+        Ok(TreeOptions { leaf_order: self.leaf_order, hash_algorithm: self.hash_algorithm, domain })
+    }
+}
+
+/// Lightweight summary of an airstream campaign, pinned separately from the campaign data so listings don't have to
+/// download the full recipient list
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CampaignMetadataDto {
+    pub root: String,
+    pub total_amount: String,
+    pub number_of_recipients: i32,
+    pub chain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    pub created_at: String,
+}
+
+/// Body of the multi-campaign Solana eligibility endpoint: one address checked against every campaign of `cids`
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct MultiEligibility {
+    pub address: String,
+    pub cids: Vec<String>,
+}
+
+/// Operation of a batch request, named by its `op` field and taking the query parameters of the matching endpoint
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+    Eligibility(Eligibility),
+    EligibilitySolana(Eligibility),
+    Validity(Validity),
+    Root(Root),
+    ClaimUrl(ClaimUrl),
+}
@@ -1,29 +1,166 @@
 use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
 
 /// Query parameters for eligibility endpoint
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
 pub struct Eligibility {
+    /// Recipient address to look up
     #[serde(default = "default_string")]
     pub address: String,
 
+    /// CID of the pinned campaign
     #[serde(default = "default_string")]
     pub cid: String,
+
+    /// Sign the result with the server attestation key
+    #[serde(default)]
+    pub attest: bool,
 }
 
 fn default_string() -> String {
     "".to_string()
 }
 
+/// Query parameters for campaign endpoint
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Campaign {
+    /// CID of the pinned campaign
+    #[serde(default = "default_string")]
+    pub cid: String,
+}
+
+/// Query parameters for campaign exists endpoint
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CampaignExists {
+    /// CID to look up
+    #[serde(default = "default_string")]
+    pub cid: String,
+}
+
+/// Query parameters for claim url endpoint
+#[derive(Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct ClaimUrl {
+    /// Recipient address to look up
+    #[serde(default = "default_string")]
+    pub address: String,
+
+    /// CID of the pinned campaign
+    #[serde(default = "default_string")]
+    pub cid: String,
+
+    /// Chain of the campaign, `ethereum` or `solana`
+    #[serde(default = "default_string")]
+    pub chain: String,
+}
+
 /// Query parameters for create endpoint
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct Create {
+    /// Decimals of the campaign token, used to scale the CSV amounts. Optional when `token_address` and `chain_id`
+    /// are given.
     #[serde(default = "default_string")]
     pub decimals: String,
+
+    /// ERC-20 contract of the campaign token, whose `decimals()` is read when `decimals` is omitted
+    #[serde(default)]
+    pub token_address: Option<String>,
+
+    /// Chain of `token_address`, served by the `RPC_URL_<chain_id>` endpoint
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+
+    /// Claim deadline of the campaign, in unix seconds. Eligibility lookups report the campaign as expired after it.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+
+    /// Read the campaign back from IPFS once pinned and report in `verified` whether it is intact
+    #[serde(default)]
+    pub verify: bool,
 }
 
-/// Query parameters for validity endpoint
+/// Query parameters of the create endpoints requesting a separate metadata pin (`metadata=true`)
 #[derive(Deserialize)]
+pub struct MetadataPin {
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Query parameters for validity endpoint
+#[derive(Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
 pub struct Validity {
+    /// CID of the pinned campaign
+    #[serde(default = "default_string")]
+    pub cid: String,
+}
+
+/// Query parameters for append endpoint
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Append {
+    /// CID of the pinned campaign the recipients are appended to
+    #[serde(default = "default_string")]
+    pub cid: String,
+}
+
+/// Query parameters for root endpoint
+#[derive(Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct Root {
+    /// CID of the pinned campaign
     #[serde(default = "default_string")]
     pub cid: String,
 }
+
+/// Query parameters for sign endpoint
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Sign {
+    /// CID of the campaign the token grants access to
+    #[serde(default = "default_string")]
+    pub cid: String,
+
+    /// Lifetime of the token in seconds, `DEFAULT_TTL` when empty
+    #[serde(default = "default_string")]
+    pub ttl: String,
+}
+
+/// Query parameters for export endpoint
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Export {
+    /// CID of the pinned campaign
+    #[serde(default = "default_string")]
+    pub cid: String,
+}
+
+/// Query parameters for proofs endpoint
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Proofs {
+    /// CID of the pinned campaign
+    #[serde(default = "default_string")]
+    pub cid: String,
+
+    /// Index of the first recipient returned
+    #[serde(default)]
+    pub offset: usize,
+
+    /// Number of recipients returned, all of those after `offset` when omitted. At most `MAX_PROOF_ENTRIES`.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Query parameters for health endpoint
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Health {
+    /// Also check that Pinata is reachable
+    #[serde(default)]
+    pub deep: bool,
+}
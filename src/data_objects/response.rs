@@ -1,6 +1,14 @@
-use crate::utils::csv_validator::ValidationError;
+use crate::{
+    data_objects::dto::CampaignParamsDto,
+    utils::{attestation::Attestation, csv_validator::ValidationError},
+};
+use bytes::Bytes;
+use http_body::Frame;
+use http_body_util::StreamBody;
 use serde::Serialize;
 use serde_json::{json, Value as Json};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
 use vercel_runtime as Vercel;
 
 /// Eligibility results are deterministic per (cid, address) because CIDs are immutable.
@@ -8,45 +16,243 @@ use vercel_runtime as Vercel;
 /// round-tripping to Pinata — it's our replacement for the Redis CID cache.
 const IMMUTABLE_CACHE_CONTROL: &str = "public, s-maxage=31536000, immutable";
 
+/// Dumps of a whole campaign are just as immutable, but they are answered to an authenticated request and hold every
+/// recipient: a shared cache must not store them and serve them to callers without the token, only the client may.
+const PRIVATE_CACHE_CONTROL: &str = "private, max-age=31536000, immutable";
+
+/// Stable, machine-readable reason of an error response. Clients should branch on it rather than on `message`, whose
+/// wording may change.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    Unauthorized,
+    MethodNotAllowed,
+    InvalidQuery,
+    UnsupportedOperation,
+    InvalidDecimals,
+    TokenDecimalsUnresolved,
+    InvalidFile,
+    PayloadTooLarge,
+    BatchTooLarge,
+    CsvValidationFailed,
+    InvalidRecipients,
+    CsvParsingFailed,
+    InvalidCid,
+    CampaignNotFound,
+    IpfsDownloadFailed,
+    IpfsUploadFailed,
+    IpfsUnpinFailed,
+    MalformedCampaign,
+    UnsupportedMerkleVersion,
+    CampaignNotAppendable,
+    TreeVerificationFailed,
+    ServerBusy,
+    AddressNotEligible,
+    AttestationUnavailable,
+    AccessTokensUnavailable,
+    RootMismatch,
+    ResponseTooLarge,
+}
+
 /// Generic Error Response structure
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct GeneralErrorResponse {
     pub message: String,
+    /// Always set on error responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    /// Seconds to wait before retrying, when an upstream provider suggested it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_seconds: Option<u64>,
 }
 
 /// Struct for the response of the create endpoint when the provided csv is invalid
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct ValidationErrorResponse {
     pub status: String,
+    pub code: ErrorCode,
     pub errors: Vec<ValidationError>,
 }
 
 /// Struct for the success response of the create endpoint
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct UploadSuccessResponse {
     pub status: String,
     pub root: String,
     pub total: String,
     pub recipients: String,
     pub cid: String,
+    /// CIDs returned by every pinner that pinned the campaign, `cid` first. They match unless a provider derives CIDs
+    /// differently.
+    pub cids: Vec<String>,
+    /// Size in bytes of the pinned campaign file, as reported by Pinata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pin_size: Option<u64>,
+    /// Whether the recipients were sorted by address before indexing (`canonical=true`). When set, leaf indices follow
+    /// that order rather than the order of the uploaded file.
+    pub canonical: bool,
+    /// Number of proof elements of the deepest leaf, for estimating the claim verification gas
+    pub tree_depth: u32,
+    /// Number of amounts rounded to the token precision under `rounding=floor|round`
+    pub rounded_rows: usize,
+    /// Rows left out of the campaign under `skip_invalid=true`, with the reason
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<ValidationError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_cid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<CampaignParamsDto>,
+    /// Whether the campaign read back from IPFS has the root of its recipients, only under `verify=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+impl UploadSuccessResponse {
+    /// Depth of a binary Merkle tree over `leaf_count` leaves, i.e. the longest proof it produces
+    pub fn tree_depth(leaf_count: usize) -> u32 {
+        leaf_count.next_power_of_two().ilog2()
+    }
+}
+
+/// Struct for the response of the dry-run validation endpoint. The campaign fields are only set for a valid file.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct DryRunResponse {
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipients: Option<String>,
+    pub errors: Vec<ValidationError>,
 }
 
 /// Struct for the success response of the eligibility endpoint
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct EligibilityResponse {
     pub index: usize,
     pub proof: Vec<String>,
+    /// Length `proof` was checked against, for verifiers that reject proofs of an unexpected length. Only set by the
+    /// Solana endpoint, whose proofs all have the depth of the tree.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_proof_len: Option<usize>,
     pub address: String,
     pub amount: String,
+    /// `amount` formatted with the campaign decimals, e.g. `1,234.5`. Only present when the campaign recorded its
+    /// params; `amount` stays the authoritative value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_display: Option<String>,
+    /// Operator annotation of the recipient, when the campaign CSV had a `label` column
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Attestation>,
+}
+
+/// Struct for one recipient of the proofs endpoint, the eligibility answer of every recipient of a campaign
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ProofEntry {
+    pub index: usize,
+    pub address: String,
+    pub amount: String,
+    pub proof: Vec<String>,
+}
+
+/// Struct for one campaign of the multi-campaign eligibility endpoint. The proof is only set when the address is
+/// eligible, and `error` when the campaign could not be checked.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct MultiEligibilityEntry {
+    pub cid: String,
+    pub eligible: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Struct for the response of the eligibility endpoints once the campaign claim deadline has passed. The proof would
+/// still verify on-chain, the deadline is reported so the claim UI can stop offering it.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CampaignExpiredResponse {
+    pub eligible: bool,
+    pub reason: String,
+}
+
+/// Struct for the success response of the claim url endpoint
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ClaimUrlResponse {
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Struct for the success response of the campaign exists endpoint. `valid_shape` is only set when the CID is pinned.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CampaignExistsResponse {
+    pub exists: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_shape: Option<bool>,
+}
+
+/// Struct for the success response of the sign endpoint
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SignResponse {
+    pub token: String,
+    /// Unix time in seconds at which the token stops being accepted
+    pub expires_at: i64,
+}
+
+/// Struct for the success response of the root endpoint
+#[derive(Serialize, Debug, ToSchema)]
+pub struct RootResponse {
+    pub root: String,
+}
+
+/// Informational success response, e.g. of a successful unpin
+#[derive(Serialize, Debug, ToSchema)]
+pub struct MessageResponse {
+    pub message: String,
+}
+
+/// Struct for the response of the health endpoint. `ipfs` is only set by a deep check.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct HealthResponse {
+    pub status: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ipfs: Option<String>,
+}
+
+/// Struct for the success response of the attestation key endpoint
+#[derive(Serialize, Debug, ToSchema)]
+pub struct AttestationKeyResponse {
+    pub algorithm: String,
+    /// Base58 Ed25519 public key that signs the eligibility attestations
+    pub public_key: String,
+}
+
+/// Struct for one result of the batch endpoint: the status and body the endpoint of the operation would answer. It
+/// describes the serialized `R` of the operation for the OpenAPI document.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BatchResult {
+    pub status: u16,
+    #[schema(value_type = Object)]
+    pub message: Json,
 }
 
 /// Struct for the success response of the validity endpoint
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct ValidResponse {
     pub root: String,
     pub total: String,
     pub recipients: String,
     pub cid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<CampaignParamsDto>,
 }
 
 /// Generic API response
@@ -56,51 +262,176 @@ pub struct R {
     pub message: Json,
     #[serde(skip)]
     pub cache_control: Option<&'static str>,
+    #[serde(skip)]
+    pub etag: Option<String>,
 }
 
 /// Create a Bad Request type of response
 pub fn bad_request(json_response: Json) -> R {
-    R { status: 400, message: json_response, cache_control: None }
+    R { status: 400, message: json_response, cache_control: None, etag: None }
 }
 
 /// Create an Ok type of response
 pub fn ok(json_response: Json) -> R {
-    R { status: 200, message: json_response, cache_control: None }
+    R { status: 200, message: json_response, cache_control: None, etag: None }
+}
+
+/// Create a Service Unavailable type of response
+pub fn service_unavailable(json_response: Json) -> R {
+    R { status: 503, message: json_response, cache_control: None, etag: None }
 }
 
 /// Same as `ok`, but flags the response as immutably cacheable at Vercel's edge.
 /// Use only for responses that are deterministic for a given URL (query string
 /// included), such as eligibility results keyed by an immutable CID.
 pub fn ok_immutable(json_response: Json) -> R {
-    R { status: 200, message: json_response, cache_control: Some(IMMUTABLE_CACHE_CONTROL) }
+    R { status: 200, message: json_response, cache_control: Some(IMMUTABLE_CACHE_CONTROL), etag: None }
+}
+
+/// Create a response without a body, e.g. the answer to a `HEAD` request. `immutable` flags it as cacheable at
+/// Vercel's edge like `ok_immutable`.
+pub fn empty(status: u16, immutable: bool) -> R {
+    R { status, message: Json::Null, cache_control: immutable.then_some(IMMUTABLE_CACHE_CONTROL), etag: None }
+}
+
+/// Strong validator of a response: the quoted SHA-256 of its JSON body
+pub fn etag(response: &R) -> String {
+    format!("\"{}\"", hex::encode(Sha256::digest(response.message.to_string())))
+}
+
+/// Set the `ETag` of an immutable response and answer 304 Not Modified in its place when `if_none_match`, the
+/// request `If-None-Match` header, already names it. An `ETag` already set, e.g. the one of the GET answer on a
+/// body-less `HEAD` answer, is kept. Other responses are returned as they are.
+pub fn not_modified_if(response: R, if_none_match: Option<&str>) -> R {
+    if response.status != 200 || response.cache_control.is_none() {
+        return response;
+    }
+
+    let etag = response.etag.clone().unwrap_or_else(|| etag(&response));
+    let cached = if_none_match.is_some_and(|header| {
+        header.split(',').map(str::trim).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+    });
+    if cached {
+        return R { status: 304, message: Json::Null, cache_control: response.cache_control, etag: Some(etag) };
+    }
+    R { etag: Some(etag), ..response }
 }
 
-/// Build a `GeneralErrorResponse`-shaped response with the given status and message.
+/// Build a `MessageResponse` with the given status and message. Errors go through `error` instead.
 pub fn message(status: u16, message: impl Into<String>) -> R {
-    R { status, message: json!(GeneralErrorResponse { message: message.into() }), cache_control: None }
+    let message = MessageResponse { message: message.into() };
+    R { status, message: json!(message), cache_control: None, etag: None }
 }
 
-/// Shorthand for `to_vercel(message(status, body))`, used by controllers to return
+/// Build a `GeneralErrorResponse` error with the given status, code and message.
+pub fn error(status: u16, code: ErrorCode, message: impl Into<String>) -> R {
+    error_with_retry_after(status, code, message, None)
+}
+
+/// Same as `error`, with the wait in seconds an upstream provider suggested before retrying, if any
+pub fn error_with_retry_after(status: u16, code: ErrorCode, message: impl Into<String>, retry_after: Option<u64>) -> R {
+    let message = GeneralErrorResponse { message: message.into(), code: Some(code), retry_after_seconds: retry_after };
+    R { status, message: json!(message), cache_control: None, etag: None }
+}
+
+/// Shorthand for `to_vercel(error(status, code, body))`, used by controllers to return
 /// a Vercel-formatted `GeneralErrorResponse` in one call.
-pub fn to_vercel_message(
+pub fn to_vercel_error(
     status: u16,
+    code: ErrorCode,
     body: impl Into<String>,
 ) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
-    to_vercel(message(status, body))
+    to_vercel(error(status, code, body))
 }
 
-/// Converts a generic response in the format required by the Vercel serverless functions
-pub fn to_vercel(response: R) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
-    let mut builder = Vercel::Response::builder()
-        .status(response.status)
-        .header("content-type", "application/json")
+/// Response builder with the headers shared by every endpoint
+fn vercel_builder(status: u16, content_type: &str) -> http::response::Builder {
+    Vercel::Response::builder()
+        .status(status)
+        .header("content-type", content_type)
         .header("Access-Control-Allow-Origin", "*")
         .header("Access-Control-Allow-Methods", "GET, POST, PATCH, PUT, DELETE, OPTIONS")
-        .header("Access-Control-Allow-Headers", "Content-Type, Authorization");
+        .header("Access-Control-Allow-Headers", "Content-Type, Authorization")
+}
+
+/// Converts a generic response in the format required by the Vercel serverless functions
+pub fn to_vercel(response: R) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    let mut builder = vercel_builder(response.status, "application/json");
 
     if let Some(cc) = response.cache_control {
         builder = builder.header("Cache-Control", cc);
     }
+    if let Some(etag) = response.etag {
+        builder = builder.header("ETag", etag);
+    }
 
+    // A 304 repeats the headers of the cached response, without its body
+    if response.status == 304 {
+        return Ok(builder.body(String::new().into())?);
+    }
     Ok(builder.body(response.message.to_string().into())?)
 }
+
+/// Same as `to_vercel`, with the body left out for a `HEAD` request
+pub fn to_vercel_head(response: R) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    let mut builder = vercel_builder(response.status, "application/json");
+
+    if let Some(cc) = response.cache_control {
+        builder = builder.header("Cache-Control", cc);
+    }
+    if let Some(etag) = response.etag {
+        builder = builder.header("ETag", etag);
+    }
+
+    Ok(builder.body(String::new().into())?)
+}
+
+/// Response body sending each chunk as soon as the iterator yields it
+fn stream_body<I>(chunks: I) -> Vercel::ResponseBody
+where
+    I: Iterator<Item = Bytes> + Send + Sync + 'static,
+{
+    StreamBody::new(tokio_stream::iter(chunks.map(|chunk| Ok(Frame::data(chunk))))).into()
+}
+
+/// Streams the items as newline-delimited JSON. Each line is sent as soon as the iterator yields it.
+pub fn to_vercel_ndjson<I>(items: I) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error>
+where
+    I: Iterator + Send + Sync + 'static,
+    I::Item: Serialize,
+{
+    let lines = items.map(|item| Bytes::from(format!("{}\n", json!(item))));
+
+    Ok(vercel_builder(200, "application/x-ndjson").body(stream_body(lines))?)
+}
+
+/// Streams the items as a single JSON array, each element sent as soon as the iterator yields it. The array is keyed
+/// by an immutable CID, so the client may cache it for good, but shared caches may not.
+pub fn to_vercel_json_array<I>(items: I) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error>
+where
+    I: Iterator + Send + Sync + 'static,
+    I::Item: Serialize,
+{
+    let elements = items.enumerate().map(|(i, item)| {
+        let separator = if i == 0 { "" } else { "," };
+        Bytes::from(format!("{separator}{}", json!(item)))
+    });
+    let chunks =
+        std::iter::once(Bytes::from_static(b"[")).chain(elements).chain(std::iter::once(Bytes::from_static(b"]")));
+
+    Ok(vercel_builder(200, "application/json")
+        .header("Cache-Control", PRIVATE_CACHE_CONTROL)
+        .body(stream_body(chunks))?)
+}
+
+/// Streams already encoded CSV rows as a `file_name` attachment. The export is keyed by an immutable CID, so it is
+/// cached like the proofs dump: for good by the client, never by shared caches.
+pub fn to_vercel_csv<I>(file_name: &str, rows: I) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error>
+where
+    I: Iterator<Item = Vec<u8>> + Send + Sync + 'static,
+{
+    Ok(vercel_builder(200, "text/csv")
+        .header("Content-Disposition", format!("attachment; filename=\"{file_name}\""))
+        .header("Cache-Control", PRIVATE_CACHE_CONTROL)
+        .body(stream_body(rows.map(Bytes::from)))?)
+}
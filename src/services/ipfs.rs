@@ -1,28 +1,78 @@
+use crate::{
+    csv_campaign_parser::CampaignCsvRecord,
+    data_objects::dto::{PersistentCampaignDto, EVM_MERKLE_VERSION},
+    utils::{csv_validator::canonical_eth_address, evm_merkle::build_tree},
+};
+use bytes::Bytes;
 use dotenvy::dotenv;
-use reqwest::multipart::{Form, Part};
-
-use serde_json::json;
-
-use crate::data_objects::dto::PersistentCampaignDto;
-use serde::{de::DeserializeOwned, Deserialize};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use reqwest::{
+    multipart::{Form, Part},
+    RequestBuilder, StatusCode,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 /// The success response after an upload request to Pinata
 #[derive(Deserialize, Debug)]
 pub struct PinataSuccess {
     #[serde(rename = "IpfsHash")]
     pub ipfs_hash: String,
+    /// Size in bytes of the pinned file
+    #[serde(rename = "PinSize", default)]
+    pub pin_size: Option<u64>,
+    /// Time of the pin, as an ISO 8601 string
+    #[serde(rename = "Timestamp", default)]
+    pub timestamp: Option<String>,
 }
 
-/// Errors surfaced from `download_from_ipfs`. Callers today only check `is_err()`,
-/// but the variants are kept distinct so callers can distinguish permanent (client)
-/// from transient (upstream) failures when they want to.
+/// A document pinned by a `Pinner`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pinned {
+    pub cid: String,
+    /// Size in bytes of the pinned file, when the provider reports it
+    pub size: Option<u64>,
+}
+
+/// Errors surfaced from `download_from_ipfs`. The variants are kept distinct so callers
+/// can distinguish permanent (client) from transient (upstream) failures, e.g. a CID
+/// that is simply not pinned (`NotFound`) from a gateway outage (`Upstream`).
 #[derive(Debug)]
 pub enum IpfsError {
     Request(reqwest::Error),
     Deserialize(serde_json::Error),
     InvalidCid,
     NotFound,
-    Upstream { status: u16, body: String },
+    Upstream {
+        status: u16,
+        body: String,
+    },
+    /// Pinata kept answering 429, with the wait it suggested in `Retry-After`, in seconds
+    RateLimited {
+        retry_after: Option<u64>,
+    },
+}
+
+impl IpfsError {
+    /// Seconds to wait before retrying, when the provider suggested it
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for IpfsError {
@@ -35,6 +85,8 @@ impl std::fmt::Display for IpfsError {
             Self::Upstream { status, body } => {
                 write!(f, "ipfs upstream error {status}: {body}")
             }
+            Self::RateLimited { retry_after: Some(seconds) } => write!(f, "ipfs rate limited, retry after {seconds}s"),
+            Self::RateLimited { retry_after: None } => write!(f, "ipfs rate limited"),
         }
     }
 }
@@ -70,11 +122,62 @@ pub fn try_deserialize_pinata_response(response_body: &str) -> Result<PinataSucc
     serde_json::from_str::<PinataSuccess>(response_body)
 }
 
-/// Upload and pin a JSON representing a valid processed airstream campaign
-pub async fn upload_to_ipfs(data: &PersistentCampaignDto) -> Result<String, reqwest::Error> {
-    dotenv().ok();
+/// Number of times a Pinata upload is retried when the `PINATA_UPLOAD_RETRIES` env var is unset or invalid
+pub const DEFAULT_UPLOAD_RETRIES: u32 = 3;
+
+/// Backoff before the first retry. It doubles for every further retry.
+const UPLOAD_BACKOFF_BASE_MS: u64 = 200;
+
+fn upload_retries() -> u32 {
+    std::env::var("PINATA_UPLOAD_RETRIES")
+        .ok()
+        .and_then(|retries| retries.parse().ok())
+        .unwrap_or(DEFAULT_UPLOAD_RETRIES)
+}
+
+/// Rate limiting and server errors are transient, anything else (e.g. rejected API keys) fails the same way again
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Longest `Retry-After` waited for between attempts. A longer hint ends the retries, as the Lambda would time out,
+/// and is passed on to the client instead.
+const MAX_RETRY_AFTER_WAIT_SECS: u64 = 5;
+
+/// Wait suggested by the `Retry-After` header, in seconds. Both the delay and the HTTP date forms are read.
+fn retry_after(response: &reqwest::Response) -> Option<u64> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(seconds);
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(u64::try_from((date.timestamp() - chrono::Utc::now().timestamp()).max(0)).unwrap_or_default())
+}
+
+/// Exponential backoff with full jitter, so concurrent uploads don't retry in lockstep
+fn backoff(retry: u32) -> Duration {
+    let max_delay = UPLOAD_BACKOFF_BASE_MS << retry.saturating_sub(1).min(6);
+    Duration::from_millis(rand::thread_rng().gen_range(0..=max_delay))
+}
+
+/// Authenticate a Pinata API request. A `PINATA_JWT` is sent as a bearer token when set; otherwise the legacy
+/// `PINATA_API_KEY` / `PINATA_SECRET_API_KEY` header pair is used.
+fn pinata_auth(request: RequestBuilder) -> RequestBuilder {
+    if let Some(jwt) = std::env::var("PINATA_JWT").ok().filter(|jwt| !jwt.is_empty()) {
+        return request.bearer_auth(jwt);
+    }
+
     let pinata_api_key = std::env::var("PINATA_API_KEY").expect("PINATA_API_KEY must be set");
     let pinata_secret_api_key = std::env::var("PINATA_SECRET_API_KEY").expect("PINATA_SECRET_API_KEY must be set");
+    request.header("pinata_api_key", pinata_api_key).header("pinata_secret_api_key", pinata_secret_api_key)
+}
+
+/// Upload and pin a JSON representing a valid processed airstream campaign. Uploads rejected with 429 or 5xx are
+/// retried after the `Retry-After` wait, or with backoff without one; the response of the last attempt is returned,
+/// except a 429 which is reported as `IpfsError::RateLimited` with the suggested wait.
+pub async fn upload_to_ipfs<T: Serialize>(data: &T) -> Result<String, IpfsError> {
+    dotenv().ok();
     let pinata_api_server = std::env::var("PINATA_API_SERVER").expect("PINATA_API_SERVER must be set");
 
     let client = reqwest::Client::new();
@@ -83,20 +186,286 @@ pub async fn upload_to_ipfs(data: &PersistentCampaignDto) -> Result<String, reqw
 
     let serialized_data = json!(data);
     let bytes = serde_json::to_vec(&serialized_data).unwrap();
-    let part = Part::bytes(bytes).file_name("data.json").mime_str("application/json")?;
+    let retries = upload_retries();
+
+    let mut retry = 0;
+    loop {
+        // The multipart body is consumed by the request, so every attempt builds its own form
+        let part = Part::bytes(bytes.clone()).file_name("data.json").mime_str("application/json")?;
+        let form = Form::new().part("file", part);
+
+        let response = pinata_auth(client.post(&api_endpoint)).multipart(form).send().await?;
+        let status = response.status();
+        let retry_after = retry_after(&response);
+
+        if retry < retries && is_retryable(status) && retry_after.is_none_or(|wait| wait <= MAX_RETRY_AFTER_WAIT_SECS) {
+            retry += 1;
+            tokio::time::sleep(retry_after.map_or_else(|| backoff(retry), Duration::from_secs)).await;
+            continue;
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(IpfsError::RateLimited { retry_after });
+        }
+        return Ok(response.text().await?);
+    }
+}
+
+/// Upload and pin a JSON document to the current `store`, returning its CID
+pub async fn pin_json<T: Serialize>(data: &T) -> Result<String, IpfsError> {
+    Ok(store().pin(&json!(data)).await?.cid)
+}
+
+/// Future returned by `Pinner::pin`, boxed so pinners can be used as trait objects
+pub type PinFuture<'a> = Pin<Box<dyn Future<Output = Result<Pinned, IpfsError>> + Send + 'a>>;
+
+/// Future returned by the other `IpfsStore` methods
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, IpfsError>> + Send + 'a>>;
+
+/// A provider that stores and pins JSON documents on IPFS
+pub trait Pinner: Send + Sync {
+    /// Name of the provider, used in logs
+    fn name(&self) -> &str;
+
+    /// Pin `document` as a JSON file, returning its CID and size
+    fn pin<'a>(&'a self, document: &'a Value) -> PinFuture<'a>;
+}
+
+/// Pinata, configured by the `PINATA_*` env vars. It is always the first pinner.
+pub struct Pinata;
+
+impl Pinner for Pinata {
+    fn name(&self) -> &str {
+        "pinata"
+    }
+
+    fn pin<'a>(&'a self, document: &'a Value) -> PinFuture<'a> {
+        Box::pin(async move {
+            let pinned = try_deserialize_pinata_response(&upload_to_ipfs(document).await?)?;
+            Ok(Pinned { cid: pinned.ipfs_hash, size: pinned.pin_size })
+        })
+    }
+}
+
+/// A store campaigns are pinned to and read back from. Pinata is the store of the deployed API, while `InMemory` keeps
+/// everything in the process, for tests and local runs without network.
+pub trait IpfsStore: Pinner {
+    /// Download the raw content of `cid`, whose format was already checked
+    fn fetch<'a>(&'a self, cid: &'a str) -> StoreFuture<'a, Bytes>;
+
+    /// Unpin `cid`, whose format was already checked. A CID that is not pinned is reported as `IpfsError::NotFound`.
+    fn unpin<'a>(&'a self, cid: &'a str) -> StoreFuture<'a, ()>;
+}
+
+impl IpfsStore for Pinata {
+    fn fetch<'a>(&'a self, cid: &'a str) -> StoreFuture<'a, Bytes> {
+        Box::pin(fetch_raw_from_pinata(cid))
+    }
+
+    fn unpin<'a>(&'a self, cid: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(unpin_from_pinata(cid))
+    }
+}
+
+/// Store keeping the pinned documents in memory, addressed by the SHA-256 of their content. Clones share their files.
+#[derive(Clone, Default)]
+pub struct InMemory {
+    files: Arc<Mutex<HashMap<String, Bytes>>>,
+}
+
+impl InMemory {
+    /// Whether `cid` is pinned in the store
+    pub fn contains(&self, cid: &str) -> bool {
+        self.files.lock().unwrap().contains_key(cid)
+    }
+}
+
+impl Pinner for InMemory {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn pin<'a>(&'a self, document: &'a Value) -> PinFuture<'a> {
+        let pinned = serde_json::to_vec(document).map(|bytes| {
+            let cid = format!("memory-{}", hex::encode(Sha256::digest(&bytes)));
+            let size = Some(bytes.len() as u64);
+            self.files.lock().unwrap().insert(cid.clone(), Bytes::from(bytes));
+            Pinned { cid, size }
+        });
+        Box::pin(async move { Ok(pinned?) })
+    }
+}
+
+impl IpfsStore for InMemory {
+    fn fetch<'a>(&'a self, cid: &'a str) -> StoreFuture<'a, Bytes> {
+        let file = self.files.lock().unwrap().get(cid).cloned();
+        Box::pin(async move { file.ok_or(IpfsError::NotFound) })
+    }
+
+    fn unpin<'a>(&'a self, cid: &'a str) -> StoreFuture<'a, ()> {
+        let file = self.files.lock().unwrap().remove(cid);
+        Box::pin(async move { file.map(|_| ()).ok_or(IpfsError::NotFound) })
+    }
+}
+
+/// In-memory store of the whole process, used when the `IPFS_STORE` env var is `memory`
+static MEMORY_STORE: Lazy<InMemory> = Lazy::new(InMemory::default);
+
+tokio::task_local! {
+    /// Store set by `with_store` for the futures it runs
+    static SCOPED_STORE: InMemory;
+}
 
-    let form = Form::new().part("file", part);
+/// Run `f` with `store` in place of Pinata and the other pinners, so handlers can be exercised end to end in-process
+pub async fn with_store<F: Future>(store: InMemory, f: F) -> F::Output {
+    SCOPED_STORE.scope(store, f).await
+}
+
+/// The in-memory store in use, if any: the one of the enclosing `with_store` scope, otherwise the process one when
+/// `IPFS_STORE=memory`
+fn memory_store() -> Option<InMemory> {
+    SCOPED_STORE.try_with(InMemory::clone).ok().or_else(|| {
+        dotenv().ok();
+        std::env::var("IPFS_STORE").is_ok_and(|store| store == "memory").then(|| MEMORY_STORE.clone())
+    })
+}
+
+/// The store campaigns are read from and unpinned at: the in-memory one when set, otherwise Pinata
+pub fn store() -> Box<dyn IpfsStore> {
+    match memory_store() {
+        Some(store) => Box::new(store),
+        None => Box::new(Pinata),
+    }
+}
+
+/// Any provider exposing the Kubo RPC `add` endpoint with bearer auth, e.g. Filebase's IPFS RPC API. Files are added
+/// as CIDv0 with the default chunker, like Pinata does, so both providers derive the same CID.
+pub struct KuboRpc {
+    pub url: String,
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+struct KuboAddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+impl Pinner for KuboRpc {
+    fn name(&self) -> &str {
+        "kubo-rpc"
+    }
+
+    fn pin<'a>(&'a self, document: &'a Value) -> PinFuture<'a> {
+        Box::pin(async move {
+            let part =
+                Part::bytes(serde_json::to_vec(document)?).file_name("data.json").mime_str("application/json")?;
+            let response = reqwest::Client::new()
+                .post(format!("{}/api/v0/add?cid-version=0&pin=true", self.url))
+                .bearer_auth(&self.token)
+                .multipart(Form::new().part("file", part))
+                .send()
+                .await?;
+
+            let status = response.status();
+            let body = response.text().await?;
+            if !status.is_success() {
+                return Err(IpfsError::Upstream { status: status.as_u16(), body });
+            }
+            Ok(Pinned { cid: serde_json::from_str::<KuboAddResponse>(&body)?.hash, size: None })
+        })
+    }
+}
+
+/// The configured pinners: Pinata, plus a Kubo RPC provider when `IPFS_RPC_URL` and `IPFS_RPC_TOKEN` are set. An
+/// in-memory store replaces all of them.
+pub fn pinners() -> Vec<Box<dyn Pinner>> {
+    if let Some(store) = memory_store() {
+        return vec![Box::new(store)];
+    }
+
+    dotenv().ok();
+    let mut pinners: Vec<Box<dyn Pinner>> = vec![Box::new(Pinata)];
+    if let (Ok(url), Ok(token)) = (std::env::var("IPFS_RPC_URL"), std::env::var("IPFS_RPC_TOKEN")) {
+        if !url.is_empty() {
+            pinners.push(Box::new(KuboRpc { url, token }));
+        }
+    }
+    pinners
+}
+
+/// Pin a JSON document with every pinner, in order. Succeeds when at least one of them did, with the successful
+/// pins; otherwise returns the error of the last pinner.
+pub async fn pin_everywhere<T: Serialize>(pinners: &[Box<dyn Pinner>], data: &T) -> Result<Vec<Pinned>, IpfsError> {
+    let document = json!(data);
+    let mut pins: Vec<Pinned> = Vec::new();
+    let mut last_error = None;
+
+    for pinner in pinners {
+        match pinner.pin(&document).await {
+            Ok(pinned) => pins.push(pinned),
+            Err(error) => {
+                tracing::error!("{} pin failed: {error}", pinner.name());
+                last_error = Some(error);
+            }
+        }
+    }
+
+    if pins.windows(2).any(|pair| pair[0].cid != pair[1].cid) {
+        let cids: Vec<&str> = pins.iter().map(|pinned| pinned.cid.as_str()).collect();
+        tracing::warn!("pinners returned different CIDs for the same document: {cids:?}");
+    }
+    match last_error {
+        Some(error) if pins.is_empty() => Err(error),
+        _ => Ok(pins),
+    }
+}
+
+/// Lightweight readiness probe: checks that Pinata is reachable and accepts the configured API keys.
+pub async fn check_pinata_auth() -> Result<(), IpfsError> {
+    dotenv().ok();
+    let pinata_api_server = std::env::var("PINATA_API_SERVER").expect("PINATA_API_SERVER must be set");
+
+    let request = reqwest::Client::new().get(format!("{pinata_api_server}/data/testAuthentication"));
+    let response = pinata_auth(request).send().await?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    Err(IpfsError::Upstream { status: status.as_u16(), body: response.text().await? })
+}
+
+/// Unpin a CID from the current `store`. A CID that is not pinned is reported as `IpfsError::NotFound`.
+pub async fn unpin(cid: &str) -> Result<(), IpfsError> {
+    if !is_cid_format_valid(cid) {
+        return Err(IpfsError::InvalidCid);
+    }
+
+    store().unpin(cid).await
+}
+
+/// Unpin a CID from the Pinata account
+async fn unpin_from_pinata(cid: &str) -> Result<(), IpfsError> {
+    dotenv().ok();
+    let pinata_api_server = std::env::var("PINATA_API_SERVER").expect("PINATA_API_SERVER must be set");
+
+    let request = reqwest::Client::new().delete(format!("{pinata_api_server}/pinning/unpin/{cid}"));
+    let response = pinata_auth(request).send().await?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
 
-    let response = client
-        .post(api_endpoint)
-        .header("pinata_api_key", pinata_api_key)
-        .header("pinata_secret_api_key", pinata_secret_api_key)
-        .multipart(form)
-        .send()
-        .await?;
+    // Pinata answers an unknown CID with a 400 carrying this reason rather than a 404
+    let body = response.text().await?;
+    if status == StatusCode::NOT_FOUND || body.contains("CURRENT_USER_HAS_NOT_PINNED_CID") {
+        return Err(IpfsError::NotFound);
+    }
 
-    let text_response = response.text().await?;
-    Ok(text_response)
+    Err(IpfsError::Upstream { status: status.as_u16(), body })
 }
 
 /// Conservative CID sanity check. Keeps genuine CIDs (base58/base32 strings) intact
@@ -107,19 +476,65 @@ fn is_cid_format_valid(cid: &str) -> bool {
     !cid.is_empty() && cid.len() <= 120 && cid.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
 }
 
-/// Download the content from a specified CID through Pinata. Callers rely on
+/// Download the content from a specified CID through the current `store`. Callers rely on
 /// Vercel's edge cache (via `Cache-Control` on the outer response) to avoid
 /// re-fetching the same CID.
 pub async fn download_from_ipfs<T: DeserializeOwned>(cid: &str) -> Result<T, IpfsError> {
+    let raw = download_raw_from_ipfs(cid).await?;
+    serde_json::from_slice(&raw).map_err(IpfsError::from)
+}
+
+/// Download the campaign pinned at `cid` and check that it is intact: its `root` is `expected_root`, and so is the root
+/// of the tree rebuilt from its recipients. Only EVM campaigns are rebuilt, any other tree format fails the check.
+pub async fn verify_pin(cid: &str, expected_root: &str) -> Result<bool, IpfsError> {
+    let campaign = download_from_ipfs::<PersistentCampaignDto>(cid).await?;
+    if !campaign.root.eq_ignore_ascii_case(expected_root) || campaign.check_merkle_version(EVM_MERKLE_VERSION).is_err()
+    {
+        return Ok(false);
+    }
+
+    // The leaf encoding panics on a value it cannot parse, so the recipients are checked first
+    let mut records = Vec::with_capacity(campaign.recipients.len());
+    for recipient in &campaign.recipients {
+        let (Some(address), Ok(amount)) = (canonical_eth_address(&recipient.address), recipient.amount.parse()) else {
+            return Ok(false);
+        };
+        records.push(CampaignCsvRecord { address, amount, label: None });
+    }
+    if records.is_empty() {
+        return Ok(false);
+    }
+
+    Ok(build_tree(&records).root().eq_ignore_ascii_case(expected_root))
+}
+
+/// Download the content from a specified CID without parsing it, for callers that forward or stream the campaign
+/// instead of deserializing it whole
+pub async fn download_raw_from_ipfs(cid: &str) -> Result<Bytes, IpfsError> {
     if !is_cid_format_valid(cid) {
         return Err(IpfsError::InvalidCid);
     }
+    if let Ok(Some(raw)) = DOWNLOADS.try_with(|downloads| downloads.borrow().get(cid).cloned()) {
+        return Ok(raw);
+    }
 
-    let raw = fetch_raw_from_pinata(cid).await?;
-    serde_json::from_str(&raw).map_err(IpfsError::from)
+    let raw = store().fetch(cid).await?;
+    let _ = DOWNLOADS.try_with(|downloads| downloads.borrow_mut().insert(cid.to_string(), raw.clone()));
+    Ok(raw)
 }
 
-async fn fetch_raw_from_pinata(cid: &str) -> Result<String, IpfsError> {
+tokio::task_local! {
+    /// Campaigns already downloaded inside a `with_download_cache` scope, by CID
+    static DOWNLOADS: RefCell<HashMap<String, Bytes>>;
+}
+
+/// Run `f` with the campaigns it downloads kept in memory, so a CID is fetched from IPFS once however many times `f`
+/// reads it. Outside of such a scope every download goes to IPFS.
+pub async fn with_download_cache<F: Future>(f: F) -> F::Output {
+    DOWNLOADS.scope(RefCell::new(HashMap::new()), f).await
+}
+
+async fn fetch_raw_from_pinata(cid: &str) -> Result<Bytes, IpfsError> {
     dotenv().ok();
     let ipfs_gateway = std::env::var("IPFS_GATEWAY").expect("IPFS_GATEWAY must be set");
     let pinata_access_token = std::env::var("PINATA_ACCESS_TOKEN").expect("PINATA_ACCESS_TOKEN must be set");
@@ -127,28 +542,34 @@ async fn fetch_raw_from_pinata(cid: &str) -> Result<String, IpfsError> {
 
     let response = reqwest::get(&ipfs_url).await?;
     let status = response.status();
-    let text = response.text().await?;
+    let raw = response.bytes().await?;
 
     if status.is_success() {
-        Ok(text)
-    } else if status.is_client_error() {
+        Ok(raw)
+    } else if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE {
         Err(IpfsError::NotFound)
     } else {
-        Err(IpfsError::Upstream { status: status.as_u16(), body: text })
+        Err(IpfsError::Upstream { status: status.as_u16(), body: String::from_utf8_lossy(&raw).into_owned() })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::async_test::{setup_env_vars, SERVER};
+    use crate::{
+        data_objects::dto::RecipientDto,
+        utils::async_test::{setup_env_vars, SERVER},
+    };
+    use mockito::Matcher;
 
     #[test]
     fn try_deserialize_pinata_response_success() {
         let result: Result<PinataSuccess, serde_json::Error> = try_deserialize_pinata_response(
             r#"{"IpfsHash": "test_hash", "PinSize": 123, "Timestamp": "2023-04-05T00:00:00Z"}"#,
         );
-        assert!(result.is_ok());
+        let pinned = result.unwrap();
+        assert_eq!(pinned.pin_size, Some(123));
+        assert_eq!(pinned.timestamp.as_deref(), Some("2023-04-05T00:00:00Z"));
     }
 
     #[test]
@@ -193,7 +614,10 @@ mod tests {
             number_of_recipients: 4,
             root: "test_root".to_string(),
             merkle_tree: "test_merkle".to_string(),
+            merkle_version: None,
             recipients: Vec::new(),
+            params: None,
+            expires_at: None,
         };
         let result = upload_to_ipfs(&data).await;
 
@@ -202,6 +626,104 @@ mod tests {
         drop(server);
     }
 
+    /// Pinner answering with a fixed result, without any request
+    struct MockPinner(Option<&'static str>);
+
+    impl Pinner for MockPinner {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn pin<'a>(&'a self, _document: &'a Value) -> PinFuture<'a> {
+            let result = self
+                .0
+                .map(|cid| Pinned { cid: cid.to_string(), size: None })
+                .ok_or(IpfsError::Upstream { status: 503, body: String::new() });
+            Box::pin(async move { result })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pin_everywhere_tolerates_a_failing_pinner() {
+        let pinners: Vec<Box<dyn Pinner>> = vec![Box::new(MockPinner(None)), Box::new(MockPinner(Some("QmCid")))];
+        let pins = pin_everywhere(&pinners, &json!({"root": "test_root"})).await.unwrap();
+        assert_eq!(pins, [Pinned { cid: "QmCid".to_string(), size: None }]);
+
+        let pinners: Vec<Box<dyn Pinner>> = vec![Box::new(MockPinner(None)), Box::new(MockPinner(None))];
+        let result = pin_everywhere(&pinners, &json!({"root": "test_root"})).await;
+        assert!(matches!(result, Err(IpfsError::Upstream { status: 503, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_kubo_rpc_pin() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("POST", "/api/v0/add")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("cid-version".into(), "0".into()),
+                Matcher::UrlEncoded("pin".into(), "true".into()),
+            ]))
+            .match_header("authorization", "Bearer mock_rpc_token")
+            .with_status(200)
+            .with_body(r#"{"Name":"data.json","Hash":"QmKuboCid","Size":"42"}"#)
+            .create();
+
+        let pinner = KuboRpc { url: server.url(), token: "mock_rpc_token".to_string() };
+        let pinned = pinner.pin(&json!({"root": "test_root"})).await.unwrap();
+
+        assert_eq!(pinned.cid, "QmKuboCid");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_upload_to_ipfs_prefers_jwt() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        std::env::set_var("PINATA_JWT", "mock_pinata_jwt");
+
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .match_header("authorization", "Bearer mock_pinata_jwt")
+            .match_header("pinata_api_key", Matcher::Missing)
+            .match_header("pinata_secret_api_key", Matcher::Missing)
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "test_hash", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .create();
+
+        let result = pin_json(&json!({"root": "test_root"})).await;
+        std::env::remove_var("PINATA_JWT");
+
+        assert_eq!(result.unwrap(), "test_hash");
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_unpin_falls_back_to_api_keys() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        std::env::set_var("PINATA_JWT", "");
+
+        let mock = server
+            .mock("DELETE", "/pinning/unpin/pinned_cid")
+            .match_header("authorization", Matcher::Missing)
+            .match_header("pinata_api_key", "mock_pinata_api_key")
+            .match_header("pinata_secret_api_key", "mock_pinata_secret_key")
+            .with_status(200)
+            .with_body("OK")
+            .create();
+
+        let result = unpin("pinned_cid").await;
+        std::env::remove_var("PINATA_JWT");
+
+        assert!(result.is_ok());
+        mock.assert();
+        drop(server);
+    }
+
     #[tokio::test]
     async fn test_upload_to_ipfs_error() {
         let mut server = SERVER.lock().await;
@@ -212,6 +734,7 @@ mod tests {
             .mock("POST", "/pinning/pinFileToIPFS")
             .with_status(500)
             .with_body(r#"{"code": "500", "message": "Internal server error"}"#)
+            .expect(DEFAULT_UPLOAD_RETRIES as usize + 1)
             .create();
 
         // Call the function with a test data object
@@ -220,7 +743,10 @@ mod tests {
             number_of_recipients: 4,
             root: "test_root".to_string(),
             merkle_tree: "test_merkle".to_string(),
+            merkle_version: None,
             recipients: Vec::new(),
+            params: None,
+            expires_at: None,
         };
         let result = upload_to_ipfs(&data).await;
 
@@ -231,6 +757,69 @@ mod tests {
         drop(server);
     }
 
+    #[tokio::test]
+    async fn test_upload_to_ipfs_retries_rate_limit() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let rate_limited = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(429)
+            .with_body(r#"{"error": "Too many requests"}"#)
+            .expect(2)
+            .create();
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(200)
+            .with_body(r#"{"IpfsHash": "test_hash", "PinSize": 123, "Timestamp": "2021-01-01T00:00:00Z"}"#)
+            .create();
+
+        let result = pin_json(&serde_json::json!({ "root": "test_root" })).await;
+
+        assert_eq!(result.unwrap(), "test_hash");
+        rate_limited.assert();
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_upload_to_ipfs_surfaces_retry_after() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        // A wait longer than the Lambda can afford is not retried but passed on
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(429)
+            .with_header("Retry-After", "120")
+            .with_body(r#"{"error": "Too many requests"}"#)
+            .expect(1)
+            .create();
+
+        let result = pin_json(&serde_json::json!({ "root": "test_root" })).await;
+
+        let error = result.unwrap_err();
+        assert!(matches!(error, IpfsError::RateLimited { retry_after: Some(120) }));
+        assert_eq!(error.retry_after(), Some(120));
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_upload_to_ipfs_does_not_retry_auth_error() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let mock = server
+            .mock("POST", "/pinning/pinFileToIPFS")
+            .with_status(401)
+            .with_body(r#"{"error": "Invalid API key"}"#)
+            .create();
+
+        let result = pin_json(&serde_json::json!({ "root": "test_root" })).await;
+
+        assert!(result.is_err());
+        mock.assert();
+        drop(server);
+    }
+
     #[tokio::test]
     async fn test_download_from_ipfs_success() {
         let mut server = SERVER.lock().await;
@@ -250,6 +839,25 @@ mod tests {
         drop(server);
     }
 
+    #[tokio::test]
+    async fn test_download_raw_from_ipfs() {
+        let mut server = SERVER.lock().await;
+
+        setup_env_vars(&server);
+
+        let body = r#"{"root": "0xroot", "recipients": []}"#;
+        let mock = server
+            .mock("GET", "/raw_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(body)
+            .create();
+
+        let raw = download_raw_from_ipfs("raw_cid").await.unwrap();
+        assert_eq!(raw, body.as_bytes());
+        mock.assert();
+        drop(server);
+    }
+
     #[tokio::test]
     async fn test_download_from_ipfs_error() {
         let mut server = SERVER.lock().await;
@@ -268,4 +876,87 @@ mod tests {
         mock.assert();
         drop(server);
     }
+
+    #[tokio::test]
+    async fn test_download_from_ipfs_not_found() {
+        let mut server = SERVER.lock().await;
+
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/unpinned_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(404)
+            .with_body("Not Found")
+            .create();
+
+        let result: Result<PinataSuccess, _> = download_from_ipfs("unpinned_cid").await;
+        assert!(matches!(result, Err(IpfsError::NotFound)));
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_download_from_ipfs_upstream_error() {
+        let mut server = SERVER.lock().await;
+
+        setup_env_vars(&server);
+
+        let mock = server
+            .mock("GET", "/valid_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(502)
+            .with_body("Bad Gateway")
+            .create();
+
+        let result: Result<PinataSuccess, _> = download_from_ipfs("valid_cid").await;
+        assert!(matches!(result, Err(IpfsError::Upstream { status: 502, .. })));
+        mock.assert();
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_verify_pin() {
+        let records: Vec<CampaignCsvRecord> =
+            ["0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491", "0xf976aF93B0A5A9F55A7f285a3B5355B8575Eb5bc"]
+                .iter()
+                .zip([100u128, 200])
+                .map(|(address, amount)| CampaignCsvRecord { address: address.to_string(), amount, label: None })
+                .collect();
+        let tree = build_tree(&records);
+        let campaign = |amounts: [&str; 2]| PersistentCampaignDto {
+            total_amount: "300".to_string(),
+            number_of_recipients: 2,
+            root: tree.root(),
+            merkle_tree: serde_json::to_string(&tree.dump()).unwrap(),
+            merkle_version: Some(EVM_MERKLE_VERSION.to_string()),
+            recipients: records
+                .iter()
+                .zip(amounts)
+                .map(|(r, amount)| RecipientDto { address: r.address.clone(), amount: amount.to_string(), label: None })
+                .collect(),
+            params: None,
+            expires_at: None,
+        };
+
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let intact = server
+            .mock("GET", "/intact_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(serde_json::to_string(&campaign(["100", "200"])).unwrap())
+            .expect(2)
+            .create();
+        // The stored root is untouched, but a recipient amount is not the one the tree was built with
+        let tampered = server
+            .mock("GET", "/tampered_cid?pinataGatewayToken=mock_pinata_access_token")
+            .with_status(200)
+            .with_body(serde_json::to_string(&campaign(["100", "2000"])).unwrap())
+            .create();
+
+        assert!(verify_pin("intact_cid", &tree.root()).await.unwrap());
+        assert!(!verify_pin("intact_cid", &format!("0x{}", "00".repeat(32))).await.unwrap());
+        assert!(!verify_pin("tampered_cid", &tree.root()).await.unwrap());
+        intact.assert();
+        tampered.assert();
+        drop(server);
+    }
 }
@@ -0,0 +1,184 @@
+use crate::utils::csv_validator::is_valid_eth_address;
+use dotenvy::dotenv;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::json;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Selector of the ERC-20 `decimals()` function
+const DECIMALS_SELECTOR: &str = "0x313ce567";
+
+/// Resolved decimals per `(chain_id, lowercase token address)`. Token decimals never change, so entries live as long
+/// as the Lambda instance.
+static DECIMALS_CACHE: Lazy<Mutex<HashMap<(u64, String), usize>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Errors surfaced from `resolve_decimals`. None of them carries the RPC URL, which usually embeds a provider key.
+#[derive(Debug)]
+pub enum TokenError {
+    InvalidAddress,
+    MissingRpc(u64),
+    Request(reqwest::Error),
+    Rpc(String),
+    InvalidResult(String),
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidAddress => write!(f, "invalid token address"),
+            Self::MissingRpc(chain_id) => write!(f, "no RPC_URL_{chain_id} configured"),
+            Self::Request(e) => write!(f, "rpc request error: {e}"),
+            Self::Rpc(message) => write!(f, "rpc error: {message}"),
+            Self::InvalidResult(result) => write!(f, "unexpected decimals() result: {result}"),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+impl From<reqwest::Error> for TokenError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Request(e.without_url())
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<String>,
+    error: Option<RpcError>,
+}
+
+/// Decodes the ABI-encoded `uint8` returned by `decimals()`
+fn decode_decimals(result: &str) -> Option<usize> {
+    let bytes = hex::decode(result.strip_prefix("0x")?).ok()?;
+    let (padding, value) = bytes.split_last_chunk::<1>()?;
+    (bytes.len() == 32 && padding.iter().all(|b| *b == 0)).then_some(value[0].into())
+}
+
+/// Reads the decimals of an ERC-20 token through the JSON-RPC endpoint of its chain, configured by the
+/// `RPC_URL_<chain_id>` env var. Successful lookups are cached.
+pub async fn resolve_decimals(chain_id: u64, token_address: &str) -> Result<usize, TokenError> {
+    if !is_valid_eth_address(token_address) {
+        return Err(TokenError::InvalidAddress);
+    }
+
+    let key = (chain_id, token_address.to_lowercase());
+    if let Some(decimals) = DECIMALS_CACHE.lock().ok().and_then(|cache| cache.get(&key).copied()) {
+        return Ok(decimals);
+    }
+
+    dotenv().ok();
+    let Ok(rpc_url) = std::env::var(format!("RPC_URL_{chain_id}")) else {
+        return Err(TokenError::MissingRpc(chain_id));
+    };
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_call",
+        "params": [{ "to": token_address, "data": DECIMALS_SELECTOR }, "latest"],
+    });
+    let response: RpcResponse = reqwest::Client::new().post(rpc_url).json(&request).send().await?.json().await?;
+
+    if let Some(error) = response.error {
+        return Err(TokenError::Rpc(error.message));
+    }
+    let result = response.result.unwrap_or_default();
+    let Some(decimals) = decode_decimals(&result) else {
+        return Err(TokenError::InvalidResult(result));
+    };
+
+    if let Ok(mut cache) = DECIMALS_CACHE.lock() {
+        cache.insert(key, decimals);
+    }
+    Ok(decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::async_test::{setup_env_vars, SERVER};
+    use mockito::Matcher;
+
+    /// Points `RPC_URL_<chain_id>` at the mock server until dropped, so the variable never outlives the test, even
+    /// when it fails
+    struct RpcUrl(u64);
+
+    impl RpcUrl {
+        fn set(chain_id: u64, server: &mockito::Server) -> Self {
+            std::env::set_var(format!("RPC_URL_{chain_id}"), format!("{}/rpc/{chain_id}", server.url()));
+            RpcUrl(chain_id)
+        }
+    }
+
+    impl Drop for RpcUrl {
+        fn drop(&mut self) {
+            std::env::remove_var(format!("RPC_URL_{}", self.0));
+        }
+    }
+
+    fn rpc_result(decimals: u8) -> String {
+        format!(r#"{{"jsonrpc":"2.0","id":1,"result":"0x{}{decimals:02x}"}}"#, "0".repeat(62))
+    }
+
+    #[tokio::test]
+    async fn test_resolve_decimals_is_cached() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let rpc_url = RpcUrl::set(31337, &server);
+
+        let mock = server
+            .mock("POST", "/rpc/31337")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::Regex(r#""method":"eth_call""#.to_string()),
+                Matcher::Regex(DECIMALS_SELECTOR.to_string()),
+            ]))
+            .with_status(200)
+            .with_body(rpc_result(6))
+            .expect(1)
+            .create();
+
+        let token = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+        assert_eq!(resolve_decimals(31337, token).await.unwrap(), 6);
+        assert_eq!(resolve_decimals(31337, &token.to_lowercase()).await.unwrap(), 6);
+
+        mock.assert();
+        drop(rpc_url);
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_decimals_errors() {
+        let mut server = SERVER.lock().await;
+        setup_env_vars(&server);
+        let rpc_url = RpcUrl::set(31338, &server);
+
+        let mock = server
+            .mock("POST", "/rpc/31338")
+            .with_status(200)
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"execution reverted"}}"#)
+            .create();
+
+        let token = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+        assert!(matches!(resolve_decimals(31338, token).await, Err(TokenError::Rpc(_))));
+        assert!(matches!(resolve_decimals(31339, token).await, Err(TokenError::MissingRpc(31339))));
+        assert!(matches!(resolve_decimals(31338, "0xnotatoken").await, Err(TokenError::InvalidAddress)));
+
+        mock.assert();
+        drop(rpc_url);
+        drop(server);
+    }
+
+    #[test]
+    fn test_decode_decimals() {
+        assert_eq!(decode_decimals(&format!("0x{}12", "0".repeat(62))), Some(18));
+        assert_eq!(decode_decimals("0x12"), None);
+        assert_eq!(decode_decimals(&format!("0x01{}", "0".repeat(62))), None);
+        assert_eq!(decode_decimals("0x"), None);
+    }
+}
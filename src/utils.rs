@@ -20,7 +20,13 @@ pub mod async_test {
         std::env::set_var("PINATA_API_SERVER", server_host);
     }
 }
+pub mod access_token;
+pub mod attestation;
 pub mod auth;
+pub mod build_pool;
+pub mod cors;
 pub mod csv_validator;
+pub mod evm_merkle;
+pub mod logging;
 pub mod request;
 pub mod solana_merkle;
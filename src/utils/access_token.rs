@@ -0,0 +1,96 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Load the HMAC key of the access tokens from the `ACCESS_TOKEN_SECRET` env var. Returns `None` when it is missing or
+/// empty, in which case no token is issued or accepted.
+pub fn secret() -> Option<String> {
+    std::env::var("ACCESS_TOKEN_SECRET").ok().filter(|secret| !secret.is_empty())
+}
+
+/// The MAC of a token. The CID never holds a `:`, so the message cannot be read as another `(cid, expires_at)` pair.
+fn mac(secret: &str, cid: &str, expires_at: i64) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{cid}:{expires_at}").as_bytes());
+    mac
+}
+
+/// Sign an access token to the eligibility of the campaign `cid`, valid until the unix time `expires_at`. The token
+/// is `<expires_at>.<hex HMAC-SHA256>`.
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::utils::access_token::{sign, verify};
+///
+/// let token = sign("secret", "cid", 1_700_003_600);
+/// assert!(verify("secret", &token, "cid", 1_700_000_000));
+/// assert!(!verify("secret", &token, "other_cid", 1_700_000_000));
+/// ```
+pub fn sign(secret: &str, cid: &str, expires_at: i64) -> String {
+    let signature = mac(secret, cid, expires_at).finalize().into_bytes();
+    format!("{expires_at}.{}", hex::encode(signature))
+}
+
+/// Check that `token` was signed with `secret` for `cid` and has not expired at the unix time `now`. The signature is
+/// compared in constant time.
+pub fn verify(secret: &str, token: &str, cid: &str, now: i64) -> bool {
+    let Some((expires_at, signature)) = token.split_once('.') else {
+        return false;
+    };
+    let Ok(expires_at) = expires_at.parse::<i64>() else {
+        return false;
+    };
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+
+    now < expires_at && mac(secret, cid, expires_at).verify_slice(&signature).is_ok()
+}
+
+/// Check the `token` query parameter of a request for `cid` against the configured secret. Fail-closed: without a
+/// secret every token is rejected.
+pub fn is_valid(token: Option<&String>, cid: &str, now: i64) -> bool {
+    match (secret(), token) {
+        (Some(secret), Some(token)) => verify(&secret, token, cid, now),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn accepts_valid_token() {
+        let token = sign("secret", "valid_cid", NOW + 60);
+        assert!(verify("secret", &token, "valid_cid", NOW));
+    }
+
+    #[test]
+    fn rejects_expired_token() {
+        let token = sign("secret", "valid_cid", NOW + 60);
+        assert!(!verify("secret", &token, "valid_cid", NOW + 60));
+        assert!(!verify("secret", &token, "valid_cid", NOW + 3600));
+    }
+
+    #[test]
+    fn rejects_tampered_token() {
+        let token = sign("secret", "valid_cid", NOW + 60);
+        let (_, signature) = token.split_once('.').unwrap();
+
+        // A later expiry with the same signature
+        assert!(!verify("secret", &format!("{}.{signature}", NOW + 3600), "valid_cid", NOW));
+        // Another campaign, or another secret
+        assert!(!verify("secret", &token, "other_cid", NOW));
+        assert!(!verify("other_secret", &token, "valid_cid", NOW));
+        // A flipped signature digit, or no signature at all
+        let flipped = if token.ends_with('0') { "1" } else { "0" };
+        assert!(!verify("secret", &format!("{}{flipped}", &token[..token.len() - 1]), "valid_cid", NOW));
+        assert!(!verify("secret", &format!("{}.", NOW + 60), "valid_cid", NOW));
+        assert!(!verify("secret", "not a token", "valid_cid", NOW));
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// The eligibility facts covered by an attestation. Field order is fixed so the serialized payload is canonical.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct AttestationPayload {
+    pub cid: String,
+    pub address: String,
+    pub index: usize,
+    pub amount: String,
+    pub root: String,
+    pub issued_at: i64,
+}
+
+/// An Ed25519 signature by the server over the canonical JSON `payload`
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct Attestation {
+    pub payload: String,
+    pub signature: String,
+    pub public_key: String,
+}
+
+/// Load the attestation keypair from the base58-encoded `ATTESTATION_SECRET_KEY` env var. Returns `None` when it is
+/// missing or malformed.
+pub fn signing_key() -> Option<Keypair> {
+    let secret = std::env::var("ATTESTATION_SECRET_KEY").ok()?;
+    let bytes = bs58::decode(secret.trim()).into_vec().ok()?;
+    Keypair::from_bytes(&bytes).ok()
+}
+
+/// Sign the canonical serialization of `payload`
+pub fn sign(keypair: &Keypair, payload: &AttestationPayload) -> Attestation {
+    let payload = serde_json::to_string(payload).unwrap();
+    let signature = keypair.sign_message(payload.as_bytes());
+
+    Attestation { payload, signature: signature.to_string(), public_key: keypair.pubkey().to_string() }
+}
+
+/// Check that the attestation signature matches its payload and public key
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::utils::attestation::{sign, verify, AttestationPayload};
+/// use solana_sdk::signature::Keypair;
+///
+/// let payload = AttestationPayload {
+///     cid: "cid".to_string(),
+///     address: "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y".to_string(),
+///     index: 0,
+///     amount: "100".to_string(),
+///     root: "0x00".to_string(),
+///     issued_at: 1_700_000_000,
+/// };
+/// let attestation = sign(&Keypair::new(), &payload);
+/// assert!(verify(&attestation));
+/// ```
+pub fn verify(attestation: &Attestation) -> bool {
+    let Ok(public_key) = Pubkey::from_str(&attestation.public_key) else {
+        return false;
+    };
+    let Ok(signature) = Signature::from_str(&attestation.signature) else {
+        return false;
+    };
+
+    signature.verify(public_key.as_ref(), attestation.payload.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> AttestationPayload {
+        AttestationPayload {
+            cid: "valid_cid".to_string(),
+            address: "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y".to_string(),
+            index: 3,
+            amount: "10000".to_string(),
+            root: "0xe51044cc70a2ed388c9fed090e7f1401278de3f5fec8a0d0c6b5176c9ebe3b93".to_string(),
+            issued_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn valid_attestation_verifies() {
+        let keypair = Keypair::new();
+        let attestation = sign(&keypair, &payload());
+
+        assert!(verify(&attestation));
+        assert_eq!(attestation.public_key, keypair.pubkey().to_string());
+        assert_eq!(serde_json::from_str::<AttestationPayload>(&attestation.payload).unwrap(), payload());
+    }
+
+    #[test]
+    fn tampered_attestation_fails() {
+        let mut attestation = sign(&Keypair::new(), &payload());
+        attestation.payload = attestation.payload.replace("10000", "99999");
+        assert!(!verify(&attestation));
+
+        let mut attestation = sign(&Keypair::new(), &payload());
+        attestation.public_key = Keypair::new().pubkey().to_string();
+        assert!(!verify(&attestation));
+    }
+}
@@ -1,20 +1,62 @@
+use crate::utils::{access_token, request};
+use subtle::ConstantTimeEq;
 use vercel_runtime as Vercel;
 
 /// Shared bearer-token check. Returns true only when the `Authorization` header
 /// is exactly `Bearer <MERKLE_API_BEARER_TOKEN>`. Fail-closed on misconfiguration:
 /// missing or empty env var rejects every request.
 pub fn is_authorized(req: &Vercel::Request) -> bool {
-    let expected = match std::env::var("MERKLE_API_BEARER_TOKEN") {
-        Ok(token) if !token.is_empty() => token,
-        _ => return false,
-    };
+    let header = req.headers().get("Authorization").and_then(|header| header.to_str().ok());
+    let expected = std::env::var("MERKLE_API_BEARER_TOKEN").ok();
+
+    matches_bearer(header, expected.as_deref())
+}
 
-    let Some(header) = req.headers().get("Authorization") else {
+/// Bearer check of `is_authorized`, or else a `token` query parameter issued by the sign endpoint for `cid` and not
+/// yet expired. Used by the endpoints that operators can share per campaign.
+pub fn is_authorized_for(req: &Vercel::Request, cid: &str) -> bool {
+    let now = chrono::Utc::now().timestamp();
+    is_authorized(req) || access_token::is_valid(request::query_params(req).get("token"), cid, now)
+}
+
+/// Compare the `Authorization` header value against the expected token. The token comparison runs in constant time
+/// so response timing does not leak how many leading bytes matched.
+fn matches_bearer(header: Option<&str>, expected: Option<&str>) -> bool {
+    let Some(expected) = expected.filter(|token| !token.is_empty()) else {
         return false;
     };
-    let Ok(value) = header.to_str() else {
+    let Some(token) = header.and_then(|value| value.strip_prefix("Bearer ")) else {
         return false;
     };
 
-    value == format!("Bearer {expected}")
+    token.as_bytes().ct_eq(expected.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_correct_token() {
+        assert!(matches_bearer(Some("Bearer secret"), Some("secret")));
+    }
+
+    #[test]
+    fn rejects_wrong_token() {
+        assert!(!matches_bearer(Some("Bearer secreT"), Some("secret")));
+        assert!(!matches_bearer(Some("Bearer secret2"), Some("secret")));
+        assert!(!matches_bearer(Some("bearer secret"), Some("secret")));
+        assert!(!matches_bearer(Some("secret"), Some("secret")));
+    }
+
+    #[test]
+    fn rejects_missing_header() {
+        assert!(!matches_bearer(None, Some("secret")));
+    }
+
+    #[test]
+    fn rejects_every_request_without_token_configured() {
+        assert!(!matches_bearer(Some("Bearer secret"), None));
+        assert!(!matches_bearer(Some("Bearer "), Some("")));
+    }
 }
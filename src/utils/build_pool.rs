@@ -0,0 +1,74 @@
+use once_cell::sync::Lazy;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Concurrent builds allowed when the `MAX_CONCURRENT_BUILDS` env var is unset or invalid
+pub const DEFAULT_MAX_CONCURRENT_BUILDS: usize = 2;
+
+/// How long a create request waits for a build slot before it is turned away
+pub const BUILD_QUEUE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Build slots shared by the create handlers of this instance, sized by the `MAX_CONCURRENT_BUILDS` env var
+pub static BUILDS: Lazy<BuildPool> = Lazy::new(|| {
+    let max_builds = std::env::var("MAX_CONCURRENT_BUILDS")
+        .ok()
+        .and_then(|max| max.parse().ok())
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_BUILDS);
+    BuildPool::new(max_builds, BUILD_QUEUE_TIMEOUT)
+});
+
+/// Bounds how many campaigns are parsed and built at once. Vercel may route several requests to the same instance, and
+/// every build holds the whole campaign and its tree in memory, so excess requests queue instead.
+pub struct BuildPool {
+    permits: Semaphore,
+    timeout: Duration,
+}
+
+/// No build slot freed up before the queue timeout
+#[derive(Debug, PartialEq)]
+pub struct Busy;
+
+impl BuildPool {
+    /// Pool of `max_builds` slots, at least one, whose waiters give up after `timeout`
+    pub fn new(max_builds: usize, timeout: Duration) -> Self {
+        BuildPool { permits: Semaphore::new(max_builds.max(1)), timeout }
+    }
+
+    /// Wait for a build slot. The slot is released when the returned permit is dropped.
+    pub async fn acquire(&self) -> Result<SemaphorePermit<'_>, Busy> {
+        match tokio::time::timeout(self.timeout, self.permits.acquire()).await {
+            Ok(Ok(permit)) => Ok(permit),
+            _ => Err(Busy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn saturated_pool_turns_requests_away() {
+        let pool = BuildPool::new(2, Duration::from_millis(50));
+        let first = pool.acquire().await.unwrap();
+        let second = pool.acquire().await.unwrap();
+        assert_eq!(pool.acquire().await.err(), Some(Busy));
+
+        drop(first);
+        let third = pool.acquire().await;
+        assert!(third.is_ok());
+        drop(second);
+    }
+
+    #[tokio::test]
+    async fn queued_request_gets_released_slot() {
+        let pool = BuildPool::new(1, Duration::from_secs(5));
+        let permit = pool.acquire().await.unwrap();
+
+        let (waiting, _) = tokio::join!(pool.acquire(), async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(permit);
+        });
+        assert!(waiting.is_ok());
+    }
+}
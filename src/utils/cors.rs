@@ -0,0 +1,83 @@
+use vercel_runtime as Vercel;
+
+/// Read the request `Origin` header, if any.
+pub fn request_origin(req: &Vercel::Request) -> Option<String> {
+    req.headers().get("Origin").and_then(|v| v.to_str().ok()).map(String::from)
+}
+
+/// Origins allowed to call the API, read from the comma-separated `ALLOWED_ORIGINS` env var. An empty list means any
+/// origin is allowed.
+pub fn allowed_origins() -> Vec<String> {
+    let raw = std::env::var("ALLOWED_ORIGINS").unwrap_or_default();
+    raw.split(',').map(str::trim).filter(|o| !o.is_empty()).map(String::from).collect()
+}
+
+/// Resolve the `Access-Control-Allow-Origin` value for a request. With no configured origins every caller gets `*`.
+/// Otherwise the request origin is reflected only when it is in the allow list, and `None` means the header must be
+/// omitted so the browser blocks the response.
+pub fn resolve_allow_origin(allowed: &[String], origin: Option<&str>) -> Option<String> {
+    if allowed.is_empty() {
+        return Some("*".to_string());
+    }
+
+    let origin = origin?;
+    allowed.iter().any(|a| a == origin).then(|| origin.to_string())
+}
+
+/// Rewrite the CORS headers of a Vercel response according to `ALLOWED_ORIGINS`. A reflected origin also enables
+/// credentialed requests and marks the response as varying by `Origin` so caches don't serve it cross-origin.
+pub fn apply(
+    origin: Option<String>,
+    response: Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error>,
+) -> Result<Vercel::Response<Vercel::ResponseBody>, Vercel::Error> {
+    let mut response = response?;
+    let allowed = allowed_origins();
+    if allowed.is_empty() {
+        return Ok(response);
+    }
+
+    let headers = response.headers_mut();
+    headers.insert("Vary", "Origin".parse()?);
+    match resolve_allow_origin(&allowed, origin.as_deref()) {
+        Some(value) => {
+            headers.insert("Access-Control-Allow-Origin", value.parse()?);
+            headers.insert("Access-Control-Allow-Credentials", "true".parse()?);
+        }
+        None => {
+            headers.remove("Access-Control-Allow-Origin");
+        }
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origins(list: &[&str]) -> Vec<String> {
+        list.iter().map(|o| o.to_string()).collect()
+    }
+
+    #[test]
+    fn any_origin_when_unconfigured() {
+        assert_eq!(resolve_allow_origin(&[], Some("https://evil.example")), Some("*".to_string()));
+        assert_eq!(resolve_allow_origin(&[], None), Some("*".to_string()));
+    }
+
+    #[test]
+    fn reflects_allowed_origin() {
+        let allowed = origins(&["https://app.sablier.com", "https://staging.sablier.com"]);
+        assert_eq!(
+            resolve_allow_origin(&allowed, Some("https://staging.sablier.com")),
+            Some("https://staging.sablier.com".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_or_missing_origin() {
+        let allowed = origins(&["https://app.sablier.com"]);
+        assert_eq!(resolve_allow_origin(&allowed, Some("https://evil.example")), None);
+        assert_eq!(resolve_allow_origin(&allowed, None), None);
+    }
+}
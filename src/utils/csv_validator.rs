@@ -1,9 +1,9 @@
 use csv::StringRecord;
-use ethers_rs::Address;
-use regex::Regex;
+use ethers_rs::{Address, Eip55};
 use serde::Serialize;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
+use utoipa::ToSchema;
 
 /// Enum to represent different blockchain address types
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -12,8 +12,58 @@ pub enum AddressType {
     Solana,
 }
 
+impl AddressType {
+    /// Maximum number of base-unit digits an amount can have so it always fits the chain's leaf amount type: `u128`
+    /// for Ethereum campaigns and `u64` for Solana ones.
+    pub fn max_amount_digits(&self) -> usize {
+        match self {
+            AddressType::Ethereum => 38,
+            AddressType::Solana => 19,
+        }
+    }
+
+    /// Largest token decimals accepted for a campaign: 18 for Ethereum tokens and 9 for Solana ones
+    pub fn max_decimals(&self) -> usize {
+        match self {
+            AddressType::Ethereum => 18,
+            AddressType::Solana => 9,
+        }
+    }
+
+    /// Key under which addresses are compared, when checking for duplicates or looking up a recipient. For Ethereum
+    /// addresses neither the case, which only carries the EIP-55 checksum, nor the `0x` prefix matter. Solana
+    /// addresses are base58, which is case-sensitive, so they compare by their decoded 32-byte key; an address that
+    /// doesn't decode is kept as is.
+    pub fn address_key(&self, address: &str) -> String {
+        match self {
+            AddressType::Ethereum => address.trim_start_matches("0x").to_lowercase(),
+            AddressType::Solana => {
+                Pubkey::from_str(address).map_or_else(|_| address.to_string(), |key| key.to_string())
+            }
+        }
+    }
+
+    /// On-chain form of an address, to show it back to users: EIP-55 checksummed for Ethereum and the base58 of the
+    /// decoded key for Solana. An address that doesn't parse is kept as is.
+    pub fn canonical_address(&self, address: &str) -> String {
+        match self {
+            AddressType::Ethereum => canonical_eth_address(address).unwrap_or_else(|| address.to_string()),
+            AddressType::Solana => self.address_key(address),
+        }
+    }
+
+    /// Check that `decimals` is within the range supported by the chain
+    pub fn check_decimals(&self, decimals: usize) -> Result<(), String> {
+        let max_decimals = self.max_decimals();
+        if decimals > max_decimals {
+            return Err(format!("decimals must be between 0 and {max_decimals}"));
+        }
+        Ok(())
+    }
+}
+
 /// Struct that encapsulates a validation error. It contains the row where the error occurred and the error message.
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone, ToSchema)]
 pub struct ValidationError {
     pub row: usize,
     pub message: String,
@@ -35,7 +85,29 @@ pub struct ValidationError {
 /// assert!(!invalid_response);
 /// ```
 pub fn is_valid_eth_address(address: &str) -> bool {
-    Address::try_from(address).is_ok()
+    canonical_eth_address(address).is_some()
+}
+
+/// The EIP-55 checksummed, `0x`-prefixed form of an Ethereum address given with or without its `0x` prefix, in any
+/// case. Returns `None` when it is not an address. Like `Address::try_from`, it tolerates a repeated prefix.
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::utils::csv_validator::canonical_eth_address;
+///
+/// let canonical = Some("0xf31b00e025584486f7c37Cf0AE0073c97c12c634".to_string());
+/// assert_eq!(canonical_eth_address("0xF31B00E025584486F7C37cf0ae0073c97c12c634"), canonical);
+/// assert_eq!(canonical_eth_address("f31b00e025584486f7c37cf0ae0073c97c12c634"), canonical);
+/// assert_eq!(canonical_eth_address("0xf31b00e025584486f7c37cf0ae0073c97c12c6"), None);
+/// ```
+pub fn canonical_eth_address(address: &str) -> Option<String> {
+    let hex = address.trim_start_matches("0x");
+    if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let address = Address::try_from(format!("0x{hex}").as_str()).ok()?;
+    Some(address.to_checksum_string())
 }
 
 /// Checks if a string is a valid Solana address.
@@ -75,6 +147,16 @@ impl AddressColumnValidator {
     pub fn new(address_type: AddressType) -> Self {
         Self { address_type }
     }
+
+    /// Creates a new AddressColumnValidator for Ethereum addresses
+    pub fn ethereum() -> Self {
+        Self::new(AddressType::Ethereum)
+    }
+
+    /// Creates a new AddressColumnValidator for Solana addresses
+    pub fn solana() -> Self {
+        Self::new(AddressType::Solana)
+    }
 }
 
 impl ColumnValidator for AddressColumnValidator {
@@ -147,10 +229,10 @@ impl ColumnValidator for AddressColumnValidator {
     }
 }
 
-/// Validator for a csv column that should contain valid amount. The format of the amount is determined through the
-/// regex var.
+/// Validator for a csv column that should contain valid amount: a positive number in plain decimal notation with at
+/// most `decimals` fractional digits.
 pub struct AmountColumnValidator {
-    pub regex: Regex,
+    pub decimals: usize,
 }
 
 impl ColumnValidator for AmountColumnValidator {
@@ -160,10 +242,8 @@ impl ColumnValidator for AmountColumnValidator {
     ///
     /// ```
     /// use sablier_merkle_api::utils::csv_validator::{AmountColumnValidator, ColumnValidator};
-    /// use regex::Regex;
     ///
-    /// let amount_regex = Regex::new(r"^[+]?\d*\.?\d{0,3}$").unwrap();
-    /// let amount_validator = AmountColumnValidator { regex: amount_regex };
+    /// let amount_validator = AmountColumnValidator { decimals: 3 };
     /// let valid_amount = "22.0";
     /// let alphanumeric_amount = "thisIsNotAnAmount";
     /// let zero_amount = "0";
@@ -180,18 +260,22 @@ impl ColumnValidator for AmountColumnValidator {
     /// assert!(!result_negative.is_none());
     /// ```
     fn validate_cel(&self, cel: &str, row_index: usize) -> Option<ValidationError> {
-        let is_valid = self.regex.is_match(cel);
-        if !is_valid {
-            return Some(ValidationError {
-                row: row_index + 2,
-                message: String::from("Amounts should be positive, in normal notation, with an optional decimal point and a maximum number of decimals as provided by the query parameter."),
-            });
-        }
-
-        let amount: f64 = cel.parse().unwrap();
+        let error = |message: String| Some(ValidationError { row: row_index + 2, message });
 
-        if amount == 0.0 {
-            return Some(ValidationError { row: row_index + 2, message: String::from("The amount cannot be 0") });
+        let unsigned = cel.strip_prefix(['+', '-']).unwrap_or(cel);
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let digits = || integer.bytes().chain(fraction.bytes());
+        if integer.len() + fraction.len() == 0 || !digits().all(|b| b.is_ascii_digit()) {
+            return error(String::from("Amount is not a valid number"));
+        }
+        if cel.starts_with('-') {
+            return error(String::from("Amount must be positive"));
+        }
+        if fraction.len() > self.decimals {
+            return error(format!("Amount has more than {} decimal places", self.decimals));
+        }
+        if digits().all(|b| b == b'0') {
+            return error(String::from("The amount cannot be 0"));
         }
         None
     }
@@ -202,10 +286,8 @@ impl ColumnValidator for AmountColumnValidator {
     ///
     /// ```
     /// use sablier_merkle_api::utils::csv_validator::{AmountColumnValidator, ColumnValidator};
-    /// use regex::Regex;
     ///
-    /// let amount_regex = Regex::new(r"^[+]?\d*\.?\d{0,3}$").unwrap();
-    /// let amount_validator = AmountColumnValidator { regex: amount_regex };
+    /// let amount_validator = AmountColumnValidator { decimals: 3 };
     /// let result_valid = amount_validator.validate_header("amount");
     /// let result_invalid = amount_validator.validate_header("address");
     ///
@@ -225,20 +307,19 @@ impl ColumnValidator for AmountColumnValidator {
     }
 }
 
-/// Validates a full CSV row based on an array of objects that implement the ColumnValidator trait.
+/// Validates a full CSV row based on an array of objects that implement the ColumnValidator trait. Empty columns after
+/// the validated ones, e.g. from a trailing comma, are ignored, but a row holding more data than that is rejected.
 ///
 ///  # Examples
 /// ```
 /// 
 /// use sablier_merkle_api::utils::csv_validator::{AddressColumnValidator ,AmountColumnValidator, ColumnValidator,validate_csv_row};
-/// use regex::Regex;
 /// use csv::StringRecord;
 ///
 /// const VALID_ETH_ADDRESS: &str = "0xf31b00e025584486f7c37Cf0AE0073c97c12c634";
 /// const INVALID_ETH_ADDRESS: &str = "0xthisIsNotAnAddress";
 /// let eth_address_validator = AddressColumnValidator::ethereum();
-/// let amount_regex = Regex::new(r"^[+]?\d*\.?\d{0,3}$").unwrap();
-/// let amount_validator = AmountColumnValidator { regex: amount_regex };
+/// let amount_validator = AmountColumnValidator { decimals: 3 };
 /// let validators: Vec<&dyn ColumnValidator> = vec![&eth_address_validator, &amount_validator];
 /// let valid_row = StringRecord::from(vec![VALID_ETH_ADDRESS, "489.312"]);
 /// assert!(validate_csv_row(&valid_row, 0, &validators).is_empty());
@@ -273,6 +354,10 @@ pub fn validate_csv_row(
         });
         return errors;
     }
+    if row.iter().skip(validators.len()).any(|cel| !cel.trim().is_empty()) {
+        errors.push(ValidationError { row: row_index + 2, message: String::from("Too many columns") });
+        return errors;
+    }
     for (index, validator) in validators.iter().enumerate() {
         let cel = row[index].trim();
         let cel_error = validator.validate_cel(cel, row_index);
@@ -289,12 +374,10 @@ pub fn validate_csv_row(
 /// ```
 /// 
 /// use sablier_merkle_api::utils::csv_validator::{AddressColumnValidator ,AmountColumnValidator, ColumnValidator,validate_csv_header};
-/// use regex::Regex;
 /// use csv::StringRecord;
 ///
 /// let address_validator = AddressColumnValidator::ethereum();
-/// let amount_regex = Regex::new(r"^[+]?\d*\.?\d{0,3}$").unwrap();
-/// let amount_validator = AmountColumnValidator { regex: amount_regex };
+/// let amount_validator = AmountColumnValidator { decimals: 3 };
 /// let validators: Vec<&dyn ColumnValidator> = vec![&address_validator, &amount_validator];
 /// let valid_header = StringRecord::from(vec!["address", "amount"]);
 /// assert!(validate_csv_header(&valid_header, &validators).is_none());
@@ -318,6 +401,37 @@ pub fn validate_csv_header(header: &StringRecord, validators: &[&dyn ColumnValid
     None
 }
 
+/// Finds the column of each validator by its header name, so the columns can come in any order. A column that is
+/// missing from the header is reported with the same error as `validate_csv_header`.
+///
+///  # Examples
+/// ```
+/// use sablier_merkle_api::utils::csv_validator::{AddressColumnValidator, AmountColumnValidator, ColumnValidator, locate_csv_columns};
+/// use csv::StringRecord;
+///
+/// let address_validator = AddressColumnValidator::ethereum();
+/// let amount_validator = AmountColumnValidator { decimals: 3 };
+/// let validators: Vec<&dyn ColumnValidator> = vec![&address_validator, &amount_validator];
+/// let swapped_header = StringRecord::from(vec![" Amount ", "address"]);
+/// assert_eq!(locate_csv_columns(&swapped_header, &validators).unwrap(), vec![1, 0]);
+/// let missing_amount = StringRecord::from(vec!["address", "value"]);
+/// assert!(locate_csv_columns(&missing_amount, &validators).is_err());
+/// ```
+pub fn locate_csv_columns(
+    header: &StringRecord,
+    validators: &[&dyn ColumnValidator],
+) -> Result<Vec<usize>, ValidationError> {
+    let columns: Option<Vec<usize>> = validators
+        .iter()
+        .map(|validator| header.iter().position(|head| validator.validate_header(head.trim()).is_none()))
+        .collect();
+
+    columns.ok_or_else(|| {
+        validate_csv_header(header, validators)
+            .unwrap_or_else(|| ValidationError { row: 1, message: String::from("CSV header invalid") })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -326,14 +440,12 @@ mod tests {
     const INVALID_ETH_ADDRESS: &str = "0xthisIsNotAnAddress";
     const VALID_SOL_ADDRESS: &str = "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y";
     const INVALID_SOL_ADDRESS: &str = "0xthisIsNotAnAddress";
-    const AMOUNT_PATTERN: &str = r"^[+]?\d*\.?\d{0,3}$";
 
     fn create_validators() -> (AddressColumnValidator, AddressColumnValidator, AmountColumnValidator) {
         let eth_address_validator = AddressColumnValidator::new(AddressType::Ethereum);
         let sol_address_validator = AddressColumnValidator::new(AddressType::Solana);
 
-        let amount_regex = Regex::new(AMOUNT_PATTERN).unwrap();
-        let amount_validator = AmountColumnValidator { regex: amount_regex };
+        let amount_validator = AmountColumnValidator { decimals: 3 };
         (eth_address_validator, sol_address_validator, amount_validator)
     }
 
@@ -359,6 +471,21 @@ mod tests {
         assert!(!is_valid_sol_address(INVALID_SOL_ADDRESS));
     }
 
+    #[test]
+    fn address_key_tests() {
+        let upper = "9JDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y";
+        let lower = "9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y";
+        assert_ne!(AddressType::Solana.address_key(upper), AddressType::Solana.address_key(lower));
+        assert_eq!(
+            AddressType::Ethereum.address_key(VALID_ETH_ADDRESS),
+            AddressType::Ethereum.address_key(VALID_ETH_ADDRESS.trim_start_matches("0x"))
+        );
+        assert_eq!(
+            AddressType::Ethereum.address_key(VALID_ETH_ADDRESS),
+            AddressType::Ethereum.address_key(&VALID_ETH_ADDRESS.to_lowercase())
+        );
+    }
+
     #[test]
     fn eth_address_column_validator_tests() {
         let (eth_address_validator, _, _) = create_validators();
@@ -388,6 +515,20 @@ mod tests {
         assert_validation_header(&amount_validator, "address", false);
     }
 
+    #[test]
+    fn amount_column_validator_messages() {
+        let (_, _, amount_validator) = create_validators();
+        let message = |value: &str| amount_validator.validate_cel(value, 0).map(|error| error.message);
+
+        assert_eq!(message("abc").as_deref(), Some("Amount is not a valid number"));
+        assert_eq!(message("").as_deref(), Some("Amount is not a valid number"));
+        assert_eq!(message("1.2.3").as_deref(), Some("Amount is not a valid number"));
+        assert_eq!(message("-1.5").as_deref(), Some("Amount must be positive"));
+        assert_eq!(message("1.2345").as_deref(), Some("Amount has more than 3 decimal places"));
+        assert_eq!(message("+000").as_deref(), Some("The amount cannot be 0"));
+        assert_eq!(message("+.5"), None);
+    }
+
     #[test]
     fn csv_row_validation() {
         let (eth_address_validator, sol_address_validator, amount_validator) = create_validators();
@@ -400,6 +541,14 @@ mod tests {
         let insufficient_columns: StringRecord = StringRecord::from(vec![VALID_ETH_ADDRESS]);
         assert!(!validate_csv_row(&insufficient_columns, 0, &eth_validators).is_empty());
 
+        let trailing_comma = StringRecord::from(vec![VALID_ETH_ADDRESS, "489.312", "", " "]);
+        assert!(validate_csv_row(&trailing_comma, 0, &eth_validators).is_empty());
+
+        let extra_data = StringRecord::from(vec![VALID_ETH_ADDRESS, "489.312", "", "extra"]);
+        let errors = validate_csv_row(&extra_data, 0, &eth_validators);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Too many columns");
+
         let invalid_address = StringRecord::from(vec!["thisIsNotAnAddress", "12534"]);
         assert!(!validate_csv_row(&invalid_address, 0, &eth_validators).is_empty());
 
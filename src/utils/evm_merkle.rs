@@ -0,0 +1,15 @@
+use crate::csv_campaign_parser::CampaignCsvRecord;
+use merkle_tree_rs::standard::StandardMerkleTree;
+
+/// ABI types of the `(index, address, amount)` leaf of an EVM campaign, as the Sablier Merkle contracts hash it
+const LEAF_TYPES: [&str; 3] = ["uint", "address", "uint256"];
+
+/// Builds the OpenZeppelin `StandardMerkleTree` of a campaign, one `(index, address, amount)` leaf per record. Every
+/// EVM tree is built here, so the campaigns that are created and the ones that are verified cannot disagree on the
+/// leaf encoding.
+pub fn build_tree(records: &[CampaignCsvRecord]) -> StandardMerkleTree {
+    let leaves =
+        records.iter().enumerate().map(|(i, r)| vec![i.to_string(), r.address.clone(), r.amount.to_string()]).collect();
+
+    StandardMerkleTree::of(leaves, &LEAF_TYPES.map(str::to_string))
+}
@@ -0,0 +1,82 @@
+use tracing_subscriber::EnvFilter;
+
+/// Filter used when `RUST_LOG` is not set
+const DEFAULT_FILTER: &str = "info";
+
+/// Characters of an address kept in the logs when addresses are redacted, not counting a `0x` prefix
+const REDACTED_PREFIX_LEN: usize = 4;
+
+/// Install the `tracing` subscriber of a Lambda, which writes one line per event to stdout for the Vercel logs. Events
+/// are filtered by the `RUST_LOG` directives (e.g. `warn` or `sablier_merkle_api=debug`), `info` when it is unset.
+/// Calling it again is a no-op.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let _ = tracing_subscriber::fmt().with_env_filter(filter).with_target(false).try_init();
+}
+
+/// Whether `LOG_REDACT_ADDRESSES` asks for recipient addresses to be truncated in the logs
+fn redact_addresses() -> bool {
+    is_redact_flag(std::env::var("LOG_REDACT_ADDRESSES").ok().as_deref())
+}
+
+/// Whether a `LOG_REDACT_ADDRESSES` value turns redaction on
+fn is_redact_flag(flag: Option<&str>) -> bool {
+    matches!(flag, Some("true" | "1"))
+}
+
+/// `address` as it should appear in a log line: whole, or truncated when `LOG_REDACT_ADDRESSES` is set. Responses
+/// always carry the full address, only logs go through this.
+pub fn address(address: &str) -> String {
+    address_with(address, redact_addresses())
+}
+
+/// `address` with the redaction flag given by the caller
+fn address_with(address: &str, redact_addresses: bool) -> String {
+    if redact_addresses {
+        redact(address)
+    } else {
+        address.to_string()
+    }
+}
+
+/// Keep the first characters of `address`, after its `0x` prefix for EVM addresses, e.g. `0x9ad7…`
+///
+/// # Examples
+///
+/// ```
+/// use sablier_merkle_api::utils::logging::redact;
+///
+/// assert_eq!(redact("0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491"), "0x9ad7…");
+/// assert_eq!(redact("9jDBxhUrFx1AFeQzWr8oVEsyMEM2AC3KE4chQr18tV1Y"), "9jDB…");
+/// ```
+pub fn redact(address: &str) -> String {
+    let (prefix, rest) = match address.strip_prefix("0x") {
+        Some(rest) => ("0x", rest),
+        None => ("", address),
+    };
+    let kept: String = rest.chars().take(REDACTED_PREFIX_LEN).collect();
+    format!("{prefix}{kept}…")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_short_and_multibyte() {
+        // Short or multi-byte input never leaks more than the prefix, nor splits a character
+        assert_eq!(redact("0x12"), "0x12…");
+        assert_eq!(redact("ééééé"), "éééé…");
+    }
+
+    #[test]
+    fn address_follows_flag() {
+        let full = "0x9ad7CAD4F10D0c3f875b8a2fd292590490c9f491";
+
+        assert_eq!(address_with(full, true), "0x9ad7…");
+        assert_eq!(address_with(full, false), full);
+
+        assert!(is_redact_flag(Some("true")) && is_redact_flag(Some("1")));
+        assert!(!is_redact_flag(Some("false")) && !is_redact_flag(Some("")) && !is_redact_flag(None));
+    }
+}
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use std::{collections::HashMap, io::Read};
 use url::form_urlencoded;
 use vercel_runtime as Vercel;
 
@@ -8,3 +9,219 @@ pub fn query_params(req: &Vercel::Request) -> HashMap<String, String> {
     let query = req.uri().query().unwrap_or_default();
     form_urlencoded::parse(query.as_bytes()).into_owned().collect()
 }
+
+/// Read the optional `expires_at` query parameter, the unix time in seconds after which a campaign can no longer be
+/// claimed
+pub fn expires_at(query: &HashMap<String, String>) -> Result<Option<i64>, String> {
+    query
+        .get("expires_at")
+        .map(|expires_at| expires_at.parse().map_err(|_| "expires_at must be a unix timestamp in seconds".to_string()))
+        .transpose()
+}
+
+/// Upload size cap used when the `MAX_UPLOAD_BYTES` env var is unset or invalid
+pub const DEFAULT_MAX_UPLOAD_BYTES: usize = 100_000_000;
+
+/// Largest request body accepted by `multipart_file`, read from the `MAX_UPLOAD_BYTES` env var
+pub fn max_upload_bytes() -> usize {
+    std::env::var("MAX_UPLOAD_BYTES").ok().and_then(|max| max.parse().ok()).unwrap_or(DEFAULT_MAX_UPLOAD_BYTES)
+}
+
+/// Reasons an uploaded file cannot be read out of a request
+#[derive(Debug, PartialEq)]
+pub enum UploadError {
+    /// The body is larger than the configured limit, in bytes
+    TooLarge(usize),
+    /// Any other problem, as a message that can be returned to the caller as is
+    Invalid(String),
+}
+
+impl std::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooLarge(limit) => write!(f, "File exceeds {limit} bytes"),
+            Self::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Maximum number of multipart parts inspected while looking for the uploaded file
+pub const MAX_MULTIPART_PARTS: usize = 16;
+
+/// Content types browsers and spreadsheet tools declare for CSV files. A part without a declared type is accepted too.
+const CSV_CONTENT_TYPES: [&str; 3] = ["text/csv", "application/vnd.ms-excel", "text/plain"];
+
+/// Error for an upload that cannot be a CSV file, e.g. an XLSX workbook
+const NOT_UTF8_CSV: &str = "Uploaded file is not a UTF-8 CSV";
+
+/// Check that an uploaded file is UTF-8 text before it is parsed as CSV. The error gives the byte offset of the first
+/// invalid sequence, since the CSV reader would otherwise turn it into a replacement character.
+pub fn check_csv(buffer: &[u8]) -> Result<(), String> {
+    std::str::from_utf8(buffer)
+        .map(|_| ())
+        .map_err(|error| format!("{NOT_UTF8_CSV}: invalid byte sequence at offset {}", error.valid_up_to()))
+}
+
+/// Read the uploaded file of a `multipart/form-data` request body, see `multipart_file_from_body` and `body`
+pub async fn multipart_file<B>(req: http::Request<B>) -> Result<Vec<u8>, UploadError>
+where
+    B: http_body::Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let Some(boundary) = req
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("multipart/form-data; boundary="))
+        .map(String::from)
+    else {
+        return Err(UploadError::Invalid("Invalid content type header".to_string()));
+    };
+
+    let body = body(req).await?;
+    multipart_file_from_body(&body, &boundary).map_err(UploadError::Invalid)
+}
+
+/// Read a request body up to `max_upload_bytes`, so an oversized upload is rejected without being buffered whole
+pub async fn body<B>(req: http::Request<B>) -> Result<Vec<u8>, UploadError>
+where
+    B: http_body::Body,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let limit = max_upload_bytes();
+    match Limited::new(req.into_body(), limit).collect().await {
+        Ok(collected) => Ok(collected.to_bytes().to_vec()),
+        Err(error) if error.is::<LengthLimitError>() => Err(UploadError::TooLarge(limit)),
+        Err(error) => Err(UploadError::Invalid(format!("Could not read body data {error}"))),
+    }
+}
+
+/// Pick the uploaded file out of a multipart body. A part named `data` wins, then one named `file`, then the first
+/// part. Bodies with more than `MAX_MULTIPART_PARTS` parts are rejected, and so is a picked part declaring a content
+/// type that is not CSV.
+pub fn multipart_file_from_body(body: &[u8], boundary: &str) -> Result<Vec<u8>, String> {
+    let mut data = multipart::server::Multipart::with_body(body, boundary);
+    let mut file = None;
+    let mut first = None;
+
+    for part in 0.. {
+        let Some(mut entry) = data.read_entry().map_err(|error| error.to_string())? else {
+            break;
+        };
+        if part >= MAX_MULTIPART_PARTS {
+            return Err(format!("Invalid form data, more than {MAX_MULTIPART_PARTS} parts"));
+        }
+
+        let mut buffer: Vec<u8> = vec![];
+        if let Err(error) = entry.data.read_to_end(&mut buffer) {
+            return Err(format!("Could not read body data {error}"));
+        }
+        let is_csv =
+            entry.headers.content_type.as_ref().is_none_or(|mime| CSV_CONTENT_TYPES.contains(&mime.essence_str()));
+        let upload = if is_csv { Ok(buffer) } else { Err(NOT_UTF8_CSV.to_string()) };
+
+        match &*entry.headers.name {
+            "data" => return upload,
+            "file" if file.is_none() => file = Some(upload),
+            _ if first.is_none() => first = Some(upload),
+            _ => {}
+        }
+    }
+
+    file.or(first).unwrap_or_else(|| Err("Invalid form data, missing file".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::async_test::SERVER;
+    use bytes::Bytes;
+    use http_body_util::Full;
+
+    const BOUNDARY: &str = "boundary";
+
+    fn body(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut body = String::new();
+        for (name, content) in parts {
+            body.push_str(&format!(
+                "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"; filename=\"{name}.csv\"\r\n\r\n{content}\r\n"
+            ));
+        }
+        body.push_str(&format!("--{BOUNDARY}--\r\n"));
+        body.into_bytes()
+    }
+
+    #[test]
+    fn multipart_file_accepts_data_or_file_part() {
+        assert_eq!(multipart_file_from_body(&body(&[("data", "a")]), BOUNDARY), Ok(b"a".to_vec()));
+        assert_eq!(multipart_file_from_body(&body(&[("file", "b")]), BOUNDARY), Ok(b"b".to_vec()));
+        assert_eq!(multipart_file_from_body(&body(&[("upload", "c")]), BOUNDARY), Ok(b"c".to_vec()));
+    }
+
+    #[test]
+    fn multipart_file_prefers_data_part() {
+        let parts = [("other", "x"), ("file", "b"), ("data", "a")];
+        assert_eq!(multipart_file_from_body(&body(&parts), BOUNDARY), Ok(b"a".to_vec()));
+
+        let parts = [("other", "x"), ("file", "b")];
+        assert_eq!(multipart_file_from_body(&body(&parts), BOUNDARY), Ok(b"b".to_vec()));
+    }
+
+    #[test]
+    fn multipart_file_checks_content_type() {
+        let part = |content_type: &str| {
+            format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"data\"; filename=\"data\"\r\nContent-Type: {content_type}\r\n\r\na\r\n--{BOUNDARY}--\r\n")
+        };
+
+        assert_eq!(multipart_file_from_body(part("text/csv").as_bytes(), BOUNDARY), Ok(b"a".to_vec()));
+        assert_eq!(multipart_file_from_body(part("text/plain; charset=utf-8").as_bytes(), BOUNDARY), Ok(b"a".to_vec()));
+        assert_eq!(multipart_file_from_body(part("application/vnd.ms-excel").as_bytes(), BOUNDARY), Ok(b"a".to_vec()));
+
+        let xlsx = part("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet");
+        assert_eq!(multipart_file_from_body(xlsx.as_bytes(), BOUNDARY), Err(NOT_UTF8_CSV.to_string()));
+    }
+
+    #[test]
+    fn csv_upload_must_be_utf8() {
+        assert_eq!(check_csv(b"address,amount\n"), Ok(()));
+        assert_eq!(
+            check_csv(b"PK\x03\x04\x14\x00\x06\x00\x08\x00\x00\x00!\x00\xb2").unwrap_err(),
+            "Uploaded file is not a UTF-8 CSV: invalid byte sequence at offset 14"
+        );
+        assert_eq!(
+            check_csv(b"address,amount,name\n0x0,1,caf\xe9\n").unwrap_err(),
+            "Uploaded file is not a UTF-8 CSV: invalid byte sequence at offset 29"
+        );
+    }
+
+    #[tokio::test]
+    async fn multipart_file_rejects_oversized_body() {
+        let server = SERVER.lock().await;
+        std::env::set_var("MAX_UPLOAD_BYTES", "256");
+
+        let request = |parts: &[(&str, &str)]| {
+            http::Request::builder()
+                .header("content-type", format!("multipart/form-data; boundary={BOUNDARY}"))
+                .body(Full::new(Bytes::from(body(parts))))
+                .unwrap()
+        };
+        let small = multipart_file(request(&[("data", "a")])).await;
+        let large = multipart_file(request(&[("data", &"a".repeat(256))])).await;
+
+        std::env::remove_var("MAX_UPLOAD_BYTES");
+        assert_eq!(small, Ok(b"a".to_vec()));
+        assert_eq!(large, Err(UploadError::TooLarge(256)));
+        assert_eq!(large.unwrap_err().to_string(), "File exceeds 256 bytes");
+        drop(server);
+    }
+
+    #[test]
+    fn multipart_file_rejects_too_many_parts() {
+        let mut parts = vec![("junk", ""); MAX_MULTIPART_PARTS];
+        parts.push(("data", "a"));
+
+        let result = multipart_file_from_body(&body(&parts), BOUNDARY);
+        assert_eq!(result, Err(format!("Invalid form data, more than {MAX_MULTIPART_PARTS} parts")));
+        assert!(multipart_file_from_body(&body(&parts[1..]), BOUNDARY).is_ok());
+    }
+}
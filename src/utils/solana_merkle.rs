@@ -1,7 +1,11 @@
 use hex;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json;
+use sha2::Sha256;
 use sha3::{Digest, Keccak256};
+use std::cmp::Ordering;
+use utoipa::ToSchema;
 
 pub fn keccak(data: &[&[u8]]) -> [u8; 32] {
     let mut hasher = Keccak256::new();
@@ -11,6 +15,73 @@ pub fn keccak(data: &[&[u8]]) -> [u8; 32] {
     hasher.finalize().into()
 }
 
+/// Hash function used for the leaves and the inner nodes of a tree. Keccak256 matches the Sablier Solana programs;
+/// SHA-256 is available for verifiers that don't support it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Keccak256,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Hash the concatenation of `data`
+    pub fn hash(&self, data: &[&[u8]]) -> [u8; 32] {
+        match self {
+            HashAlgorithm::Keccak256 => keccak(data),
+            HashAlgorithm::Sha256 => {
+                let mut hasher = Sha256::new();
+                for item in data {
+                    hasher.update(item);
+                }
+                hasher.finalize().into()
+            }
+        }
+    }
+
+    /// Hash a pair of sibling nodes, the smaller one first according to `compare_nodes`. Building and verifying a tree
+    /// both go through it, so they always agree on the order of a pair.
+    pub fn hash_pair(&self, a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        match compare_nodes(a, b) {
+            Ordering::Greater => self.hash(&[b, a]),
+            Ordering::Less | Ordering::Equal => self.hash(&[a, b]),
+        }
+    }
+}
+
+/// Order of two node hashes: byte by byte from the first byte, i.e. as big-endian 256-bit integers. Equal hashes give
+/// the same parent in either order.
+pub fn compare_nodes(a: &[u8; 32], b: &[u8; 32]) -> Ordering {
+    a.iter().zip(b).map(|(a, b)| a.cmp(b)).find(|ordering| ordering.is_ne()).unwrap_or(Ordering::Equal)
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = String;
+
+    /// Parse `keccak256` or `sha256`, case-insensitively
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sablier_merkle_api::utils::solana_merkle::HashAlgorithm;
+    ///
+    /// assert_eq!("SHA256".parse::<HashAlgorithm>(), Ok(HashAlgorithm::Sha256));
+    /// assert!("md5".parse::<HashAlgorithm>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "keccak256" => Ok(HashAlgorithm::Keccak256),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            other => Err(format!("Unknown hash algorithm `{other}`")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MerkleLeaf {
     pub index: u32,
@@ -28,33 +99,143 @@ impl MerkleLeaf {
         pubkey.copy_from_slice(&decoded);
         Ok(pubkey)
     }
+
+    /// Hash the leaf with `algorithm`, concatenating its fields in the given order.
+    pub fn hash(&self, leaf_order: LeafOrder, algorithm: HashAlgorithm) -> [u8; 32] {
+        self.hash_with_domain(leaf_order, algorithm, &[])
+    }
+
+    /// Same as `hash`, with the `domain` tag prepended to the fields before the first hash. An empty domain gives the
+    /// hash of `hash`.
+    pub fn hash_with_domain(&self, leaf_order: LeafOrder, algorithm: HashAlgorithm, domain: &[u8]) -> [u8; 32] {
+        let index_bytes = self.index.to_le_bytes();
+        let recipient_pubkey = self.parse_pubkey().expect("Invalid Solana address");
+        let amount_bytes = self.amount.to_le_bytes();
+        let leaf_bytes: Vec<&[u8]> = std::iter::once(domain)
+            .chain(leaf_order.0.iter().map(|field| match field {
+                LeafField::Index => &index_bytes[..],
+                LeafField::Recipient => &recipient_pubkey[..],
+                LeafField::Amount => &amount_bytes[..],
+            }))
+            .collect();
+        let leaf_hash = algorithm.hash(&leaf_bytes);
+        // Hash one more time to protect against the second pre-image attacks
+        algorithm.hash(&[&leaf_hash])
+    }
+}
+
+/// A field of a Merkle leaf
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LeafField {
+    Index,
+    Recipient,
+    Amount,
+}
+
+/// Order in which the leaf fields are concatenated before hashing. Different claim contracts expect different
+/// layouts, so the order is stored with the tree and defaults to `index || recipient || amount`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct LeafOrder(pub [LeafField; 3]);
+
+impl Default for LeafOrder {
+    fn default() -> Self {
+        LeafOrder([LeafField::Index, LeafField::Recipient, LeafField::Amount])
+    }
+}
+
+impl LeafOrder {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl std::str::FromStr for LeafOrder {
+    type Err = String;
+
+    /// Parse a comma-separated field list such as `amount,index,recipient`. Each field must appear exactly once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sablier_merkle_api::utils::solana_merkle::{LeafField, LeafOrder};
+    ///
+    /// let order: LeafOrder = "amount,index,recipient".parse().unwrap();
+    /// assert_eq!(order, LeafOrder([LeafField::Amount, LeafField::Index, LeafField::Recipient]));
+    /// assert!("index,index,amount".parse::<LeafOrder>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s
+            .split(',')
+            .map(|f| match f.trim().to_lowercase().as_str() {
+                "index" => Ok(LeafField::Index),
+                "recipient" | "address" => Ok(LeafField::Recipient),
+                "amount" => Ok(LeafField::Amount),
+                other => Err(format!("Unknown leaf field `{other}`")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let [a, b, c] = fields[..] else {
+            return Err("Leaf order must list exactly 3 fields".to_string());
+        };
+        if a == b || a == c || b == c {
+            return Err("Leaf order must list each of index, recipient and amount once".to_string());
+        }
+
+        Ok(LeafOrder([a, b, c]))
+    }
+}
+
+/// How the leaves of a Solana tree are hashed: the `leaf_order`, `hash_algorithm` and `domain` query parameters of the
+/// Solana create endpoints, stored in the campaign params so the tree can be rebuilt
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeOptions {
+    pub leaf_order: LeafOrder,
+    pub hash_algorithm: HashAlgorithm,
+    /// Domain separation tag prepended to every leaf preimage, if any
+    pub domain: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MerkleTree {
     pub root: String,
     pub tree: Vec<Vec<String>>,
+    #[serde(default, skip_serializing_if = "LeafOrder::is_default")]
+    pub leaf_order: LeafOrder,
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_default")]
+    pub hash_algorithm: HashAlgorithm,
+    /// Hex-encoded domain separation tag prepended to every leaf preimage, so proofs of one campaign cannot be
+    /// replayed against a tree built for another domain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
 }
 
 impl MerkleTree {
-    pub fn build_tree(leaves: Vec<MerkleLeaf>) -> Self {
+    /// Build the tree bottom up. Each pair of sibling hashes is sorted with `compare_nodes` before it is hashed, so
+    /// proofs don't carry left/right flags, and the last node of an odd level is hashed with itself. The pairing is
+    /// meant to match the Sablier Solana claim program, but no root from the program's own test suite checks it here.
+    pub fn build_tree(leaves: Vec<MerkleLeaf>, leaf_order: LeafOrder, hash_algorithm: HashAlgorithm) -> Self {
+        Self::build_tree_with_domain(leaves, leaf_order, hash_algorithm, None)
+    }
+
+    /// Same as `build_tree`, with the leaves hashed under the `domain` tag, which is stored in the tree for
+    /// `verify_proof`. Without a domain the root is the one of `build_tree`.
+    pub fn build_tree_with_domain(
+        leaves: Vec<MerkleLeaf>,
+        leaf_order: LeafOrder,
+        hash_algorithm: HashAlgorithm,
+        domain: Option<&[u8]>,
+    ) -> Self {
         if leaves.is_empty() {
             panic!("Cannot build merkle tree with empty leaves");
         }
 
+        // Leaf hashes are independent and dominate the build time of large campaigns. `collect` on an indexed
+        // parallel iterator keeps the leaf order.
+        let prefix = domain.unwrap_or_default();
         let mut leaf_hashes: Vec<String> = leaves
-            .iter()
-            .map(|leaf| {
-                let index_bytes = leaf.index.to_le_bytes();
-                let recipient_pubkey = leaf.parse_pubkey().expect("Invalid Solana address");
-                let amount_bytes = leaf.amount.to_le_bytes();
-                let leaf_bytes: &[&[u8]] = &[&index_bytes, &recipient_pubkey, &amount_bytes];
-                let mut leaf_hash = keccak(leaf_bytes);
-                // Hash one more time to protect against the second pre-image attacks
-                leaf_hash = keccak(&[&leaf_hash]);
-
-                hex::encode(leaf_hash)
-            })
+            .par_iter()
+            .map(|leaf| hex::encode(leaf.hash_with_domain(leaf_order, hash_algorithm, prefix)))
             .collect();
 
         let mut tree = vec![leaf_hashes.clone()];
@@ -72,17 +253,13 @@ impl MerkleTree {
                     hash1_array.copy_from_slice(&hash1);
                     hash2_array.copy_from_slice(&hash2);
 
-                    if hash1_array <= hash2_array {
-                        hex::encode(keccak(&[&hash1_array, &hash2_array]))
-                    } else {
-                        hex::encode(keccak(&[&hash2_array, &hash1_array]))
-                    }
+                    hex::encode(hash_algorithm.hash_pair(&hash1_array, &hash2_array))
                 } else {
                     // Odd number - hash the last element with itself
                     let hash1 = hex::decode(&chunk[0]).expect("Invalid hex");
                     let mut hash1_array = [0u8; 32];
                     hash1_array.copy_from_slice(&hash1);
-                    hex::encode(keccak(&[&hash1_array, &hash1_array]))
+                    hex::encode(hash_algorithm.hash_pair(&hash1_array, &hash1_array))
                 };
                 next_level.push(hash);
             }
@@ -93,14 +270,57 @@ impl MerkleTree {
 
         let root = leaf_hashes[0].clone();
 
-        MerkleTree { root, tree }
+        MerkleTree { root, tree, leaf_order, hash_algorithm, domain: domain.map(hex::encode) }
+    }
+
+    /// Build the tree of leaves that did not get their indices from their position, e.g. hand-written input. The
+    /// indices must be exactly `0..leaves.len()` in order, since `get_proof` finds a leaf by its index.
+    pub fn try_build_tree(
+        leaves: Vec<MerkleLeaf>,
+        leaf_order: LeafOrder,
+        hash_algorithm: HashAlgorithm,
+    ) -> Result<Self, String> {
+        if leaves.is_empty() {
+            return Err("Cannot build merkle tree with empty leaves".to_string());
+        }
+        Self::check_indices(&leaves)?;
+        Ok(Self::build_tree(leaves, leaf_order, hash_algorithm))
+    }
+
+    /// Check that the leaf at each position has that position as index, so there are no gaps or duplicates
+    pub fn check_indices(leaves: &[MerkleLeaf]) -> Result<(), String> {
+        match leaves.iter().enumerate().find(|(position, leaf)| leaf.index as usize != *position) {
+            Some((position, leaf)) => Err(format!("Leaf {position} has index {}, expected {position}", leaf.index)),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of elements in the proof of the leaf at `index`, computed from the leaf count alone, or 0 for an index
+    /// outside the tree. A node without a sibling is paired with itself and its duplicate is part of the proof, so
+    /// every leaf has one element per level above the leaves.
+    pub fn expected_proof_len(&self, index: u32) -> usize {
+        let mut nodes = self.leaf_count();
+        if index as usize >= nodes {
+            return 0;
+        }
+
+        let mut len = 0;
+        while nodes > 1 {
+            nodes = nodes.div_ceil(2);
+            len += 1;
+        }
+        len
+    }
+
+    /// Number of leaves of the tree, the size of its first level
+    pub fn leaf_count(&self) -> usize {
+        self.tree.first().map_or(0, Vec::len)
     }
 
     pub fn get_proof(&self, index: u32) -> Option<Vec<String>> {
         let index = index as usize;
 
-        let num_leaves = if self.tree.is_empty() { 0 } else { self.tree[0].len() };
-        if index >= num_leaves {
+        if index >= self.leaf_count() {
             return None;
         }
 
@@ -124,6 +344,27 @@ impl MerkleTree {
         Some(proof)
     }
 
+    /// Verify a proof, as returned by `get_proof`, for the given leaf against the tree root. The leaf is hashed
+    /// with the field order, hash algorithm and domain the tree was built with.
+    pub fn verify_proof(&self, leaf: &MerkleLeaf, proof: &[String]) -> bool {
+        let Ok(domain) = hex::decode(self.domain.as_deref().unwrap_or_default()) else {
+            return false;
+        };
+        let mut computed_hash = leaf.hash_with_domain(self.leaf_order, self.hash_algorithm, &domain);
+        for proof_element_hex in proof {
+            let Ok(bytes) = hex::decode(proof_element_hex.trim_start_matches("0x")) else {
+                return false;
+            };
+            let Ok(proof_element) = <[u8; 32]>::try_from(bytes.as_slice()) else {
+                return false;
+            };
+
+            computed_hash = self.hash_algorithm.hash_pair(&computed_hash, &proof_element);
+        }
+
+        hex::encode(computed_hash) == self.root
+    }
+
     pub fn root_hex(&self) -> String {
         format!("0x{}", self.root)
     }
@@ -132,8 +373,68 @@ impl MerkleTree {
         serde_json::to_string(self)
     }
 
-    pub fn load(data: &str) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(data)
+    /// Parse a tree produced by `dump` and check its structure, so a truncated or tampered tree is rejected instead of
+    /// making `get_proof` index out of bounds
+    pub fn load(data: &str) -> Result<Self, LoadError> {
+        let tree: Self = serde_json::from_str(data)?;
+        tree.check_structure().map_err(LoadError::Invalid)?;
+        Ok(tree)
+    }
+
+    /// Every level holds 64-char hex hashes and pairs up into the next one, down to a single root that matches `root`
+    fn check_structure(&self) -> Result<(), String> {
+        let Some(leaves) = self.tree.first() else {
+            return Err("the tree has no levels".to_string());
+        };
+        if leaves.is_empty() {
+            return Err("the tree has no leaves".to_string());
+        }
+
+        for (depth, pair) in self.tree.windows(2).enumerate() {
+            let expected = pair[0].len().div_ceil(2);
+            if pair[1].len() != expected {
+                return Err(format!("level {} has {} nodes, expected {expected}", depth + 1, pair[1].len()));
+            }
+        }
+
+        if self.domain.as_deref().is_some_and(|domain| hex::decode(domain).is_err()) {
+            return Err("the domain is not hex".to_string());
+        }
+
+        let is_hash = |node: &String| node.len() == 64 && node.bytes().all(|b| b.is_ascii_hexdigit());
+        if let Some(depth) = self.tree.iter().position(|level| !level.iter().all(is_hash)) {
+            return Err(format!("level {depth} holds a node that is not a 32-byte hex hash"));
+        }
+
+        match self.tree.last().map(Vec::as_slice) {
+            Some([root]) if *root == self.root => Ok(()),
+            Some([_]) => Err("the root does not match the top of the tree".to_string()),
+            _ => Err("the top level of the tree is not a single root".to_string()),
+        }
+    }
+}
+
+/// Errors surfaced from `MerkleTree::load`
+#[derive(Debug)]
+pub enum LoadError {
+    Deserialize(serde_json::Error),
+    Invalid(String),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Deserialize(e) => write!(f, "merkle tree deserialize error: {e}"),
+            Self::Invalid(reason) => write!(f, "invalid merkle tree: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Deserialize(e)
     }
 }
 
@@ -206,7 +507,7 @@ mod tests {
     #[test]
     fn test_build_tree_with_four_leaves() {
         let leaves = create_test_leaves();
-        let tree = MerkleTree::build_tree(leaves.clone());
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         // println!("root: {:?}", tree.root_hex());
         // println!("proof: {:?}", tree.get_proof(1));
@@ -224,7 +525,7 @@ mod tests {
     fn test_build_tree_with_single_leaf() {
         let leaves =
             vec![MerkleLeaf { index: 0, recipient: "11111111111111111111111111111112".to_string(), amount: 500 }];
-        let tree = MerkleTree::build_tree(leaves.clone());
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         assert_eq!(tree.tree.len(), 1); // only leaf level
         assert_eq!(tree.tree[0].len(), 1);
@@ -236,7 +537,7 @@ mod tests {
         let mut leaves = create_test_leaves();
         leaves.push(MerkleLeaf { index: 4, recipient: "11111111111111111111111111111114".to_string(), amount: 500 });
 
-        let tree = MerkleTree::build_tree(leaves.clone());
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         assert_eq!(tree.tree[0].len(), 5); // leaf level
     }
@@ -245,13 +546,55 @@ mod tests {
     #[should_panic(expected = "Cannot build merkle tree with empty leaves")]
     fn test_build_tree_with_empty_leaves() {
         let leaves = vec![];
-        MerkleTree::build_tree(leaves);
+        MerkleTree::build_tree(leaves, LeafOrder::default(), HashAlgorithm::default());
+    }
+
+    #[test]
+    fn test_build_tree_with_domain() {
+        let leaves = create_test_leaves();
+        let build = |domain: Option<&[u8]>| {
+            MerkleTree::build_tree_with_domain(leaves.clone(), LeafOrder::default(), HashAlgorithm::default(), domain)
+        };
+
+        let plain = build(None);
+        let sablier = build(Some(b"sablier-campaign-1"));
+        let other = build(Some(b"sablier-campaign-2"));
+        assert_eq!(plain, MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default()));
+        assert_ne!(sablier.root, plain.root);
+        assert_ne!(sablier.root, other.root);
+
+        // The domain travels with the tree, and a proof only verifies under the domain it was built with
+        let loaded = MerkleTree::load(&sablier.dump().unwrap()).unwrap();
+        let proof = loaded.get_proof(2).unwrap();
+        assert!(loaded.verify_proof(&leaves[2], &proof));
+        assert!(!MerkleTree { domain: None, ..loaded.clone() }.verify_proof(&leaves[2], &proof));
+        assert!(!MerkleTree { domain: other.domain.clone(), ..loaded.clone() }.verify_proof(&leaves[2], &proof));
+
+        let tampered = MerkleTree { domain: Some("not hex".to_string()), ..loaded };
+        assert!(matches!(MerkleTree::load(&tampered.dump().unwrap()), Err(LoadError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_try_build_tree_checks_indices() {
+        let mut leaves = create_test_leaves();
+        assert!(MerkleTree::try_build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default()).is_ok());
+
+        leaves[2].index = 3;
+        leaves[3].index = 5;
+        let gappy = MerkleTree::try_build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
+        assert_eq!(gappy.unwrap_err(), "Leaf 2 has index 3, expected 2");
+
+        leaves[2].index = 1;
+        assert_eq!(MerkleTree::check_indices(&leaves).unwrap_err(), "Leaf 2 has index 1, expected 2");
+
+        let empty = MerkleTree::try_build_tree(vec![], LeafOrder::default(), HashAlgorithm::default());
+        assert_eq!(empty.unwrap_err(), "Cannot build merkle tree with empty leaves");
     }
 
     #[test]
     fn test_get_proof_valid_indices() {
         let leaves = create_test_leaves();
-        let tree = MerkleTree::build_tree(leaves.clone());
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         // Test proof for each leaf
         for (i, leaf) in leaves.iter().enumerate() {
@@ -268,7 +611,7 @@ mod tests {
     #[test]
     fn test_get_proof_invalid_index() {
         let leaves = create_test_leaves();
-        let tree = MerkleTree::build_tree(leaves.clone());
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         // Test out of bounds index
         let proof = tree.get_proof(10);
@@ -279,7 +622,7 @@ mod tests {
     fn test_get_proof_single_leaf() {
         let leaves =
             vec![MerkleLeaf { index: 0, recipient: "11111111111111111111111111111112".to_string(), amount: 500 }];
-        let tree = MerkleTree::build_tree(leaves.clone());
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         let proof = tree.get_proof(0).unwrap();
         assert_eq!(proof.len(), 0); // No siblings needed for single leaf
@@ -291,7 +634,7 @@ mod tests {
     #[test]
     fn test_merkle_proof_verification() {
         let leaves = create_test_leaves();
-        let tree = MerkleTree::build_tree(leaves.clone());
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         // Test that each leaf can be verified with its proof
         for (i, leaf) in leaves.iter().enumerate() {
@@ -305,7 +648,7 @@ mod tests {
     #[test]
     fn test_merkle_proof_wrong_leaf() {
         let leaves = create_test_leaves();
-        let tree = MerkleTree::build_tree(leaves.clone());
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         // Get proof for index 0
         let proof = tree.get_proof(0).unwrap();
@@ -320,7 +663,7 @@ mod tests {
     #[test]
     fn test_root_hex() {
         let leaves = create_test_leaves();
-        let tree = MerkleTree::build_tree(leaves);
+        let tree = MerkleTree::build_tree(leaves, LeafOrder::default(), HashAlgorithm::default());
 
         let hex_root = tree.root_hex();
         assert_eq!(hex_root.len(), 66); // "0x" + 32 bytes = 66 hex characters
@@ -334,7 +677,7 @@ mod tests {
     #[test]
     fn test_proof_hex_format() {
         let leaves = create_test_leaves();
-        let tree = MerkleTree::build_tree(leaves.clone());
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         for (i, leaf) in leaves.iter().enumerate() {
             let proof = tree.get_proof(i as u32).unwrap();
@@ -359,7 +702,7 @@ mod tests {
     #[test]
     fn test_dump_and_load() {
         let leaves = create_test_leaves();
-        let original_tree = MerkleTree::build_tree(leaves.clone());
+        let original_tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default());
 
         // Dump the tree
         let serialized = original_tree.dump().unwrap();
@@ -383,4 +726,211 @@ mod tests {
             assert!(verify_proof(leaf, &loaded_tree.root, raw_loaded_proof));
         }
     }
+
+    #[test]
+    fn test_leaf_orders_yield_distinct_self_verifying_roots() {
+        use LeafField::{Amount, Index, Recipient};
+        let orders = [
+            [Index, Recipient, Amount],
+            [Index, Amount, Recipient],
+            [Recipient, Index, Amount],
+            [Recipient, Amount, Index],
+            [Amount, Index, Recipient],
+            [Amount, Recipient, Index],
+        ];
+        let leaves = create_test_leaves();
+
+        let mut roots = std::collections::HashSet::new();
+        for order in orders {
+            let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder(order), HashAlgorithm::default());
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = tree.get_proof(i as u32).unwrap();
+                assert!(tree.verify_proof(leaf, &proof), "Failed to verify leaf {i} under order {order:?}");
+            }
+            roots.insert(tree.root);
+        }
+
+        assert_eq!(roots.len(), orders.len());
+    }
+
+    #[test]
+    fn test_leaf_order_round_trips_through_dump() {
+        let leaves = create_test_leaves();
+        let order: LeafOrder = "amount,index,recipient".parse().unwrap();
+        let tree = MerkleTree::build_tree(leaves.clone(), order, HashAlgorithm::default());
+
+        let loaded_tree = MerkleTree::load(&tree.dump().unwrap()).unwrap();
+        assert_eq!(loaded_tree.leaf_order, order);
+
+        // Trees built with the default order keep the original JSON layout
+        let default_tree = MerkleTree::build_tree(leaves, LeafOrder::default(), HashAlgorithm::default());
+        assert!(!default_tree.dump().unwrap().contains("leaf_order"));
+    }
+
+    #[test]
+    fn test_hash_algorithms_yield_distinct_self_verifying_roots() {
+        let leaves = create_test_leaves();
+        let keccak_tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::Keccak256);
+        let sha_tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::Sha256);
+
+        assert_ne!(keccak_tree.root, sha_tree.root);
+        for tree in [&keccak_tree, &sha_tree] {
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = tree.get_proof(i as u32).unwrap();
+                assert!(tree.verify_proof(leaf, &proof), "Failed to verify leaf {i} under {:?}", tree.hash_algorithm);
+            }
+        }
+
+        // A SHA-256 proof doesn't verify against the Keccak256 tree
+        let proof = sha_tree.get_proof(0).unwrap();
+        assert!(!keccak_tree.verify_proof(&leaves[0], &proof));
+    }
+
+    #[test]
+    fn test_hash_algorithm_round_trips_through_dump() {
+        let leaves = create_test_leaves();
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::Sha256);
+
+        let loaded_tree = MerkleTree::load(&tree.dump().unwrap()).unwrap();
+        assert_eq!(loaded_tree.hash_algorithm, HashAlgorithm::Sha256);
+        let proof = loaded_tree.get_proof(2).unwrap();
+        assert!(loaded_tree.verify_proof(&leaves[2], &proof));
+
+        // Trees built with Keccak256 keep the original JSON layout
+        let default_tree = MerkleTree::build_tree(leaves, LeafOrder::default(), HashAlgorithm::Keccak256);
+        assert!(!default_tree.dump().unwrap().contains("hash_algorithm"));
+    }
+
+    #[test]
+    fn test_regression_lock_with_odd_level() {
+        // The root and proof below were produced by `build_tree` itself: they catch a change of the tree layout, they
+        // do not prove compatibility with the claim program
+        let leaves = create_test_leaves()[..3].to_vec();
+        let tree = MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::Keccak256);
+
+        let root = "2e51f08eafe0a06a7cbe0bc37082a52bc89aced98b84e92819911d01100c4932";
+        let proof = vec![
+            "0x158db4f6ff3d0547cef89e6125c2c39052d1b2b288a8db9742958cc1b80fcb43".to_string(),
+            "0xa6a693f5474548569bfd931d4af466a50a5eb0374d895071f647393ff6da241b".to_string(),
+        ];
+        assert_eq!(tree.root, root);
+        assert_eq!(tree.get_proof(2).unwrap(), proof);
+        // The last leaf of the odd level is paired with itself
+        assert_eq!(proof[0], format!("0x{}", tree.tree[0][2]));
+
+        let unprefixed: Vec<String> = proof.iter().map(|p| p.trim_start_matches("0x").to_string()).collect();
+        assert!(verify_proof(&leaves[2], root, unprefixed));
+        for leaf in &leaves {
+            assert!(tree.verify_proof(leaf, &tree.get_proof(leaf.index).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_expected_proof_len() {
+        for (leaf_count, expected) in [(1, 0), (2, 1), (3, 2), (4, 2), (5, 3), (7, 3), (8, 3), (9, 4), (33, 6)] {
+            let leaves: Vec<MerkleLeaf> = (0..leaf_count)
+                .map(|i| MerkleLeaf {
+                    index: i,
+                    recipient: create_test_leaves()[0].recipient.clone(),
+                    amount: 1 + u64::from(i),
+                })
+                .collect();
+            let tree = MerkleTree::build_tree(leaves, LeafOrder::default(), HashAlgorithm::Keccak256);
+
+            for index in 0..leaf_count {
+                assert_eq!(tree.expected_proof_len(index), expected, "{leaf_count} leaves");
+                assert_eq!(tree.get_proof(index).unwrap().len(), expected, "{leaf_count} leaves");
+            }
+            assert_eq!(tree.expected_proof_len(leaf_count), 0);
+        }
+    }
+
+    #[test]
+    fn test_identical_siblings() {
+        let zero = [0u8; 32];
+        assert_eq!(compare_nodes(&zero, &zero), Ordering::Equal);
+        // keccak256 and sha256 of 64 zero bytes
+        assert_eq!(
+            hex::encode(HashAlgorithm::Keccak256.hash_pair(&zero, &zero)),
+            "ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+        );
+        assert_eq!(
+            hex::encode(HashAlgorithm::Sha256.hash_pair(&zero, &zero)),
+            "f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b"
+        );
+
+        let mut low = zero;
+        low[31] = 1;
+        let mut high = zero;
+        high[0] = 1;
+        assert_eq!(compare_nodes(&low, &high), Ordering::Less);
+        assert_eq!(HashAlgorithm::Keccak256.hash_pair(&high, &low), keccak(&[&low, &high]));
+
+        // Two identical leaves hash to identical siblings, whose parent is the root
+        let leaf = create_test_leaves()[0].clone();
+        let tree =
+            MerkleTree::build_tree(vec![leaf.clone(), leaf.clone()], LeafOrder::default(), HashAlgorithm::Keccak256);
+        let leaf_hash = leaf.hash(LeafOrder::default(), HashAlgorithm::Keccak256);
+        assert_eq!(tree.root, hex::encode(keccak(&[&leaf_hash, &leaf_hash])));
+        assert!(tree.verify_proof(&leaf, &tree.get_proof(1).unwrap()));
+    }
+
+    #[test]
+    fn test_parallel_leaf_hashing_matches_sequential() {
+        let leaves: Vec<MerkleLeaf> = (0..5_001u32)
+            .map(|i| {
+                let mut key = [0u8; 32];
+                key[..4].copy_from_slice(&i.to_le_bytes());
+                MerkleLeaf { index: i, recipient: bs58::encode(key).into_string(), amount: u64::from(i) + 1 }
+            })
+            .collect();
+        let build_with_threads = |threads| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| MerkleTree::build_tree(leaves.clone(), LeafOrder::default(), HashAlgorithm::default()))
+        };
+
+        let sequential = build_with_threads(1);
+        let parallel = build_with_threads(4);
+
+        assert_eq!(parallel.root, sequential.root);
+        assert_eq!(parallel.tree, sequential.tree);
+        let expected_leaves: Vec<String> =
+            leaves.iter().map(|l| hex::encode(l.hash(LeafOrder::default(), HashAlgorithm::default()))).collect();
+        assert_eq!(parallel.tree[0], expected_leaves);
+    }
+
+    #[test]
+    fn test_load_rejects_truncated_tree() {
+        let tree = MerkleTree::build_tree(create_test_leaves(), LeafOrder::default(), HashAlgorithm::default());
+
+        let mut truncated = tree.clone();
+        truncated.tree.remove(1);
+        let Err(LoadError::Invalid(reason)) = MerkleTree::load(&truncated.dump().unwrap()) else {
+            panic!("a tree without its middle level must not load");
+        };
+        assert_eq!(reason, "level 1 has 1 nodes, expected 2");
+
+        let mut truncated = tree.clone();
+        truncated.tree.pop();
+        assert!(matches!(MerkleTree::load(&truncated.dump().unwrap()), Err(LoadError::Invalid(_))));
+
+        let truncated_json = &tree.dump().unwrap()[..40];
+        assert!(matches!(MerkleTree::load(truncated_json), Err(LoadError::Deserialize(_))));
+    }
+
+    #[test]
+    fn test_load_rejects_wrong_root() {
+        let tree = MerkleTree::build_tree(create_test_leaves(), LeafOrder::default(), HashAlgorithm::default());
+
+        let mut tampered = tree.clone();
+        tampered.root = "00".repeat(32);
+        let Err(LoadError::Invalid(reason)) = MerkleTree::load(&tampered.dump().unwrap()) else {
+            panic!("a tree with a wrong root must not load");
+        };
+        assert_eq!(reason, "the root does not match the top of the tree");
+
+        let mut tampered = tree.clone();
+        tampered.tree[0][2] = "not a hash".to_string();
+        assert!(matches!(MerkleTree::load(&tampered.dump().unwrap()), Err(LoadError::Invalid(_))));
+    }
 }